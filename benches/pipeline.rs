@@ -0,0 +1,95 @@
+//! Throughput of the hot paths, labelled with the scalar type. Compare the precisions with
+//! `cargo bench` and `cargo bench --features f32`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+use cpu_renderer::framebuffer::Framebuffer;
+use cpu_renderer::matrix::Matrix4;
+use cpu_renderer::renderer::Program;
+use cpu_renderer::scalar::{consts, Scalar};
+use cpu_renderer::simd::{self, Level};
+use cpu_renderer::vector::{Vector3, Vector4};
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+const WIDTH: usize = 350;
+const HEIGHT: usize = 200;
+
+fn scalar() -> &'static str {
+    std::any::type_name::<Scalar>()
+}
+
+fn model(t: Scalar) -> Matrix4 {
+    Matrix4::translation(Vector3::new(0.0, 0.0, 5.0)) * Matrix4::rotation_x(consts::PI / 4.0) * Matrix4::rotation_y(t)
+}
+
+fn vertex(v: Vector3, t: &(Scalar, Vector3)) -> (Vector3, Scalar) {
+    let projection = Matrix4::perspective(consts::PI / 3.0, WIDTH as Scalar / HEIGHT as Scalar, 0.1, 100.0);
+    ((Matrix4::viewport() * projection * model(t.0)).transform_point(v), 1.0)
+}
+
+fn fragment(_: Vector3, _: Scalar, (t, n): &(Scalar, Vector3)) -> Vector3 {
+    let normal = model(*t).transform_direction(*n);
+    Vector3::new(0.4, 0.5, 0.0) * (0.3 + 0.7 * normal.dot(Vector3::new(0.0, 0.0, -1.0)).max(0.0))
+}
+
+fn rasterize(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("rasterize/{}", scalar()));
+    group.throughput(Throughput::Elements((WIDTH * HEIGHT) as u64));
+    let mut buffer = Framebuffer::new(WIDTH, HEIGHT);
+    let mut program = Program::new(vertex, fragment, (0.3, Vector3::zero()), WIDTH, HEIGHT, 30, 30);
+    group.bench_function("roller", |b| {
+        b.iter(|| {
+            buffer.clear(Vector3::zero());
+            program.reset();
+            // large enough to cover most of the screen
+            common::enqueue_roller(&mut program, 200);
+            common::render(&mut program, &mut buffer, 30);
+        })
+    });
+    group.finish();
+}
+
+fn clear(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("clear/{}", scalar()));
+    group.throughput(Throughput::Elements((WIDTH * HEIGHT) as u64));
+    let mut buffer = Framebuffer::new(WIDTH, HEIGHT);
+    group.bench_function("framebuffer", |b| b.iter(|| buffer.clear(black_box(Vector3::zero()))));
+    group.finish();
+}
+
+fn transform(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("transform/{}", scalar()));
+    let points: Vec<Vector3> = (0..4096).map(|i| Vector3::new(i as Scalar, 1.0, -(i as Scalar))).collect();
+    group.throughput(Throughput::Elements(points.len() as u64));
+    let matrix = Matrix4::perspective(consts::PI / 3.0, 1.5, 0.1, 100.0) * model(0.5);
+    group.bench_function("points", |b| {
+        b.iter(|| points.iter().map(|&p| matrix.transform_point(p)).fold(Vector3::zero(), |sum, p| sum + p))
+    });
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("inverse", |b| b.iter(|| black_box(matrix).inverse()));
+    let vector = Vector4::new(1.0, 2.0, 3.0, 1.0);
+    let vectors: Vec<Vector4> = points.iter().map(|p| Vector4::new(p.x, p.y, p.z, 1.0)).collect();
+    let mut out = vec![Vector4::zero(); vectors.len()];
+    for &level in [Level::Scalar, Level::Sse2, Level::Avx2].iter().filter(|&&level| level.supported() == level) {
+        group.throughput(Throughput::Elements(1));
+        group.bench_function(format!("product/{:?}", level), |b| {
+            b.iter(|| simd::mul_matrix4(level, black_box(&matrix), black_box(&matrix)))
+        });
+        group.bench_function(format!("vector/{:?}", level), |b| {
+            b.iter(|| simd::mul_matrix4_vector4(level, black_box(&matrix), black_box(&vector)))
+        });
+        group.throughput(Throughput::Elements(vectors.len() as u64));
+        group.bench_function(format!("vectors/{:?}", level), |b| {
+            b.iter(|| simd::mul_matrix4_vector4s(level, &matrix, black_box(&vectors), &mut out))
+        });
+        group.bench_function(format!("add/{:?}", level), |b| {
+            b.iter(|| simd::add_vector4s(level, black_box(&vectors), black_box(&vectors), &mut out))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, rasterize, clear, transform);
+criterion_main!(benches);
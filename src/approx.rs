@@ -0,0 +1,84 @@
+//! Tolerant comparison of the math types, for tests and for code that has to compare
+//! computed values, e.g. to detect that an animation reached its target.
+
+use crate::scalar::Scalar;
+
+/// How far apart two scalars may be to count as equal. They are when they differ by at
+/// most `epsilon`, or when at most `ulps` representable values lie between them.
+///
+/// The absolute `epsilon` suits values near zero, where floats are dense, and `ulps`
+/// scales with the magnitude of the compared values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tolerance {
+    pub epsilon: Scalar,
+    pub ulps: u32,
+}
+
+impl Tolerance {
+    pub const fn new(epsilon: Scalar, ulps: u32) -> Self {
+        Self { epsilon, ulps }
+    }
+
+    /// Only the absolute difference counts.
+    pub const fn absolute(epsilon: Scalar) -> Self {
+        Self::new(epsilon, 0)
+    }
+
+    /// Only the distance in units in the last place counts.
+    pub const fn ulps(ulps: u32) -> Self {
+        Self::new(0.0, ulps)
+    }
+
+    pub fn with_epsilon(self, epsilon: Scalar) -> Self {
+        Self { epsilon, ..self }
+    }
+
+    pub fn with_ulps(self, ulps: u32) -> Self {
+        Self { ulps, ..self }
+    }
+}
+
+/// A few rounding errors, enough for the result of a handful of operations.
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self::new(4.0 * Scalar::EPSILON, 4)
+    }
+}
+
+/// Equality up to a `Tolerance`, component by component for vectors and matrices.
+pub trait ApproxEq {
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance) -> bool;
+}
+
+/// Position of the value among all floats, ordered like the values, with both zeros at 0.
+#[cfg(not(feature = "f32"))]
+fn ordered_bits(value: Scalar) -> i128 {
+    let bits = value.to_bits() as i64;
+    (if bits < 0 { i64::MIN - bits } else { bits }) as i128
+}
+
+#[cfg(feature = "f32")]
+fn ordered_bits(value: Scalar) -> i128 {
+    let bits = value.to_bits() as i32;
+    (if bits < 0 { i32::MIN - bits } else { bits }) as i128
+}
+
+impl ApproxEq for Scalar {
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance) -> bool {
+        if self == other {
+            // also infinities of the same sign
+            return true;
+        }
+        if !self.is_finite() || !other.is_finite() {
+            return false;
+        }
+        (self - other).abs() <= tolerance.epsilon
+            || (ordered_bits(*self) - ordered_bits(*other)).unsigned_abs() <= tolerance.ulps as u128
+    }
+}
+
+impl<T: ApproxEq, const N: usize> ApproxEq for [T; N] {
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance) -> bool {
+        self.iter().zip(other.iter()).all(|(a, b)| a.approx_eq(b, tolerance))
+    }
+}
@@ -0,0 +1,196 @@
+use crate::format::{TexelBuffer, TexelFormat};
+use crate::scalar::Scalar;
+use crate::texture::{ImageTexture, Texture};
+use crate::utils::clamp;
+use crate::vector::{Vector2, Vector3, Vector4};
+
+/// Area of an atlas in texels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Packs many small textures into one, see `Atlas`.
+pub struct AtlasBuilder {
+    images: Vec<ImageTexture>,
+    padding: usize,
+    extrude: usize,
+    format: TexelFormat,
+}
+
+impl AtlasBuilder {
+    pub fn new() -> Self {
+        Self {
+            images: Vec::new(),
+            padding: 0,
+            extrude: 1,
+            format: TexelFormat::Rgba32F,
+        }
+    }
+
+    /// Empty texels left between packed images.
+    pub fn with_padding(self, padding: usize) -> Self {
+        Self { padding, ..self }
+    }
+
+    /// Number of times the border texels of every image are repeated around it, so that
+    /// bilinear filtering at the edge of a sprite never reads its neighbours.
+    pub fn with_extrude(self, extrude: usize) -> Self {
+        Self { extrude, ..self }
+    }
+
+    pub fn with_format(self, format: TexelFormat) -> Self {
+        Self { format, ..self }
+    }
+
+    /// Queues an image and returns the index of its sprite in the built atlas.
+    pub fn add(&mut self, image: ImageTexture) -> usize {
+        self.images.push(image);
+        self.images.len() - 1
+    }
+
+    /// Shelf packs the images, tallest first, into a texture as wide as the smallest
+    /// power of two fitting the total area.
+    pub fn build(self) -> Atlas {
+        let border = self.extrude;
+        let cell = |image: &ImageTexture| (image.width() + 2 * border + self.padding, image.height() + 2 * border + self.padding);
+
+        let area: usize = self.images.iter().map(|image| cell(image).0 * cell(image).1).sum();
+        let widest = self.images.iter().map(|image| cell(image).0).max().unwrap_or(1);
+        let width = ((area as Scalar).sqrt().ceil() as usize).max(widest).next_power_of_two();
+
+        let mut order: Vec<usize> = (0..self.images.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(self.images[i].height()));
+
+        let mut cells = vec![(0, 0); self.images.len()];
+        let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+        for &i in order.iter() {
+            let (cell_width, cell_height) = cell(&self.images[i]);
+            if x + cell_width > width {
+                x = 0;
+                y += shelf_height;
+                shelf_height = 0;
+            }
+            cells[i] = (x, y);
+            x += cell_width;
+            shelf_height = shelf_height.max(cell_height);
+        }
+        let height = (y + shelf_height).max(1);
+
+        let mut texels = vec![Vector4::zero(); width * height];
+        let mut rects = Vec::with_capacity(self.images.len());
+        for (image, &(cell_x, cell_y)) in self.images.iter().zip(cells.iter()) {
+            let (w, h) = (image.width() as i64, image.height() as i64);
+            // the image with its extruded border, clamping repeats the edge texels
+            for dy in -(border as i64)..h + border as i64 {
+                for dx in -(border as i64)..w + border as i64 {
+                    let tx = (cell_x as i64 + border as i64 + dx) as usize;
+                    let ty = (cell_y as i64 + border as i64 + dy) as usize;
+                    texels[ty * width + tx] = image.texel_value(clamp(dx, 0, w - 1) as usize, clamp(dy, 0, h - 1) as usize);
+                }
+            }
+            rects.push(Rect {
+                x: cell_x + border,
+                y: cell_y + border,
+                width: image.width(),
+                height: image.height(),
+            });
+        }
+
+        Atlas {
+            texture: ImageTexture::from_buffer(width, height, TexelBuffer::from_values(self.format, texels)),
+            rects,
+        }
+    }
+}
+
+impl Default for AtlasBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Texture holding many images, each of them reachable as a `Sprite`.
+///
+/// The sampler of the atlas texture is used by all sprites. Mipmaps would mix
+/// neighbouring images at coarse levels, so they are better left off.
+#[derive(Clone, Debug)]
+pub struct Atlas {
+    pub texture: ImageTexture,
+    rects: Vec<Rect>,
+}
+
+impl Atlas {
+    /// Splits a sprite sheet into `columns` times `rows` equally sized frames,
+    /// numbered row by row from the top left. Panics without columns or rows.
+    pub fn from_grid(texture: ImageTexture, columns: usize, rows: usize) -> Self {
+        assert!(columns > 0 && rows > 0, "sprite grids need at least one column and one row, got {}x{}", columns, rows);
+        let (width, height) = (texture.width() / columns, texture.height() / rows);
+        let rects = (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| Rect { x: column * width, y: row * height, width, height }))
+            .collect();
+        Self { texture, rects }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    pub fn rect(&self, sprite: usize) -> Rect {
+        self.rects[sprite]
+    }
+
+    /// Handle sampling the sprite with its own `[0, 1]` coordinates. Cheap, the atlas
+    /// texels are shared.
+    pub fn sprite(&self, sprite: usize) -> Sprite {
+        let rect = self.rects[sprite];
+        let size = Vector2::new(self.texture.width() as Scalar, self.texture.height() as Scalar);
+        Sprite {
+            texture: self.texture.clone(),
+            offset: Vector2::new(rect.x as Scalar / size.x, rect.y as Scalar / size.y),
+            scale: Vector2::new(rect.width as Scalar / size.x, rect.height as Scalar / size.y),
+        }
+    }
+}
+
+/// Part of an atlas sampled as a texture of its own. Coordinates are clamped to the sprite.
+#[derive(Clone, Debug)]
+pub struct Sprite {
+    texture: ImageTexture,
+    offset: Vector2,
+    scale: Vector2,
+}
+
+impl Sprite {
+    /// Coordinates in the atlas of the sprite coordinates `uv`.
+    pub fn atlas_uv(&self, uv: Vector2) -> Vector2 {
+        let u = clamp(uv.x, 0.0, 1.0);
+        let v = clamp(uv.y, 0.0, 1.0);
+        Vector2::new(self.offset.x + u * self.scale.x, self.offset.y + v * self.scale.y)
+    }
+
+    fn atlas_derivative(&self, d: Vector2) -> Vector2 {
+        d.component_mul(self.scale)
+    }
+}
+
+impl Texture for Sprite {
+    fn sample(&self, uv: Vector2) -> Vector3 {
+        self.texture.sample(self.atlas_uv(uv))
+    }
+
+    fn sample_lod(&self, uv: Vector2, lod: Scalar) -> Vector3 {
+        self.texture.sample_lod(self.atlas_uv(uv), lod)
+    }
+
+    fn sample_grad(&self, uv: Vector2, ddx: Vector2, ddy: Vector2) -> Vector3 {
+        self.texture.sample_grad(self.atlas_uv(uv), self.atlas_derivative(ddx), self.atlas_derivative(ddy))
+    }
+}
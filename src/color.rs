@@ -0,0 +1,311 @@
+use std::{fmt, ops};
+
+use crate::approx::{ApproxEq, Tolerance};
+use crate::format::{linear_to_srgb, srgb_to_linear};
+use crate::matrix::Matrix3;
+use crate::scalar::Scalar;
+use crate::utils::clamp;
+use crate::vector::{self, Vector3, Vector4};
+
+/// Linear RGB with straight alpha, the space shaders light and blend in. Channels are
+/// not limited to `[0, 1]`, values above 1 are brighter than the display can show.
+///
+/// Other spaces are returned as `Vector3`s of their components. HSV and HSL describe the
+/// sRGB encoded color, like color pickers do, with the hue in degrees.
+///
+/// `Default` is transparent black.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct Color {
+    pub r: Scalar,
+    pub g: Scalar,
+    pub b: Scalar,
+    pub a: Scalar,
+}
+
+/// Linear sRGB to CIE XYZ, D65 white point.
+#[allow(clippy::excessive_precision)]
+const RGB_TO_XYZ: Matrix3 = Matrix3 {
+    m00: 0.412_456_4, m01: 0.357_576_1, m02: 0.180_437_5,
+    m10: 0.212_672_9, m11: 0.715_152_2, m12: 0.072_175_0,
+    m20: 0.019_333_9, m21: 0.119_192_0, m22: 0.950_304_1,
+};
+
+#[allow(clippy::excessive_precision)]
+const XYZ_TO_RGB: Matrix3 = Matrix3 {
+    m00: 3.240_454_2, m01: -1.537_138_5, m02: -0.498_531_4,
+    m10: -0.969_266_0, m11: 1.876_010_8, m12: 0.041_556_0,
+    m20: 0.055_643_4, m21: -0.204_025_9, m22: 1.057_225_2,
+};
+
+// Oklab goes through the cone responses LMS, cube roots of them and a final matrix.
+
+#[allow(clippy::excessive_precision)]
+const RGB_TO_LMS: Matrix3 = Matrix3 {
+    m00: 0.412_221_470_8, m01: 0.536_332_536_3, m02: 0.051_445_992_9,
+    m10: 0.211_903_498_2, m11: 0.680_699_545_1, m12: 0.107_396_956_6,
+    m20: 0.088_302_461_9, m21: 0.281_718_837_6, m22: 0.629_978_700_5,
+};
+
+#[allow(clippy::excessive_precision)]
+const LMS_TO_OKLAB: Matrix3 = Matrix3 {
+    m00: 0.210_454_255_3, m01: 0.793_617_785_0, m02: -0.004_072_046_8,
+    m10: 1.977_998_495_1, m11: -2.428_592_205_0, m12: 0.450_593_709_9,
+    m20: 0.025_904_037_1, m21: 0.782_771_766_2, m22: -0.808_675_766_0,
+};
+
+#[allow(clippy::excessive_precision)]
+const OKLAB_TO_LMS: Matrix3 = Matrix3 {
+    m00: 1.0, m01: 0.396_337_777_4, m02: 0.215_803_757_3,
+    m10: 1.0, m11: -0.105_561_345_8, m12: -0.063_854_172_8,
+    m20: 1.0, m21: -0.089_484_177_5, m22: -1.291_485_548_0,
+};
+
+#[allow(clippy::excessive_precision)]
+const LMS_TO_RGB: Matrix3 = Matrix3 {
+    m00: 4.076_741_662_1, m01: -3.307_711_591_3, m02: 0.230_969_929_2,
+    m10: -1.268_438_004_6, m11: 2.609_757_401_1, m12: -0.341_319_396_5,
+    m20: -0.004_196_086_3, m21: -0.703_418_614_7, m22: 1.707_614_701_0,
+};
+
+impl Color {
+    pub const BLACK: Self = Self::rgb(0.0, 0.0, 0.0);
+    pub const WHITE: Self = Self::rgb(1.0, 1.0, 1.0);
+    pub const RED: Self = Self::rgb(1.0, 0.0, 0.0);
+    pub const GREEN: Self = Self::rgb(0.0, 1.0, 0.0);
+    pub const BLUE: Self = Self::rgb(0.0, 0.0, 1.0);
+    pub const YELLOW: Self = Self::rgb(1.0, 1.0, 0.0);
+    pub const CYAN: Self = Self::rgb(0.0, 1.0, 1.0);
+    pub const MAGENTA: Self = Self::rgb(1.0, 0.0, 1.0);
+    pub const TRANSPARENT: Self = Self::new(0.0, 0.0, 0.0, 0.0);
+
+    pub const fn new(r: Scalar, g: Scalar, b: Scalar, a: Scalar) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Opaque color.
+    pub const fn rgb(r: Scalar, g: Scalar, b: Scalar) -> Self {
+        Self::new(r, g, b, 1.0)
+    }
+
+    /// Opaque gray of the given linear intensity.
+    pub const fn gray(value: Scalar) -> Self {
+        Self::rgb(value, value, value)
+    }
+
+    pub fn with_alpha(self, a: Scalar) -> Self {
+        Self { a, ..self }
+    }
+
+    pub fn lerp(self, other: Self, t: Scalar) -> Self {
+        self + (other - self) * t
+    }
+
+    /// Relative luminance, the `Y` of `to_xyz`.
+    pub fn luminance(&self) -> Scalar {
+        RGB_TO_XYZ.m10 * self.r + RGB_TO_XYZ.m11 * self.g + RGB_TO_XYZ.m12 * self.b
+    }
+
+    /// Opaque color of sRGB encoded components in `[0, 1]`.
+    pub fn from_srgb(srgb: Vector3) -> Self {
+        Self::rgb(srgb_to_linear(srgb.x), srgb_to_linear(srgb.y), srgb_to_linear(srgb.z))
+    }
+
+    pub fn to_srgb(&self) -> Vector3 {
+        Vector3::new(linear_to_srgb(self.r), linear_to_srgb(self.g), linear_to_srgb(self.b))
+    }
+
+    /// Opaque color of sRGB encoded bytes, as found in image files and color pickers.
+    pub fn from_srgb8(r: u8, g: u8, b: u8) -> Self {
+        Self::from_srgb(Vector3::new(r as Scalar, g as Scalar, b as Scalar) / 255.0)
+    }
+
+    /// sRGB encoded color bytes and linear alpha, rounded to the nearest value.
+    pub fn to_srgb8(&self) -> [u8; 4] {
+        let unorm8 = |c: Scalar| (clamp(c, 0.0, 1.0) * 255.0).round() as u8;
+        let srgb = self.to_srgb();
+        [unorm8(srgb.x), unorm8(srgb.y), unorm8(srgb.z), unorm8(self.a)]
+    }
+
+    /// Opaque color of hue in degrees, any value wraps around, saturation and value in `[0, 1]`.
+    pub fn from_hsv(hsv: Vector3) -> Self {
+        let chroma = hsv.z * hsv.y;
+        Self::from_hue_chroma(hsv.x, chroma, hsv.z - chroma)
+    }
+
+    /// Hue in `[0, 360)`, 0 for grays, saturation and value.
+    pub fn to_hsv(&self) -> Vector3 {
+        let (hue, min, max) = self.hue_min_max();
+        let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+        Vector3::new(hue, saturation, max)
+    }
+
+    /// Opaque color of hue in degrees, any value wraps around, saturation and lightness in `[0, 1]`.
+    pub fn from_hsl(hsl: Vector3) -> Self {
+        let chroma = (1.0 - (2.0 * hsl.z - 1.0).abs()) * hsl.y;
+        Self::from_hue_chroma(hsl.x, chroma, hsl.z - chroma * 0.5)
+    }
+
+    /// Hue in `[0, 360)`, 0 for grays, saturation and lightness.
+    pub fn to_hsl(&self) -> Vector3 {
+        let (hue, min, max) = self.hue_min_max();
+        let lightness = (max + min) * 0.5;
+        let saturation = if max > min { (max - min) / (1.0 - (2.0 * lightness - 1.0).abs()) } else { 0.0 };
+        Vector3::new(hue, saturation, lightness)
+    }
+
+    /// Hue and the smallest and largest of the sRGB encoded components, shared by HSV and HSL.
+    fn hue_min_max(&self) -> (Scalar, Scalar, Scalar) {
+        let Vector3 { x: r, y: g, z: b } = self.to_srgb();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+        let sector = if chroma == 0.0 {
+            0.0
+        } else if max == r {
+            ((g - b) / chroma).rem_euclid(6.0)
+        } else if max == g {
+            (b - r) / chroma + 2.0
+        } else {
+            (r - g) / chroma + 4.0
+        };
+        (sector * 60.0, min, max)
+    }
+
+    /// sRGB encoded color of the given hue and chroma, lifted by `offset` in all channels.
+    fn from_hue_chroma(hue: Scalar, chroma: Scalar, offset: Scalar) -> Self {
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector.rem_euclid(2.0) - 1.0).abs());
+        let (r, g, b) = match sector as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        Self::from_srgb(Vector3::new(r + offset, g + offset, b + offset))
+    }
+
+    /// Opaque color of CIE XYZ tristimulus values, D65 white is `Color::WHITE`.
+    pub fn from_xyz(xyz: Vector3) -> Self {
+        (XYZ_TO_RGB * xyz).into()
+    }
+
+    pub fn to_xyz(&self) -> Vector3 {
+        RGB_TO_XYZ * Vector3::from(*self)
+    }
+
+    /// Opaque color of Oklab lightness `L` and opponent axes `a` and `b`. Oklab is
+    /// perceptually uniform, so gradients interpolated in it look even.
+    pub fn from_oklab(lab: Vector3) -> Self {
+        let lms = OKLAB_TO_LMS * lab;
+        (LMS_TO_RGB * Vector3::new(lms.x.powi(3), lms.y.powi(3), lms.z.powi(3))).into()
+    }
+
+    pub fn to_oklab(&self) -> Vector3 {
+        let lms = RGB_TO_LMS * Vector3::from(*self);
+        LMS_TO_OKLAB * Vector3::new(lms.x.cbrt(), lms.y.cbrt(), lms.z.cbrt())
+    }
+
+    /// Opaque color of a `0x00RRGGBB` value like the ones `Framebuffer::colors` holds.
+    /// The bytes are taken as they are, without decoding.
+    pub fn from_u32(packed: u32) -> Self {
+        let channel = |shift: u32| ((packed >> shift) & 0xff) as Scalar / 255.0;
+        Self::rgb(channel(16), channel(8), channel(0))
+    }
+
+    /// Packs into `0x00RRGGBB` the way the framebuffer presents linear color planes,
+    /// channels clamped to `[0, 1]` and truncated to bytes without encoding, alpha dropped.
+    pub fn to_u32(&self) -> u32 {
+        let channel = |c: Scalar| (clamp(c, 0.0, 1.0) * 255.0) as u32;
+        channel(self.r) << 16 | channel(self.g) << 8 | channel(self.b)
+    }
+}
+
+impl_op_ex!(+ |lhs: &Color, rhs: &Color| -> Color {
+    Color {
+        r: lhs.r + rhs.r,
+        g: lhs.g + rhs.g,
+        b: lhs.b + rhs.b,
+        a: lhs.a + rhs.a
+    }
+});
+
+impl_op_ex!(- |lhs: &Color, rhs: &Color| -> Color {
+    Color {
+        r: lhs.r - rhs.r,
+        g: lhs.g - rhs.g,
+        b: lhs.b - rhs.b,
+        a: lhs.a - rhs.a
+    }
+});
+
+// modulates channel by channel, e.g. light by albedo
+impl_op_ex!(* |lhs: &Color, rhs: &Color| -> Color {
+    Color {
+        r: lhs.r * rhs.r,
+        g: lhs.g * rhs.g,
+        b: lhs.b * rhs.b,
+        a: lhs.a * rhs.a
+    }
+});
+
+impl_op_ex!(* |lhs: &Color, rhs: Scalar| -> Color {
+    Color {
+        r: lhs.r * rhs,
+        g: lhs.g * rhs,
+        b: lhs.b * rhs,
+        a: lhs.a * rhs
+    }
+});
+
+impl_op_ex!(* |lhs: Scalar, rhs: &Color| -> Color {
+    rhs * lhs
+});
+
+impl_op_ex!(+= |lhs: &mut Color, rhs: &Color| {
+    *lhs = *lhs + rhs;
+});
+
+impl_op_ex!(*= |lhs: &mut Color, rhs: Scalar| {
+    *lhs = *lhs * rhs;
+});
+
+/// Opaque color, how shaders return colors.
+impl From<Vector3> for Color {
+    fn from(v: Vector3) -> Self {
+        Self::rgb(v.x, v.y, v.z)
+    }
+}
+
+impl From<Color> for Vector3 {
+    fn from(c: Color) -> Self {
+        Vector3::new(c.r, c.g, c.b)
+    }
+}
+
+impl From<Vector4> for Color {
+    fn from(v: Vector4) -> Self {
+        Self::new(v.x, v.y, v.z, v.w)
+    }
+}
+
+impl From<Color> for Vector4 {
+    fn from(c: Color) -> Self {
+        Vector4::new(c.r, c.g, c.b, c.a)
+    }
+}
+
+impl ApproxEq for Color {
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance) -> bool {
+        Vector4::from(*self).approx_eq(&(*other).into(), tolerance)
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        vector::fmt_components(f, &[self.r, self.g, self.b, self.a])
+    }
+}
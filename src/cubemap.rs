@@ -0,0 +1,232 @@
+use crate::matrix::Matrix4;
+use crate::renderer::Program;
+use crate::scalar::consts::PI;
+use crate::scalar::Scalar;
+use crate::texture::{Filter, ImageTexture, Texture};
+use crate::vector::{Vector2, Vector3, Vector4};
+
+/// Faces of a cube texture in the order expected by `CubeTexture::from_faces`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CubeFace {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+impl CubeFace {
+    pub const ALL: [CubeFace; 6] = [
+        CubeFace::PositiveX,
+        CubeFace::NegativeX,
+        CubeFace::PositiveY,
+        CubeFace::NegativeY,
+        CubeFace::PositiveZ,
+        CubeFace::NegativeZ,
+    ];
+
+    /// Face hit by `direction` and the position on it, both coordinates in `[-1, 1]`.
+    /// Faces are oriented the same way as OpenGL cube maps.
+    pub fn project(direction: Vector3) -> (CubeFace, Scalar, Scalar) {
+        let Vector3 { x, y, z } = direction;
+        let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+        if ax >= ay && ax >= az {
+            if x > 0.0 {
+                (CubeFace::PositiveX, -z / ax, -y / ax)
+            } else {
+                (CubeFace::NegativeX, z / ax, -y / ax)
+            }
+        } else if ay >= az {
+            if y > 0.0 {
+                (CubeFace::PositiveY, x / ay, z / ay)
+            } else {
+                (CubeFace::NegativeY, x / ay, -z / ay)
+            }
+        } else if z > 0.0 {
+            (CubeFace::PositiveZ, x / az, -y / az)
+        } else {
+            (CubeFace::NegativeZ, -x / az, -y / az)
+        }
+    }
+
+    /// Inverse of `project`, not normalized direction through the point `(s, t)` of the face.
+    pub fn direction(self, s: Scalar, t: Scalar) -> Vector3 {
+        match self {
+            CubeFace::PositiveX => Vector3::new(1.0, -t, -s),
+            CubeFace::NegativeX => Vector3::new(-1.0, -t, s),
+            CubeFace::PositiveY => Vector3::new(s, 1.0, t),
+            CubeFace::NegativeY => Vector3::new(s, -1.0, -t),
+            CubeFace::PositiveZ => Vector3::new(s, -t, 1.0),
+            CubeFace::NegativeZ => Vector3::new(-s, -t, -1.0),
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Texture sampled by a direction, e.g. for skyboxes and reflections.
+///
+/// Bilinear filtering is seamless, texels past the edge of a face are taken from
+/// the neighbouring face.
+#[derive(Clone, Debug)]
+pub struct CubeTexture {
+    faces: [ImageTexture; 6],
+    size: usize,
+    pub filter: Filter,
+}
+
+impl CubeTexture {
+    /// Faces have to be square and of equal size, see `CubeFace` for the order.
+    pub fn from_faces(faces: [ImageTexture; 6]) -> Self {
+        let size = faces[0].width();
+        for face in faces.iter() {
+            assert!(face.width() == size && face.height() == size, "cube faces have to be squares of equal size");
+        }
+        Self {
+            faces,
+            size,
+            filter: Filter::Bilinear,
+        }
+    }
+
+    /// Resamples an equirectangular (latitude-longitude) panorama into faces of `size` texels.
+    /// The center of the panorama looks towards -z, its top row towards +y. It is seen from
+    /// the inside like the skybox shows it, the right half of the panorama is towards -x.
+    pub fn from_equirectangular(panorama: &dyn Texture, size: usize) -> Self {
+        let face = |face: CubeFace| {
+            let mut texels = Vec::with_capacity(size * size);
+            for y in 0..size {
+                for x in 0..size {
+                    let s = (x as Scalar + 0.5) / size as Scalar * 2.0 - 1.0;
+                    let t = (y as Scalar + 0.5) / size as Scalar * 2.0 - 1.0;
+                    let direction = face.direction(s, t);
+                    let direction = direction.normalize();
+
+                    let u = 0.5 + (-direction.x).atan2(-direction.z) / (2.0 * PI);
+                    let v = direction.y.acos() / PI;
+                    texels.push(panorama.sample(Vector2::new(u, v)));
+                }
+            }
+            ImageTexture::new(size, size, texels)
+        };
+        Self::from_faces([
+            face(CubeFace::PositiveX),
+            face(CubeFace::NegativeX),
+            face(CubeFace::PositiveY),
+            face(CubeFace::NegativeY),
+            face(CubeFace::PositiveZ),
+            face(CubeFace::NegativeZ),
+        ])
+    }
+
+    pub fn with_filter(self, filter: Filter) -> Self {
+        Self { filter, ..self }
+    }
+
+    pub fn face(&self, face: CubeFace) -> &ImageTexture {
+        &self.faces[face.index()]
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Texel of `face` at integer coordinates which may lie past its edges.
+    fn fetch(&self, face: CubeFace, x: i64, y: i64) -> Vector3 {
+        let size = self.size as i64;
+        if x >= 0 && x < size && y >= 0 && y < size {
+            return self.faces[face.index()].texel(x as usize, y as usize);
+        }
+
+        // continue through the edge onto the neighbouring face
+        let s = (x as Scalar + 0.5) / self.size as Scalar * 2.0 - 1.0;
+        let t = (y as Scalar + 0.5) / self.size as Scalar * 2.0 - 1.0;
+        let (face, s, t) = CubeFace::project(face.direction(s, t));
+        let (x, y) = self.texel_coords(s, t);
+        self.faces[face.index()].texel(x, y)
+    }
+
+    fn texel_coords(&self, s: Scalar, t: Scalar) -> (usize, usize) {
+        let to_texel = |c: Scalar| (((c + 1.0) * 0.5 * self.size as Scalar) as usize).min(self.size - 1);
+        (to_texel(s), to_texel(t))
+    }
+
+    pub fn sample(&self, direction: Vector3) -> Vector3 {
+        let (face, s, t) = CubeFace::project(direction);
+        match self.filter {
+            Filter::Nearest => {
+                let (x, y) = self.texel_coords(s, t);
+                self.faces[face.index()].texel(x, y)
+            }
+            Filter::Bilinear => {
+                let x = (s + 1.0) * 0.5 * self.size as Scalar - 0.5;
+                let y = (t + 1.0) * 0.5 * self.size as Scalar - 0.5;
+                let x0 = x.floor();
+                let y0 = y.floor();
+                let fx = x - x0;
+                let fy = y - y0;
+                let (x0, y0) = (x0 as i64, y0 as i64);
+
+                let top = self.fetch(face, x0, y0) * (1.0 - fx) + self.fetch(face, x0 + 1, y0) * fx;
+                let bottom = self.fetch(face, x0, y0 + 1) * (1.0 - fx) + self.fetch(face, x0 + 1, y0 + 1) * fx;
+                top * (1.0 - fy) + bottom * fy
+            }
+        }
+    }
+}
+
+/// Depth at which the skybox is drawn, behind any other geometry.
+pub const SKYBOX_DEPTH: Scalar = Scalar::MAX;
+
+/// Uniform of the skybox program. `view_projection` should only rotate and project,
+/// the camera is always in the center of the skybox.
+#[derive(Clone, Debug)]
+pub struct SkyboxUniform {
+    pub texture: CubeTexture,
+    pub view_projection: Matrix4,
+}
+
+pub fn skybox_vertex_shader(direction: Vector3, uniform: &SkyboxUniform) -> (Vector3, Vector3) {
+    // the same screen orientation as scenes drawn with `Matrix4::viewport`, +y up
+    let screen = (Matrix4::viewport() * uniform.view_projection).transform_point(direction);
+    (Vector3::new(screen.x, screen.y, SKYBOX_DEPTH), direction)
+}
+
+pub fn skybox_fragment_shader(_: Vector3, direction: Vector3, uniform: &SkyboxUniform) -> Vector3 {
+    uniform.texture.sample(direction)
+}
+
+pub fn skybox_program(uniform: SkyboxUniform, width: usize, height: usize, region_width: usize, region_height: usize) -> Program<Vector3, SkyboxUniform, Vector3> {
+    Program::new(skybox_vertex_shader, skybox_fragment_shader, uniform, width, height, region_width, region_height)
+}
+
+/// Enqueues the cube around the camera, every face split into `subdivisions` squared quads.
+/// Attributes are interpolated linearly in screen space, finer subdivision hides the error.
+/// Triangles reaching behind the camera are skipped, they are never visible for sane
+/// fields of view.
+pub fn enqueue_skybox(program: &mut Program<Vector3, SkyboxUniform, Vector3>, subdivisions: usize) {
+    let subdivisions = subdivisions.max(1);
+    let view_projection = program.uniform.view_projection;
+    let in_front = |direction: &Vector3| (view_projection * Vector4::new(direction.x, direction.y, direction.z, 1.0)).w > 1e-6;
+
+    for face in CubeFace::ALL.iter() {
+        let corner = |i: usize, j: usize| {
+            let s = i as Scalar / subdivisions as Scalar * 2.0 - 1.0;
+            let t = j as Scalar / subdivisions as Scalar * 2.0 - 1.0;
+            face.direction(s, t)
+        };
+        for j in 0..subdivisions {
+            for i in 0..subdivisions {
+                let quad = [corner(i, j), corner(i + 1, j), corner(i + 1, j + 1), corner(i, j + 1)];
+                if !quad.iter().all(&in_front) {
+                    continue;
+                }
+                program.enqueue_triangle(quad[0], quad[1], quad[2]);
+                program.enqueue_triangle(quad[0], quad[2], quad[3]);
+            }
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use crate::scalar::Scalar;
+use crate::utils::clamp;
+use crate::vector::{Vector3, Vector4};
+
+// size of the largest texel, `Rgb64F`
+const MAX_TEXEL_BYTES: usize = 24;
+
+/// How texels of textures and framebuffer planes are stored.
+///
+/// Values are always read and written as linear `Vector4`. Missing channels read as
+/// zero, missing alpha as one. sRGB formats decode the color channels on read and
+/// encode them on write, alpha stays linear.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TexelFormat {
+    R8,
+    Rg8,
+    Rgba8,
+    Rgba8Srgb,
+    /// Half precision float.
+    R16F,
+    Rgba16F,
+    R32F,
+    Rg32F,
+    Rgb32F,
+    Rgba32F,
+    /// Double precision, holds colors of `f64` scalars exactly.
+    Rgb64F,
+    /// Unsigned integer in the first channel, e.g. object ids.
+    R32Ui,
+    /// Depth in `[0, 1]` quantized to 16 bits.
+    Depth16,
+    /// Depth in `[0, 1]` quantized to 24 bits.
+    Depth24,
+    Depth32F,
+}
+
+impl TexelFormat {
+    pub fn channels(self) -> usize {
+        match self {
+            TexelFormat::R8 | TexelFormat::R16F | TexelFormat::R32F | TexelFormat::R32Ui => 1,
+            TexelFormat::Depth16 | TexelFormat::Depth24 | TexelFormat::Depth32F => 1,
+            TexelFormat::Rg8 | TexelFormat::Rg32F => 2,
+            TexelFormat::Rgb32F | TexelFormat::Rgb64F => 3,
+            TexelFormat::Rgba8 | TexelFormat::Rgba8Srgb | TexelFormat::Rgba16F | TexelFormat::Rgba32F => 4,
+        }
+    }
+
+    pub fn bytes_per_texel(self) -> usize {
+        match self {
+            TexelFormat::R8 => 1,
+            TexelFormat::Rg8 | TexelFormat::R16F | TexelFormat::Depth16 => 2,
+            TexelFormat::Depth24 => 3,
+            TexelFormat::Rgba8 | TexelFormat::Rgba8Srgb | TexelFormat::R32F | TexelFormat::R32Ui | TexelFormat::Depth32F => 4,
+            TexelFormat::Rgba16F | TexelFormat::Rg32F => 8,
+            TexelFormat::Rgb32F => 12,
+            TexelFormat::Rgba32F => 16,
+            TexelFormat::Rgb64F => 24,
+        }
+    }
+
+    pub fn is_srgb(self) -> bool {
+        self == TexelFormat::Rgba8Srgb
+    }
+
+    pub fn is_depth(self) -> bool {
+        matches!(self, TexelFormat::Depth16 | TexelFormat::Depth24 | TexelFormat::Depth32F)
+    }
+
+    pub fn is_integer(self) -> bool {
+        self == TexelFormat::R32Ui
+    }
+
+    /// Stores `value` into `bytes`, which have to be `bytes_per_texel` long.
+    pub fn write(self, bytes: &mut [u8], value: Vector4) {
+        let unorm8 = |c: Scalar| (clamp(c, 0.0, 1.0) * 255.0).round() as u8;
+        let srgb8 = |c: Scalar| unorm8(linear_to_srgb(c));
+        let unorm = |c: Scalar, max: Scalar| (clamp(c, 0.0, 1.0) * max).round() as u32;
+        #[allow(clippy::unnecessary_cast)] // `Scalar` may be `f32` already
+        let mut put_f32 = |i: usize, c: Scalar| bytes[i * 4..i * 4 + 4].copy_from_slice(&(c as f32).to_le_bytes());
+        match self {
+            TexelFormat::R8 => bytes[0] = unorm8(value.x),
+            TexelFormat::Rg8 => {
+                bytes[0] = unorm8(value.x);
+                bytes[1] = unorm8(value.y);
+            }
+            TexelFormat::Rgba8 => {
+                bytes.copy_from_slice(&[unorm8(value.x), unorm8(value.y), unorm8(value.z), unorm8(value.w)]);
+            }
+            TexelFormat::Rgba8Srgb => {
+                bytes.copy_from_slice(&[srgb8(value.x), srgb8(value.y), srgb8(value.z), unorm8(value.w)]);
+            }
+            TexelFormat::R16F => bytes.copy_from_slice(&f16_from_scalar(value.x).to_le_bytes()),
+            TexelFormat::Rgba16F => {
+                for (i, &c) in [value.x, value.y, value.z, value.w].iter().enumerate() {
+                    bytes[i * 2..i * 2 + 2].copy_from_slice(&f16_from_scalar(c).to_le_bytes());
+                }
+            }
+            TexelFormat::R32F | TexelFormat::Depth32F => put_f32(0, value.x),
+            TexelFormat::Rg32F => {
+                put_f32(0, value.x);
+                put_f32(1, value.y);
+            }
+            TexelFormat::Rgb32F => {
+                put_f32(0, value.x);
+                put_f32(1, value.y);
+                put_f32(2, value.z);
+            }
+            TexelFormat::Rgba32F => {
+                put_f32(0, value.x);
+                put_f32(1, value.y);
+                put_f32(2, value.z);
+                put_f32(3, value.w);
+            }
+            TexelFormat::Rgb64F => {
+                for (i, &c) in [value.x, value.y, value.z].iter().enumerate() {
+                    #[allow(clippy::unnecessary_cast)] // `Scalar` may be `f64` already
+                    bytes[i * 8..i * 8 + 8].copy_from_slice(&(c as f64).to_le_bytes());
+                }
+            }
+            TexelFormat::R32Ui => {
+                let id = clamp(value.x.round(), 0.0, u32::MAX as Scalar) as u32;
+                bytes.copy_from_slice(&id.to_le_bytes());
+            }
+            TexelFormat::Depth16 => bytes.copy_from_slice(&(unorm(value.x, 65535.0) as u16).to_le_bytes()),
+            TexelFormat::Depth24 => bytes.copy_from_slice(&unorm(value.x, 16_777_215.0).to_le_bytes()[..3]),
+        }
+    }
+
+    /// Loads the value stored in `bytes`, which have to be `bytes_per_texel` long.
+    pub fn read(self, bytes: &[u8]) -> Vector4 {
+        let unorm8 = |b: u8| b as Scalar / 255.0;
+        let get_f32 = |i: usize| f32::from_le_bytes([bytes[i * 4], bytes[i * 4 + 1], bytes[i * 4 + 2], bytes[i * 4 + 3]]) as Scalar;
+        let get_f64 = |i: usize| {
+            let mut le = [0; 8];
+            le.copy_from_slice(&bytes[i * 8..i * 8 + 8]);
+            f64::from_le_bytes(le) as Scalar
+        };
+        let get_f16 = |i: usize| f16_to_scalar(u16::from_le_bytes([bytes[i * 2], bytes[i * 2 + 1]]));
+        match self {
+            TexelFormat::R8 => Vector4::new(unorm8(bytes[0]), 0.0, 0.0, 1.0),
+            TexelFormat::Rg8 => Vector4::new(unorm8(bytes[0]), unorm8(bytes[1]), 0.0, 1.0),
+            TexelFormat::Rgba8 => Vector4::new(unorm8(bytes[0]), unorm8(bytes[1]), unorm8(bytes[2]), unorm8(bytes[3])),
+            TexelFormat::Rgba8Srgb => Vector4::new(
+                srgb_to_linear(unorm8(bytes[0])),
+                srgb_to_linear(unorm8(bytes[1])),
+                srgb_to_linear(unorm8(bytes[2])),
+                unorm8(bytes[3]),
+            ),
+            TexelFormat::R16F => Vector4::new(get_f16(0), 0.0, 0.0, 1.0),
+            TexelFormat::Rgba16F => Vector4::new(get_f16(0), get_f16(1), get_f16(2), get_f16(3)),
+            TexelFormat::R32F | TexelFormat::Depth32F => Vector4::new(get_f32(0), 0.0, 0.0, 1.0),
+            TexelFormat::Rg32F => Vector4::new(get_f32(0), get_f32(1), 0.0, 1.0),
+            TexelFormat::Rgb32F => Vector4::new(get_f32(0), get_f32(1), get_f32(2), 1.0),
+            TexelFormat::Rgba32F => Vector4::new(get_f32(0), get_f32(1), get_f32(2), get_f32(3)),
+            TexelFormat::Rgb64F => Vector4::new(get_f64(0), get_f64(1), get_f64(2), 1.0),
+            TexelFormat::R32Ui => {
+                let id = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                Vector4::new(id as Scalar, 0.0, 0.0, 1.0)
+            }
+            TexelFormat::Depth16 => Vector4::new(u16::from_le_bytes([bytes[0], bytes[1]]) as Scalar / 65535.0, 0.0, 0.0, 1.0),
+            TexelFormat::Depth24 => {
+                let depth = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]);
+                Vector4::new(depth as Scalar / 16_777_215.0, 0.0, 0.0, 1.0)
+            }
+        }
+    }
+
+    /// The value as it reads back after being stored in this format.
+    pub fn quantize(self, value: Vector4) -> Vector4 {
+        let mut bytes = [0; MAX_TEXEL_BYTES];
+        let bytes = &mut bytes[..self.bytes_per_texel()];
+        self.write(bytes, value);
+        self.read(bytes)
+    }
+}
+
+/// sRGB transfer function, linear intensity to encoded value.
+pub fn linear_to_srgb(c: Scalar) -> Scalar {
+    if c <= 0.003_130_8 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Inverse of `linear_to_srgb`.
+pub fn srgb_to_linear(c: Scalar) -> Scalar {
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Rounds to the nearest half precision float, ties to even. Values past the range
+/// become infinities, tiny ones subnormals or zero. Rounds straight from the bits of
+/// `Scalar`, going through `f32` first would round twice.
+fn f16_from_scalar(value: Scalar) -> u16 {
+    #[cfg(not(feature = "f32"))]
+    let (bits, mantissa_bits, exponent_bits) = (value.to_bits(), 52, 11);
+    #[cfg(feature = "f32")]
+    let (bits, mantissa_bits, exponent_bits) = (u64::from(value.to_bits()), 23, 8);
+    let exponent_max = (1 << exponent_bits) - 1;
+    let sign = ((bits >> (mantissa_bits + exponent_bits - 15)) & 0x8000) as u16;
+    let exponent = ((bits >> mantissa_bits) & exponent_max) as i32;
+    let mantissa = bits & ((1 << mantissa_bits) - 1);
+
+    if exponent == exponent_max as i32 {
+        // infinity or nan, keep nans quiet
+        return sign | 0x7c00 | if mantissa != 0 { 0x0200 } else { 0 };
+    }
+    let exponent = exponent - (exponent_max >> 1) as i32 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    // bits of the mantissa dropped by rounding, more for subnormals, which also get
+    // their implicit bit shifted into place
+    let (mantissa, shift, exponent) = if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        (mantissa | 1 << mantissa_bits, mantissa_bits - 9 - exponent, 0)
+    } else {
+        (mantissa, mantissa_bits - 10, exponent)
+    };
+    let half = ((exponent as u64) << 10) | (mantissa >> shift);
+    let rest = mantissa & ((1 << shift) - 1);
+    let halfway = 1 << (shift - 1);
+    let round = rest > halfway || (rest == halfway && half & 1 == 1);
+    // a carry out of the mantissa correctly bumps the exponent, up to infinity
+    sign | (half + round as u64) as u16
+}
+
+fn f16_to_scalar(half: u16) -> Scalar {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((half >> 10) & 0x1f) as i32;
+    let mantissa = (half & 0x03ff) as Scalar;
+    match exponent {
+        0 => sign * mantissa * Scalar::powi(2.0, -24),
+        0x1f if mantissa == 0.0 => sign * Scalar::INFINITY,
+        0x1f => Scalar::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * Scalar::powi(2.0, exponent - 15),
+    }
+}
+
+/// Tightly packed plane of texels in one format.
+#[derive(Clone, Debug)]
+pub struct TexelBuffer {
+    format: TexelFormat,
+    bytes: Vec<u8>,
+}
+
+impl TexelBuffer {
+    /// Buffer of `len` texels, all holding `value`.
+    pub fn new(format: TexelFormat, len: usize, value: Vector4) -> Self {
+        let size = format.bytes_per_texel();
+        let mut texel = [0; MAX_TEXEL_BYTES];
+        format.write(&mut texel[..size], value);
+        Self {
+            format,
+            bytes: texel[..size].repeat(len),
+        }
+    }
+
+    pub fn from_values(format: TexelFormat, values: impl IntoIterator<Item = Vector4>) -> Self {
+        let size = format.bytes_per_texel();
+        let mut bytes = Vec::new();
+        let mut texel = [0; MAX_TEXEL_BYTES];
+        for value in values {
+            format.write(&mut texel[..size], value);
+            bytes.extend_from_slice(&texel[..size]);
+        }
+        Self { format, bytes }
+    }
+
+    /// Color channels of `colors`, alpha is one.
+    pub fn from_colors(format: TexelFormat, colors: impl IntoIterator<Item = Vector3>) -> Self {
+        Self::from_values(format, colors.into_iter().map(|c| Vector4::new(c.x, c.y, c.z, 1.0)))
+    }
+
+    pub fn format(&self) -> TexelFormat {
+        self.format
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len() / self.format.bytes_per_texel()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Raw bytes of the texels, e.g. sRGB encoded values ready for display.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn get(&self, index: usize) -> Vector4 {
+        let size = self.format.bytes_per_texel();
+        self.format.read(&self.bytes[index * size..(index + 1) * size])
+    }
+
+    /// Color channels of the texel at `index`.
+    pub fn color(&self, index: usize) -> Vector3 {
+        let value = self.get(index);
+        Vector3::new(value.x, value.y, value.z)
+    }
+
+    pub fn set(&mut self, index: usize, value: Vector4) {
+        let size = self.format.bytes_per_texel();
+        self.format.write(&mut self.bytes[index * size..(index + 1) * size], value);
+    }
+
+    pub fn set_color(&mut self, index: usize, color: Vector3) {
+        self.set(index, Vector4::new(color.x, color.y, color.z, 1.0));
+    }
+
+    /// Sets every texel to `value`, encoded once and copied in place.
+    pub fn fill(&mut self, value: Vector4) {
+        let size = self.format.bytes_per_texel();
+        let mut texel = [0; MAX_TEXEL_BYTES];
+        self.format.write(&mut texel[..size], value);
+        for chunk in self.bytes.chunks_exact_mut(size) {
+            chunk.copy_from_slice(&texel[..size]);
+        }
+    }
+}
@@ -1,95 +1,332 @@
 use std::marker::{PhantomData, PhantomPinned};
-use std::pin::Pin;
+#[cfg(feature = "png")]
+use std::path::Path;
 
-use crate::texture::Texture;
-use crate::vector::Vector2;
+use crate::color::Color;
+use crate::cubemap::SKYBOX_DEPTH;
+use crate::format::{TexelBuffer, TexelFormat};
+use crate::image;
+use crate::scalar::Scalar;
+use crate::shadow::DepthTexture;
+use crate::target::{RenderTarget, TargetDescription};
+use crate::texture::{ImageTexture, Texture};
+use crate::vector::{Vector2, Vector4};
 
 use super::utils::clamp;
 use super::vector::Vector3;
 
+/// Attributes of the visible surface stored by the geometry pass of deferred shading.
+#[derive(Clone, Copy, Debug)]
+pub struct GBufferSample {
+    pub normal: Vector3,
+    pub albedo: Vector3,
+    pub material: u32,
+}
+
+impl Default for GBufferSample {
+    fn default() -> Self {
+        Self {
+            normal: Vector3::zero(),
+            albedo: Vector3::zero(),
+            material: 0,
+        }
+    }
+}
+
+/// Object found by picking: id of the draw and depth of its surface.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pick {
+    pub id: u32,
+    pub depth: Scalar,
+}
+
+/// Plane of a framebuffer which can be turned into a texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Plane {
+    Color,
+    /// Depth replicated in all three channels.
+    Depth,
+    /// Render target with given index, see `Framebuffer::with_targets`.
+    Target(usize),
+}
+
 pub struct Framebuffer {
     buffer: Vec<u32>,
-    colors: Vec<Vector3>,
-    depth_bits: Vec<f64>,
+    colors: TexelBuffer,
+    depth_bits: Vec<Scalar>,
+    ids: Vec<u32>,
+    // g-buffer planes, empty unless created with `with_gbuffer`
+    normals: Vec<Vector3>,
+    albedo: Vec<Vector3>,
+    materials: Vec<u32>,
+    targets: Vec<RenderTarget>,
     pub width: usize,
     pub height: usize,
     _pinned: PhantomPinned,
 }
 
+/// Format as precise as `Scalar`, values of the color plane of new framebuffers and of
+/// depth planes turned into textures read back as they were written.
+const SCALAR_FORMAT: TexelFormat = if cfg!(feature = "f32") { TexelFormat::Rgb32F } else { TexelFormat::Rgb64F };
+
 impl Framebuffer {
+    /// Framebuffer with color and depth planes, see `set_color_format` for narrower colors.
     pub fn new(width: usize, height: usize) -> Self {
         Self {
-            colors: vec![Vector3::zero(); width * height],
+            colors: TexelBuffer::new(SCALAR_FORMAT, width * height, Vector4::new(0.0, 0.0, 0.0, 1.0)),
             buffer: vec![0; width * height],
-            depth_bits: vec![std::f64::INFINITY; width * height],
+            depth_bits: vec![Scalar::INFINITY; width * height],
+            ids: vec![0; width * height],
+            normals: Vec::new(),
+            albedo: Vec::new(),
+            materials: Vec::new(),
+            targets: Vec::new(),
             width,
             height,
             _pinned: PhantomPinned,
         }
     }
 
-    pub fn set_color(&mut self, x: usize, y: usize, color: Vector3, depth: f64) {
+    /// Framebuffer with g-buffer planes (normal, albedo and material id) next to the
+    /// color and depth planes, needed by deferred programs.
+    pub fn with_gbuffer(width: usize, height: usize) -> Self {
+        let sample = GBufferSample::default();
+        Self {
+            normals: vec![sample.normal; width * height],
+            albedo: vec![sample.albedo; width * height],
+            materials: vec![sample.material; width * height],
+            ..Self::new(width, height)
+        }
+    }
+
+    pub fn has_gbuffer(&self) -> bool {
+        !self.materials.is_empty()
+    }
+
+    /// Framebuffer with one plane per description, written in order by fragment shaders
+    /// of programs created with [`Program::with_targets`].
+    ///
+    /// [`Program::with_targets`]: ../renderer/struct.Program.html#method.with_targets
+    pub fn with_targets(width: usize, height: usize, descriptions: &[TargetDescription]) -> Self {
+        Self {
+            targets: descriptions.iter().map(|&description| RenderTarget::new(description, width * height)).collect(),
+            ..Self::new(width, height)
+        }
+    }
+
+    pub fn targets(&self) -> &[RenderTarget] {
+        &self.targets
+    }
+
+    pub fn target_value(&self, target: usize, x: usize, y: usize) -> Vector4 {
+        self.targets[target].get(self.pos_to_index(x, y))
+    }
+
+    /// Writes `values` to the render targets if `depth` passes the depth test.
+    pub fn set_targets(&mut self, x: usize, y: usize, values: &[Vector4], depth: Scalar) {
         let index = self.pos_to_index(x, y);
         if self.depth_bits[index] > depth {
-            self.colors[index] = color;
             self.depth_bits[index] = depth;
+            self.overwrite_targets(x, y, values);
+        }
+    }
+
+    /// Writes `values` to the render targets without looking at the depth plane. Panics when
+    /// there are more values than targets.
+    pub fn overwrite_targets(&mut self, x: usize, y: usize, values: &[Vector4]) {
+        assert!(values.len() <= self.targets.len(), "{} values written to {} render targets", values.len(), self.targets.len());
+        let index = self.pos_to_index(x, y);
+        for (target, &value) in self.targets.iter_mut().zip(values) {
+            target.write(index, value);
         }
     }
 
-    pub fn clear(&mut self, clear_color: Vector3) {
-        for color in self.colors.iter_mut() {
-            *color = clear_color;
+    pub fn set_color(&mut self, x: usize, y: usize, color: Vector3, depth: Scalar) {
+        let index = self.pos_to_index(x, y);
+        if self.depth_bits[index] > depth {
+            self.colors.set_color(index, color);
+            self.depth_bits[index] = depth;
         }
+    }
+
+    /// Writes the color without looking at the depth plane.
+    pub fn overwrite_color(&mut self, x: usize, y: usize, color: Vector3) {
+        let index = self.pos_to_index(x, y);
+        self.colors.set_color(index, color);
+    }
+
+    pub fn depth(&self, x: usize, y: usize) -> Scalar {
+        self.depth_bits[self.pos_to_index(x, y)]
+    }
+
+    pub fn set_depth(&mut self, x: usize, y: usize, depth: Scalar) {
+        let index = self.pos_to_index(x, y);
+        if self.depth_bits[index] > depth {
+            self.depth_bits[index] = depth;
+        }
+    }
+
+    pub fn id(&self, x: usize, y: usize) -> u32 {
+        self.ids[self.pos_to_index(x, y)]
+    }
+
+    /// Writes the object id without looking at the depth plane.
+    pub fn overwrite_id(&mut self, x: usize, y: usize, id: u32) {
+        let index = self.pos_to_index(x, y);
+        self.ids[index] = id;
+    }
+
+    /// Object drawn at `(x, y)`, `None` if nothing but the skybox was drawn there or the
+    /// position is outside of the framebuffer.
+    pub fn pick(&self, x: usize, y: usize) -> Option<Pick> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let index = self.pos_to_index(x, y);
+        let depth = self.depth_bits[index];
+        // also rejects the infinite depth of cleared pixels
+        if depth < SKYBOX_DEPTH {
+            Some(Pick { id: self.ids[index], depth })
+        } else {
+            None
+        }
+    }
+
+    /// Topmost object drawn inside the rectangle `(x0, y0)..(x1, y1)`, the rectangle is
+    /// clipped to the framebuffer.
+    pub fn pick_rect(&self, x0: usize, y0: usize, x1: usize, y1: usize) -> Option<Pick> {
+        let mut topmost: Option<Pick> = None;
+        for y in y0..y1.min(self.height) {
+            for x in x0..x1.min(self.width) {
+                if let Some(pick) = self.pick(x, y) {
+                    // `Option::is_none_or` would need Rust 1.82
+                    #[allow(clippy::unnecessary_map_or)]
+                    let nearer = topmost.map_or(true, |topmost| pick.depth < topmost.depth);
+                    if nearer {
+                        topmost = Some(pick);
+                    }
+                }
+            }
+        }
+        topmost
+    }
+
+    /// Panics when the framebuffer was created without g-buffer planes.
+    pub fn gbuffer(&self, x: usize, y: usize) -> GBufferSample {
+        let index = self.pos_to_index(x, y);
+        GBufferSample {
+            normal: self.normals[index],
+            albedo: self.albedo[index],
+            material: self.materials[index],
+        }
+    }
+
+    /// Panics when the framebuffer was created without g-buffer planes.
+    pub fn set_gbuffer(&mut self, x: usize, y: usize, sample: GBufferSample) {
+        let index = self.pos_to_index(x, y);
+        self.normals[index] = sample.normal;
+        self.albedo[index] = sample.albedo;
+        self.materials[index] = sample.material;
+    }
+
+    /// Clears every plane, colors to `clear_color`, which is opaque when given as a `Vector3`.
+    pub fn clear(&mut self, clear_color: impl Into<Color>) {
+        self.colors.fill(clear_color.into().into());
         for depth in self.depth_bits.iter_mut() {
-            *depth = std::f64::INFINITY;
+            *depth = Scalar::INFINITY;
+        }
+        for id in self.ids.iter_mut() {
+            *id = 0;
+        }
+        let sample = GBufferSample::default();
+        for normal in self.normals.iter_mut() {
+            *normal = sample.normal;
+        }
+        for albedo in self.albedo.iter_mut() {
+            *albedo = sample.albedo;
+        }
+        for material in self.materials.iter_mut() {
+            *material = sample.material;
+        }
+        for target in self.targets.iter_mut() {
+            target.clear();
         }
     }
 
     pub fn finish_rendering(&mut self) {
-        self.buffer = self.colors.iter().map(|c| {
-            let r = clamp(c.x, 0., 1.) * 255.0;
-            let g = clamp(c.y, 0., 1.) * 255.0;
-            let b = clamp(c.z, 0., 1.) * 255.0;
-            r as u32 * 256 * 256 + g as u32 * 256 + b as u32
-        }).collect();
+        self.buffer = present(&self.colors);
+    }
+
+    /// Like `finish_rendering`, but presents the given render target instead of the color plane.
+    pub fn finish_rendering_target(&mut self, target: usize) {
+        self.buffer = present(self.targets[target].values());
+    }
+
+    pub fn color_format(&self) -> TexelFormat {
+        self.colors.format()
+    }
+
+    /// Reallocates the color plane in `format`, cleared to black. With `TexelFormat::Rgba8Srgb`
+    /// colors written in linear space are gamma encoded, blending and sampling decode them back.
+    pub fn set_color_format(&mut self, format: TexelFormat) {
+        self.colors = TexelBuffer::new(format, self.width * self.height, Vector4::new(0.0, 0.0, 0.0, 1.0));
     }
 
     pub fn colors(&self) -> &[u32] {
         &self.buffer
     }
 
+    /// Image of the colors presented by the last `finish_rendering` call.
+    pub fn to_image(&self) -> image::Image {
+        let pixels = self.buffer.iter().map(|&c| Color::from_u32(c).into()).collect();
+        image::Image {
+            width: self.width,
+            height: self.height,
+            pixels,
+        }
+    }
+
+    /// Saves the presented colors, see `to_image`.
+    #[cfg(feature = "png")]
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), image::ImageError> {
+        image::save_png(&self.to_image(), path)
+    }
+
+    /// Copies the plane into a texture, so that it can be bound as an input of later passes
+    /// while this framebuffer is cleared and rendered into again.
+    pub fn texture(&self, plane: Plane) -> ImageTexture {
+        let texels = match plane {
+            Plane::Color => self.colors.clone(),
+            Plane::Depth => {
+                let depths = self.depth_bits.iter().map(|&depth| Vector3::new(depth, depth, depth));
+                TexelBuffer::from_colors(SCALAR_FORMAT, depths)
+            }
+            Plane::Target(target) => self.targets[target].values().clone(),
+        };
+        ImageTexture::from_buffer(self.width, self.height, texels)
+    }
+
+    /// Copies the depth plane into a shadow map stored in the depth `format`.
+    pub fn depth_texture(&self, format: TexelFormat) -> DepthTexture {
+        let depths = self.depth_bits.iter().map(|&depth| Vector4::new(depth, 0.0, 0.0, 1.0));
+        DepthTexture::from_buffer(self.width, self.height, TexelBuffer::from_values(format, depths))
+    }
+
     pub fn regions<'a>(&'a mut self, region_width: usize, region_height: usize) -> Vec<Vec<RegionBuffer<'a>>> {
         let width = self.width;
         let height = self.height;
 
-        let count_x = if width % region_width != 0 {
-            width / region_width + 1
-        } else {
-            width / region_width
-        };
-        let count_y = if self.height % region_height != 0 {
-            height / region_height + 1
-        } else {
-            height / region_height
-        };
+        let count_x = width.div_ceil(region_width);
+        let count_y = height.div_ceil(region_height);
 
         let mut regions = vec![vec![RegionBuffer::without_dimensions(self); count_x]; count_y];
 
-        for x in 0..count_x {
-            for y in 0..count_y {
-                regions[y][x].from_x = x * region_width;
-                regions[y][x].from_y = y * region_height;
-                regions[y][x].height = region_height;
-                regions[y][x].width = region_height;
-            }
-            if height % region_height != 0 {
-                regions[count_y - 1][x].height = height % region_height;
-            }
-        }
-
-        if width % region_width != 0 {
-            for y in 0..count_y {
-                regions[y][count_x - 1].width = width % region_width;
+        for (y, row) in regions.iter_mut().enumerate() {
+            for (x, region) in row.iter_mut().enumerate() {
+                region.from_x = x * region_width;
+                region.from_y = y * region_height;
+                region.width = region_width.min(width - region.from_x);
+                region.height = region_height.min(height - region.from_y);
             }
         }
 
@@ -102,12 +339,25 @@ impl Framebuffer {
     }
 }
 
+/// Packs a plane for display, sRGB planes already hold encoded bytes.
+fn present(plane: &TexelBuffer) -> Vec<u32> {
+    if plane.format().is_srgb() {
+        plane
+            .bytes()
+            .chunks_exact(4)
+            .map(|rgba| (rgba[0] as u32) << 16 | (rgba[1] as u32) << 8 | rgba[2] as u32)
+            .collect()
+    } else {
+        (0..plane.len()).map(|index| Color::from(plane.color(index)).to_u32()).collect()
+    }
+}
+
 impl Texture for Framebuffer {
     fn sample(&self, uv: Vector2) -> Vector3 {
-        let x = (uv.x * (self.width as f64 - 1.0)) as usize;
-        let y = (uv.y * (self.height as f64 - 1.0)) as usize;
+        let x = (clamp(uv.x, 0.0, 1.0) * (self.width as Scalar - 1.0)) as usize;
+        let y = (clamp(uv.y, 0.0, 1.0) * (self.height as Scalar - 1.0)) as usize;
         let index = self.pos_to_index(x, y);
-        self.colors[index]
+        self.colors.color(index)
     }
 }
 
@@ -134,13 +384,91 @@ impl<'a> RegionBuffer<'a> {
         }
     }
 
-    pub fn set_color(&mut self, x: usize, y: usize, color: Vector3, depth: f64) {
-        if x >= self.from_x && x < self.from_x + self.width && y >= self.from_y && y < self.from_y + self.height {
+    fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.from_x && x < self.from_x + self.width && y >= self.from_y && y < self.from_y + self.height
+    }
+
+    pub fn set_color(&mut self, x: usize, y: usize, color: Vector3, depth: Scalar) {
+        if self.contains(x, y) {
             unsafe {
                 (*self.framebuffer).set_color(x, y, color, depth);
             }
         }
     }
+
+    pub fn overwrite_color(&mut self, x: usize, y: usize, color: Vector3) {
+        if self.contains(x, y) {
+            unsafe {
+                (*self.framebuffer).overwrite_color(x, y, color);
+            }
+        }
+    }
+
+    pub fn overwrite_id(&mut self, x: usize, y: usize, id: u32) {
+        if self.contains(x, y) {
+            unsafe {
+                (*self.framebuffer).overwrite_id(x, y, id);
+            }
+        }
+    }
+
+    pub fn set_targets(&mut self, x: usize, y: usize, values: &[Vector4], depth: Scalar) {
+        if self.contains(x, y) {
+            unsafe {
+                (*self.framebuffer).set_targets(x, y, values, depth);
+            }
+        }
+    }
+
+    pub fn overwrite_targets(&mut self, x: usize, y: usize, values: &[Vector4]) {
+        if self.contains(x, y) {
+            unsafe {
+                (*self.framebuffer).overwrite_targets(x, y, values);
+            }
+        }
+    }
+
+    pub fn set_depth(&mut self, x: usize, y: usize, depth: Scalar) {
+        if self.contains(x, y) {
+            unsafe {
+                (*self.framebuffer).set_depth(x, y, depth);
+            }
+        }
+    }
+
+    pub fn has_gbuffer(&self) -> bool {
+        unsafe { (*self.framebuffer).has_gbuffer() }
+    }
+
+    pub fn target_count(&self) -> usize {
+        unsafe { (*self.framebuffer).targets().len() }
+    }
+
+    pub fn gbuffer(&self, x: usize, y: usize) -> GBufferSample {
+        if self.contains(x, y) {
+            unsafe { (*self.framebuffer).gbuffer(x, y) }
+        } else {
+            GBufferSample::default()
+        }
+    }
+
+    pub fn set_gbuffer(&mut self, x: usize, y: usize, sample: GBufferSample) {
+        if self.contains(x, y) {
+            unsafe {
+                (*self.framebuffer).set_gbuffer(x, y, sample);
+            }
+        }
+    }
+
+    /// Depth stored at `(x, y)`, positions outside of the region are reported as
+    /// infinitely close so that nothing is ever drawn there.
+    pub fn depth(&self, x: usize, y: usize) -> Scalar {
+        if self.contains(x, y) {
+            unsafe { (*self.framebuffer).depth(x, y) }
+        } else {
+            Scalar::NEG_INFINITY
+        }
+    }
 }
 
 unsafe impl<'a> Send for RegionBuffer<'a> {}
\ No newline at end of file
@@ -0,0 +1,390 @@
+use crate::approx::{ApproxEq, Tolerance};
+use crate::matrix::Matrix4;
+use crate::scalar::Scalar;
+use crate::vector::{Vector2, Vector3, Vector4};
+
+/// Axis aligned bounding box. `Aabb::empty` has `min` above `max` and contains nothing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Aabb {
+    pub min: Vector3,
+    pub max: Vector3,
+}
+
+impl Aabb {
+    pub fn new(min: Vector3, max: Vector3) -> Self {
+        Self { min, max }
+    }
+
+    /// Neutral element of `union` and `grow`.
+    pub fn empty() -> Self {
+        Self::new(Vector3::splat(Scalar::INFINITY), Vector3::splat(Scalar::NEG_INFINITY))
+    }
+
+    pub fn from_points<I: IntoIterator<Item = Vector3>>(points: I) -> Self {
+        points.into_iter().fold(Self::empty(), Self::grow)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn center(&self) -> Vector3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Half of the size along every axis.
+    pub fn half_extents(&self) -> Vector3 {
+        (self.max - self.min) * 0.5
+    }
+
+    pub fn grow(self, point: Vector3) -> Self {
+        Self::new(self.min.min(point), self.max.max(point))
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    pub fn contains(&self, point: Vector3) -> bool {
+        point.x >= self.min.x && point.x <= self.max.x
+            && point.y >= self.min.y && point.y <= self.max.y
+            && point.z >= self.min.z && point.z <= self.max.z
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x <= other.max.x && self.max.x >= other.min.x
+            && self.min.y <= other.max.y && self.max.y >= other.min.y
+            && self.min.z <= other.max.z && self.max.z >= other.min.z
+    }
+
+    /// Box around the transformed box, for affine matrices.
+    pub fn transform(&self, matrix: &Matrix4) -> Self {
+        let center = matrix.transform_point(self.center());
+        let extents = self.half_extents();
+        // every axis of the result gathers the absolute contributions of the old ones
+        let half = Vector3::new(
+            matrix.m00.abs() * extents.x + matrix.m01.abs() * extents.y + matrix.m02.abs() * extents.z,
+            matrix.m10.abs() * extents.x + matrix.m11.abs() * extents.y + matrix.m12.abs() * extents.z,
+            matrix.m20.abs() * extents.x + matrix.m21.abs() * extents.y + matrix.m22.abs() * extents.z,
+        );
+        Self::new(center - half, center + half)
+    }
+}
+
+impl ApproxEq for Aabb {
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance) -> bool {
+        self.min.approx_eq(&other.min, tolerance) && self.max.approx_eq(&other.max, tolerance)
+    }
+}
+
+/// Plane of the points `p` with `normal * p + distance = 0`. Points with a positive
+/// signed distance are in front of it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Plane {
+    pub normal: Vector3,
+    pub distance: Scalar,
+}
+
+impl Plane {
+    pub fn new(normal: Vector3, distance: Scalar) -> Self {
+        Self { normal, distance }
+    }
+
+    pub fn from_point_normal(point: Vector3, normal: Vector3) -> Self {
+        let normal = normal.normalize();
+        Self::new(normal, -(normal * point))
+    }
+
+    /// Plane through three points, in front of it they go around counter-clockwise.
+    pub fn from_points(a: Vector3, b: Vector3, c: Vector3) -> Self {
+        Self::from_point_normal(a, (b - a).cross(c - a))
+    }
+
+    /// Scales the equation so that the normal has length 1 and distances are true distances.
+    pub fn normalize(self) -> Self {
+        let length = self.normal.length();
+        Self::new(self.normal * (1.0 / length), self.distance / length)
+    }
+
+    /// `normalize`, leaving degenerate planes like the far one of infinite projections alone.
+    fn normalize_or_keep(self) -> Self {
+        if self.normal.length_squared() > 0.0 {
+            self.normalize()
+        } else {
+            self
+        }
+    }
+
+    pub fn signed_distance(&self, point: Vector3) -> Scalar {
+        self.normal * point + self.distance
+    }
+}
+
+impl ApproxEq for Plane {
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance) -> bool {
+        self.normal.approx_eq(&other.normal, tolerance) && self.distance.approx_eq(&other.distance, tolerance)
+    }
+}
+
+/// Half-line from `origin` along `direction`, which does not have to be normalized.
+/// Distances `t` along it are in multiples of `direction`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ray {
+    pub origin: Vector3,
+    pub direction: Vector3,
+}
+
+impl Ray {
+    pub fn new(origin: Vector3, direction: Vector3) -> Self {
+        Self { origin, direction }
+    }
+
+    /// Ray through the screen position `screen`, in the `[0, 1]` coordinates vertex shaders
+    /// output, starting at the near plane. `to_screen` is the matrix the vertex shader
+    /// applies, e.g. `Matrix4::viewport() * projection * view`. `None` if the matrix is
+    /// singular.
+    ///
+    /// Meant for picking on the CPU with the `intersect_*` methods, e.g. against bounds of
+    /// objects that were never rendered. `Framebuffer::pick` reads the id plane of a rendered
+    /// frame instead and needs no ray.
+    pub fn from_screen(to_screen: &Matrix4, screen: Vector2) -> Option<Self> {
+        let inverse = to_screen.inverse()?;
+        let unproject = |depth: Scalar| inverse.transform_point(Vector3::new(screen.x, screen.y, depth));
+        // depths strictly inside are finite for infinite and reversed projections as well,
+        // w of the projected points grows with the distance from the camera
+        let (a, b) = (unproject(0.25), unproject(0.75));
+        let w = |p: Vector3| (to_screen * p.extend(1.0)).w;
+        let reversed = w(a) > w(b);
+        let origin = unproject(if reversed { 1.0 } else { 0.0 });
+        let direction = if reversed { a - b } else { b - a }.normalize();
+
+        let finite = |v: Vector3| v.x.is_finite() && v.y.is_finite() && v.z.is_finite();
+        if finite(origin) && finite(direction) {
+            Some(Self::new(origin, direction))
+        } else {
+            None
+        }
+    }
+
+    pub fn at(&self, t: Scalar) -> Vector3 {
+        self.origin + self.direction * t
+    }
+
+    /// Distance to the plane, `None` if the ray is parallel to it or points away.
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<Scalar> {
+        let denominator = plane.normal * self.direction;
+        if denominator == 0.0 {
+            return None;
+        }
+        let t = -plane.signed_distance(self.origin) / denominator;
+        if t >= 0.0 {
+            Some(t)
+        } else {
+            None
+        }
+    }
+
+    /// Distance and barycentric weights `(u, v)` of `b` and `c` of the hit, both sides of the
+    /// triangle count. Möller-Trumbore.
+    pub fn intersect_triangle(&self, a: Vector3, b: Vector3, c: Vector3) -> Option<(Scalar, Scalar, Scalar)> {
+        let edge0 = b - a;
+        let edge1 = c - a;
+        let p = self.direction.cross(edge1);
+        let determinant = edge0 * p;
+        if determinant == 0.0 {
+            return None;
+        }
+        let inv = 1.0 / determinant;
+        let s = self.origin - a;
+        let u = (s * p) * inv;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(edge0);
+        let v = (self.direction * q) * inv;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = (edge1 * q) * inv;
+        if t >= 0.0 {
+            Some((t, u, v))
+        } else {
+            None
+        }
+    }
+
+    /// Range of distances inside the box, the ray starting inside gives 0 as the entry.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<(Scalar, Scalar)> {
+        let inv = Vector3::splat(1.0).component_div(self.direction);
+        let t0 = (aabb.min - self.origin).component_mul(inv);
+        let t1 = (aabb.max - self.origin).component_mul(inv);
+        let near = t0.min(t1);
+        let far = t0.max(t1);
+        let entry = near.x.max(near.y).max(near.z).max(0.0);
+        let exit = far.x.min(far.y).min(far.z);
+        if entry <= exit {
+            Some((entry, exit))
+        } else {
+            None
+        }
+    }
+
+    /// Distance to the first hit with the surface of the sphere, the far side if the ray
+    /// starts inside.
+    pub fn intersect_sphere(&self, sphere: &Sphere) -> Option<Scalar> {
+        let offset = self.origin - sphere.center;
+        let a = self.direction * self.direction;
+        let b = offset * self.direction;
+        let c = offset * offset - sphere.radius * sphere.radius;
+        let discriminant = b * b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        let near = (-b - root) / a;
+        let far = (-b + root) / a;
+        if near >= 0.0 {
+            Some(near)
+        } else if far >= 0.0 {
+            Some(far)
+        } else {
+            None
+        }
+    }
+}
+
+impl ApproxEq for Ray {
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance) -> bool {
+        self.origin.approx_eq(&other.origin, tolerance) && self.direction.approx_eq(&other.direction, tolerance)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sphere {
+    pub center: Vector3,
+    pub radius: Scalar,
+}
+
+impl Sphere {
+    pub fn new(center: Vector3, radius: Scalar) -> Self {
+        Self { center, radius }
+    }
+
+    /// Sphere around the box, not the smallest one around its content.
+    pub fn from_aabb(aabb: &Aabb) -> Self {
+        Self::new(aabb.center(), aabb.half_extents().length())
+    }
+
+    pub fn contains(&self, point: Vector3) -> bool {
+        (point - self.center).length_squared() <= self.radius * self.radius
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        let radius = self.radius + other.radius;
+        (other.center - self.center).length_squared() <= radius * radius
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        let closest = self.center.clamp(aabb.min, aabb.max);
+        (closest - self.center).length_squared() <= self.radius * self.radius
+    }
+}
+
+impl ApproxEq for Sphere {
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance) -> bool {
+        self.center.approx_eq(&other.center, tolerance) && self.radius.approx_eq(&other.radius, tolerance)
+    }
+}
+
+/// Where a volume lies relative to a frustum.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Containment {
+    Outside,
+    Intersecting,
+    Inside,
+}
+
+/// Volume seen through a projection, bounded by six planes facing inwards.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Frustum {
+    /// Left, right, bottom, top, near and far.
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Planes of the clip space volume of `view_projection`, `-w <= x, y <= w` and
+    /// `0 <= z <= w`, in the space the matrix is applied to. Works with every projection
+    /// of `Matrix4`; the far plane of an infinite one never culls anything.
+    pub fn from_matrix(view_projection: &Matrix4) -> Self {
+        let [r0, r1, r2, r3] = view_projection.as_rows();
+        let plane = |row: Vector4| Plane::new(Vector3::new(row.x, row.y, row.z), row.w).normalize_or_keep();
+        Self {
+            planes: [
+                plane(r3 + r0),
+                plane(r3 - r0),
+                plane(r3 + r1),
+                plane(r3 - r1),
+                plane(r2),
+                plane(r3 - r2),
+            ],
+        }
+    }
+
+    pub fn contains(&self, point: Vector3) -> bool {
+        self.planes.iter().all(|plane| plane.signed_distance(point) >= 0.0)
+    }
+
+    /// Conservative, spheres near the corners can be `Intersecting` while just outside.
+    pub fn classify_sphere(&self, sphere: &Sphere) -> Containment {
+        let mut containment = Containment::Inside;
+        for plane in self.planes.iter() {
+            let distance = plane.signed_distance(sphere.center);
+            if distance < -sphere.radius {
+                return Containment::Outside;
+            }
+            if distance < sphere.radius {
+                containment = Containment::Intersecting;
+            }
+        }
+        containment
+    }
+
+    /// Conservative like `classify_sphere`.
+    pub fn classify_aabb(&self, aabb: &Aabb) -> Containment {
+        let center = aabb.center();
+        let extents = aabb.half_extents();
+        let mut containment = Containment::Inside;
+        for plane in self.planes.iter() {
+            // projected radius of the box onto the normal
+            let radius = extents * plane.normal.abs();
+            let distance = plane.signed_distance(center);
+            if distance < -radius {
+                return Containment::Outside;
+            }
+            if distance < radius {
+                containment = Containment::Intersecting;
+            }
+        }
+        containment
+    }
+
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.classify_sphere(sphere) != Containment::Outside
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.classify_aabb(aabb) != Containment::Outside
+    }
+}
+
+impl ApproxEq for Frustum {
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance) -> bool {
+        self.planes.approx_eq(&other.planes, tolerance)
+    }
+}
@@ -0,0 +1,568 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::format::{srgb_to_linear, TexelBuffer, TexelFormat};
+use crate::scalar::Scalar;
+use crate::texture::ImageTexture;
+use crate::vector::{Vector3, Vector4};
+
+/// Error returned when an image can not be read or decoded.
+#[derive(Debug)]
+pub enum ImageError {
+    Io(io::Error),
+    /// The data does not start with the signature of any supported format.
+    UnknownFormat,
+    /// The data ends before the image is complete.
+    UnexpectedEnd,
+    /// The header is malformed, e.g. it has zero dimensions or an invalid field.
+    InvalidHeader(&'static str),
+    /// The pixel data is malformed, e.g. a value is out of range.
+    InvalidData(&'static str),
+    /// The file is valid but uses a variant of the format which is not implemented.
+    Unsupported(&'static str),
+    #[cfg(feature = "png")]
+    PngDecoding(png::DecodingError),
+    #[cfg(feature = "png")]
+    PngEncoding(png::EncodingError),
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImageError::Io(error) => write!(f, "io error: {}", error),
+            ImageError::UnknownFormat => write!(f, "unknown image format"),
+            ImageError::UnexpectedEnd => write!(f, "unexpected end of image data"),
+            ImageError::InvalidHeader(reason) => write!(f, "invalid image header: {}", reason),
+            ImageError::InvalidData(reason) => write!(f, "invalid image data: {}", reason),
+            ImageError::Unsupported(reason) => write!(f, "unsupported image: {}", reason),
+            #[cfg(feature = "png")]
+            ImageError::PngDecoding(error) => write!(f, "png decoding error: {}", error),
+            #[cfg(feature = "png")]
+            ImageError::PngEncoding(error) => write!(f, "png encoding error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImageError::Io(error) => Some(error),
+            #[cfg(feature = "png")]
+            ImageError::PngDecoding(error) => Some(error),
+            #[cfg(feature = "png")]
+            ImageError::PngEncoding(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ImageError {
+    fn from(error: io::Error) -> Self {
+        ImageError::Io(error)
+    }
+}
+
+#[cfg(feature = "png")]
+impl From<png::DecodingError> for ImageError {
+    fn from(error: png::DecodingError) -> Self {
+        match error {
+            png::DecodingError::IoError(error) if error.kind() == io::ErrorKind::UnexpectedEof => ImageError::UnexpectedEnd,
+            png::DecodingError::IoError(error) => ImageError::Io(error),
+            error => ImageError::PngDecoding(error),
+        }
+    }
+}
+
+#[cfg(feature = "png")]
+impl From<png::EncodingError> for ImageError {
+    fn from(error: png::EncodingError) -> Self {
+        match error {
+            png::EncodingError::IoError(error) => ImageError::Io(error),
+            error => ImageError::PngEncoding(error),
+        }
+    }
+}
+
+/// Decoded image, rows stored top to bottom and channels normalized to `[0, 1]`.
+/// Formats without alpha are loaded as opaque.
+#[derive(Clone, Debug)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Vector4>,
+}
+
+impl Image {
+    pub fn pixel(&self, x: usize, y: usize) -> Vector4 {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Texture of the color channels, alpha is dropped.
+    pub fn to_texture(&self) -> ImageTexture {
+        let texels = self.pixels.iter().map(|p| Vector3::new(p.x, p.y, p.z)).collect();
+        ImageTexture::new(self.width, self.height, texels)
+    }
+
+    /// Texture keeping the values as sRGB encoded bytes, which is how 8-bit images are
+    /// usually authored. Sampling and mipmapping then work on linear colors.
+    pub fn to_srgb_texture(&self) -> ImageTexture {
+        let linear = self.pixels.iter().map(|p| Vector4::new(srgb_to_linear(p.x), srgb_to_linear(p.y), srgb_to_linear(p.z), p.w));
+        ImageTexture::from_buffer(self.width, self.height, TexelBuffer::from_values(TexelFormat::Rgba8Srgb, linear))
+    }
+}
+
+/// Reads and decodes the image at `path`, the format is detected from its contents.
+pub fn load(path: impl AsRef<Path>) -> Result<Image, ImageError> {
+    decode(&fs::read(path)?)
+}
+
+/// Reads the image at `path` straight into a texture.
+pub fn load_texture(path: impl AsRef<Path>) -> Result<ImageTexture, ImageError> {
+    load(path).map(|image| image.to_texture())
+}
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Decodes a PNG, PPM/PGM, BMP or TGA image. TGA has no signature, so it is tried last.
+pub fn decode(bytes: &[u8]) -> Result<Image, ImageError> {
+    match bytes {
+        #[cfg(feature = "png")]
+        _ if bytes.starts_with(&PNG_SIGNATURE) => decode_png(bytes),
+        #[cfg(not(feature = "png"))]
+        _ if bytes.starts_with(&PNG_SIGNATURE) => Err(ImageError::Unsupported("PNG support requires the png feature")),
+        [b'P', b'2'..=b'3', ..] | [b'P', b'5'..=b'6', ..] => decode_pnm(bytes),
+        [b'P', b'1', ..] | [b'P', b'4', ..] => Err(ImageError::Unsupported("PBM bitmaps")),
+        [b'B', b'M', ..] => decode_bmp(bytes),
+        _ if looks_like_tga(bytes) => decode_tga(bytes),
+        _ => Err(ImageError::UnknownFormat),
+    }
+}
+
+fn check_dimensions(width: usize, height: usize) -> Result<(), ImageError> {
+    if width == 0 || height == 0 {
+        return Err(ImageError::InvalidHeader("zero width or height"));
+    }
+    match width.checked_mul(height) {
+        Some(len) if len <= isize::MAX as usize / 32 => Ok(()),
+        _ => Err(ImageError::InvalidHeader("dimensions too large")),
+    }
+}
+
+/// Little endian reader over a byte slice.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn seek(&mut self, position: usize) -> Result<(), ImageError> {
+        if position > self.bytes.len() {
+            return Err(ImageError::UnexpectedEnd);
+        }
+        self.position = position;
+        Ok(())
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ImageError> {
+        let end = self.position.checked_add(len).ok_or(ImageError::UnexpectedEnd)?;
+        let slice = self.bytes.get(self.position..end).ok_or(ImageError::UnexpectedEnd)?;
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, ImageError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ImageError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, ImageError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn i32(&mut self) -> Result<i32, ImageError> {
+        Ok(self.u32()? as i32)
+    }
+}
+
+fn unorm8(value: u8) -> Scalar {
+    value as Scalar / 255.0
+}
+
+fn unorm5(value: u16) -> Scalar {
+    (value & 0x1f) as Scalar / 31.0
+}
+
+/// Decodes binary (`P5`, `P6`) and ASCII (`P2`, `P3`) PGM/PPM images, 8 or 16 bits per sample.
+pub fn decode_pnm(bytes: &[u8]) -> Result<Image, ImageError> {
+    let (ascii, channels) = match bytes {
+        [b'P', b'2', ..] => (true, 1),
+        [b'P', b'3', ..] => (true, 3),
+        [b'P', b'5', ..] => (false, 1),
+        [b'P', b'6', ..] => (false, 3),
+        _ => return Err(ImageError::UnknownFormat),
+    };
+    let mut position = 2;
+
+    // skips whitespace and comments, then reads an unsigned decimal number
+    let number = |position: &mut usize| -> Result<u32, ImageError> {
+        loop {
+            match bytes.get(*position) {
+                Some(b'#') => {
+                    while bytes.get(*position).is_some_and(|&b| b != b'\n' && b != b'\r') {
+                        *position += 1;
+                    }
+                }
+                Some(b) if b.is_ascii_whitespace() => *position += 1,
+                Some(_) => break,
+                None => return Err(ImageError::UnexpectedEnd),
+            }
+        }
+        let start = *position;
+        let mut value: u32 = 0;
+        while let Some(digit) = bytes.get(*position).filter(|b| b.is_ascii_digit()) {
+            value = value
+                .checked_mul(10)
+                .and_then(|v| v.checked_add((digit - b'0') as u32))
+                .ok_or(ImageError::InvalidData("number out of range"))?;
+            *position += 1;
+        }
+        if start == *position {
+            return Err(ImageError::InvalidData("expected a number"));
+        }
+        Ok(value)
+    };
+
+    let width = number(&mut position)? as usize;
+    let height = number(&mut position)? as usize;
+    let max_value = number(&mut position)?;
+    check_dimensions(width, height)?;
+    if max_value == 0 || max_value > 65535 {
+        return Err(ImageError::InvalidHeader("maximum value has to be in 1..=65535"));
+    }
+
+    let samples = width * height * channels;
+    let mut values = Vec::with_capacity(samples.min(bytes.len()));
+    if ascii {
+        for _ in 0..samples {
+            values.push(number(&mut position)?);
+        }
+    } else {
+        // exactly one whitespace character separates the header from the raster
+        if !bytes.get(position).is_some_and(|b| b.is_ascii_whitespace()) {
+            return Err(ImageError::InvalidHeader("missing whitespace after header"));
+        }
+        let mut reader = Reader::new(bytes);
+        reader.seek(position + 1)?;
+        let wide = max_value > 255;
+        for _ in 0..samples {
+            values.push(if wide {
+                let pair = reader.take(2)?;
+                u16::from_be_bytes([pair[0], pair[1]]) as u32
+            } else {
+                reader.u8()? as u32
+            });
+        }
+    }
+    if values.iter().any(|&v| v > max_value) {
+        return Err(ImageError::InvalidData("sample larger than maximum value"));
+    }
+
+    let scale = 1.0 / max_value as Scalar;
+    let pixels = values
+        .chunks(channels)
+        .map(|c| match c {
+            [l] => Vector4::new(*l as Scalar * scale, *l as Scalar * scale, *l as Scalar * scale, 1.0),
+            _ => Vector4::new(c[0] as Scalar * scale, c[1] as Scalar * scale, c[2] as Scalar * scale, 1.0),
+        })
+        .collect();
+    Ok(Image { width, height, pixels })
+}
+
+fn looks_like_tga(bytes: &[u8]) -> bool {
+    bytes.len() >= 18
+        && bytes[1] <= 1
+        && matches!(bytes[2], 1 | 2 | 3 | 9 | 10 | 11)
+        && matches!(bytes[16], 8 | 15 | 16 | 24 | 32)
+}
+
+/// Decodes a TGA pixel of `depth` bits per pixel stored in BGR(A) order.
+fn tga_color(bytes: &[u8], depth: u8) -> Vector4 {
+    match depth {
+        8 => Vector4::new(unorm8(bytes[0]), unorm8(bytes[0]), unorm8(bytes[0]), 1.0),
+        15 | 16 => {
+            let value = u16::from_le_bytes([bytes[0], bytes[1]]);
+            Vector4::new(unorm5(value >> 10), unorm5(value >> 5), unorm5(value), 1.0)
+        }
+        24 => Vector4::new(unorm8(bytes[2]), unorm8(bytes[1]), unorm8(bytes[0]), 1.0),
+        _ => Vector4::new(unorm8(bytes[2]), unorm8(bytes[1]), unorm8(bytes[0]), unorm8(bytes[3])),
+    }
+}
+
+/// Decodes uncompressed and run-length encoded TGA images, true color, grayscale or color mapped.
+pub fn decode_tga(bytes: &[u8]) -> Result<Image, ImageError> {
+    let mut reader = Reader::new(bytes);
+    let id_length = reader.u8()? as usize;
+    let color_map_type = reader.u8()?;
+    let image_type = reader.u8()?;
+    let color_map_first = reader.u16()? as usize;
+    let color_map_length = reader.u16()? as usize;
+    let color_map_depth = reader.u8()?;
+    let _origin = reader.take(4)?;
+    let width = reader.u16()? as usize;
+    let height = reader.u16()? as usize;
+    let depth = reader.u8()?;
+    let descriptor = reader.u8()?;
+    check_dimensions(width, height)?;
+
+    let (mapped, grayscale, rle) = match image_type {
+        1 => (true, false, false),
+        2 => (false, false, false),
+        3 => (false, true, false),
+        9 => (true, false, true),
+        10 => (false, false, true),
+        11 => (false, true, true),
+        0 => return Err(ImageError::InvalidHeader("image without data")),
+        _ => return Err(ImageError::Unsupported("TGA image type")),
+    };
+    if color_map_type > 1 || (mapped && color_map_type == 0) {
+        return Err(ImageError::InvalidHeader("invalid color map type"));
+    }
+    let valid_depth = match (mapped, grayscale) {
+        (true, _) => depth == 8 || depth == 16,
+        (false, true) => depth == 8,
+        (false, false) => matches!(depth, 15 | 16 | 24 | 32),
+    };
+    if !valid_depth {
+        return Err(ImageError::InvalidHeader("invalid pixel depth for the image type"));
+    }
+    reader.take(id_length)?;
+
+    let mut palette = Vec::new();
+    if color_map_type == 1 {
+        if !matches!(color_map_depth, 15 | 16 | 24 | 32) {
+            return Err(ImageError::InvalidHeader("invalid color map depth"));
+        }
+        let entry_size = (color_map_depth as usize).div_ceil(8);
+        for _ in 0..color_map_length {
+            palette.push(tga_color(reader.take(entry_size)?, color_map_depth));
+        }
+    }
+
+    let pixel_size = (depth as usize).div_ceil(8);
+    let color = |bytes: &[u8]| -> Result<Vector4, ImageError> {
+        if mapped {
+            let index = if pixel_size == 1 { bytes[0] as usize } else { u16::from_le_bytes([bytes[0], bytes[1]]) as usize };
+            index
+                .checked_sub(color_map_first)
+                .and_then(|i| palette.get(i).copied())
+                .ok_or(ImageError::InvalidData("color map index out of range"))
+        } else {
+            Ok(tga_color(bytes, depth))
+        }
+    };
+
+    let len = width * height;
+    let mut pixels = Vec::with_capacity(len.min(bytes.len()));
+    if rle {
+        while pixels.len() < len {
+            let packet = reader.u8()?;
+            let count = (packet & 0x7f) as usize + 1;
+            if count > len - pixels.len() {
+                return Err(ImageError::InvalidData("run crosses the end of the image"));
+            }
+            if packet & 0x80 != 0 {
+                let value = color(reader.take(pixel_size)?)?;
+                pixels.resize(pixels.len() + count, value);
+            } else {
+                for _ in 0..count {
+                    pixels.push(color(reader.take(pixel_size)?)?);
+                }
+            }
+        }
+    } else {
+        for _ in 0..len {
+            pixels.push(color(reader.take(pixel_size)?)?);
+        }
+    }
+
+    // rows are stored bottom to top unless bit 5 is set, right to left if bit 4 is set
+    if descriptor & 0x10 != 0 {
+        for row in pixels.chunks_mut(width) {
+            row.reverse();
+        }
+    }
+    if descriptor & 0x20 == 0 {
+        flip_rows(&mut pixels, width);
+    }
+    Ok(Image { width, height, pixels })
+}
+
+fn flip_rows(pixels: &mut [Vector4], width: usize) {
+    let height = pixels.len() / width;
+    for y in 0..height / 2 {
+        let (top, bottom) = pixels.split_at_mut((height - 1 - y) * width);
+        top[y * width..(y + 1) * width].swap_with_slice(&mut bottom[..width]);
+    }
+}
+
+/// Extracts the channel selected by `mask` and normalizes it to `[0, 1]`.
+fn bmp_channel(value: u32, mask: u32) -> Scalar {
+    if mask == 0 {
+        return 0.0;
+    }
+    let max = mask >> mask.trailing_zeros();
+    ((value & mask) >> mask.trailing_zeros()) as Scalar / max as Scalar
+}
+
+/// Decodes uncompressed 24 and 32-bit BMP images, including 32-bit images with channel bit masks.
+pub fn decode_bmp(bytes: &[u8]) -> Result<Image, ImageError> {
+    let mut reader = Reader::new(bytes);
+    if reader.take(2)? != b"BM" {
+        return Err(ImageError::UnknownFormat);
+    }
+    reader.seek(10)?;
+    let data_offset = reader.u32()? as usize;
+    let header_size = reader.u32()?;
+
+    let (width, height, bits, compression) = if header_size == 12 {
+        let width = reader.u16()? as i32;
+        let height = reader.u16()? as i32;
+        let _planes = reader.u16()?;
+        (width, height, reader.u16()?, 0)
+    } else if header_size >= 40 {
+        let width = reader.i32()?;
+        let height = reader.i32()?;
+        let _planes = reader.u16()?;
+        let bits = reader.u16()?;
+        (width, height, bits, reader.u32()?)
+    } else {
+        return Err(ImageError::InvalidHeader("unknown info header size"));
+    };
+    if width <= 0 || height == 0 || height == i32::MIN {
+        return Err(ImageError::InvalidHeader("invalid dimensions"));
+    }
+    let top_down = height < 0;
+    let (width, height) = (width as usize, height.unsigned_abs() as usize);
+    check_dimensions(width, height)?;
+
+    if bits != 24 && bits != 32 {
+        return Err(ImageError::Unsupported("only 24 and 32-bit BMP images are supported"));
+    }
+    // masks follow the 40 byte header for BI_BITFIELDS, later headers embed them
+    let masks = match compression {
+        0 if bits == 32 => [0x00ff_0000, 0x0000_ff00, 0x0000_00ff, 0],
+        0 => [0; 4],
+        3 | 6 if bits == 32 => {
+            reader.seek(14 + 40)?;
+            let mut masks = [reader.u32()?, reader.u32()?, reader.u32()?, 0];
+            if compression == 6 || header_size >= 56 {
+                masks[3] = reader.u32()?;
+            }
+            masks
+        }
+        1 | 2 => return Err(ImageError::Unsupported("run-length encoded BMP")),
+        _ => return Err(ImageError::Unsupported("BMP compression method")),
+    };
+
+    let stride = (width * bits as usize / 8).div_ceil(4) * 4;
+    reader.seek(data_offset)?;
+    let mut pixels = Vec::with_capacity((width * height).min(bytes.len()));
+    for _ in 0..height {
+        let row = reader.take(stride)?;
+        for x in 0..width {
+            pixels.push(if bits == 24 {
+                let p = &row[x * 3..x * 3 + 3];
+                Vector4::new(unorm8(p[2]), unorm8(p[1]), unorm8(p[0]), 1.0)
+            } else {
+                let p = &row[x * 4..x * 4 + 4];
+                let value = u32::from_le_bytes([p[0], p[1], p[2], p[3]]);
+                let alpha = if masks[3] == 0 { 1.0 } else { bmp_channel(value, masks[3]) };
+                Vector4::new(bmp_channel(value, masks[0]), bmp_channel(value, masks[1]), bmp_channel(value, masks[2]), alpha)
+            });
+        }
+    }
+    if !top_down {
+        flip_rows(&mut pixels, width);
+    }
+    Ok(Image { width, height, pixels })
+}
+
+/// Decodes a PNG image of any bit depth and color type, palettes and transparency chunks
+/// are expanded and interlaced images deinterlaced.
+#[cfg(feature = "png")]
+pub fn decode_png(bytes: &[u8]) -> Result<Image, ImageError> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    let (width, height) = (info.width as usize, info.height as usize);
+    check_dimensions(width, height)?;
+
+    let channels = match info.color_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::Rgb => 3,
+        png::ColorType::Rgba => 4,
+        png::ColorType::Indexed => return Err(ImageError::Unsupported("unexpanded PNG palette")),
+    };
+    let samples: Vec<Scalar> = match info.bit_depth {
+        png::BitDepth::Eight => buffer[..info.line_size * height].iter().map(|&b| unorm8(b)).collect(),
+        png::BitDepth::Sixteen => buffer[..info.line_size * height]
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]) as Scalar / 65535.0)
+            .collect(),
+        _ => return Err(ImageError::Unsupported("unexpanded PNG bit depth")),
+    };
+
+    let pixels = samples
+        .chunks_exact(channels)
+        .map(|c| match *c {
+            [l] => Vector4::new(l, l, l, 1.0),
+            [l, a] => Vector4::new(l, l, l, a),
+            [r, g, b] => Vector4::new(r, g, b, 1.0),
+            [r, g, b, a] => Vector4::new(r, g, b, a),
+            _ => unreachable!(),
+        })
+        .collect();
+    Ok(Image { width, height, pixels })
+}
+
+/// Encodes the image as an 8-bit PNG, alpha is only stored when some pixel is not opaque.
+#[cfg(feature = "png")]
+pub fn encode_png(image: &Image, writer: impl io::Write) -> Result<(), ImageError> {
+    let opaque = image.pixels.iter().all(|p| p.w >= 1.0);
+    let mut encoder = png::Encoder::new(writer, image.width as u32, image.height as u32);
+    encoder.set_color(if opaque { png::ColorType::Rgb } else { png::ColorType::Rgba });
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let quantize = |c: Scalar| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let mut data = Vec::with_capacity(image.pixels.len() * 4);
+    for p in image.pixels.iter() {
+        data.extend_from_slice(&[quantize(p.x), quantize(p.y), quantize(p.z)]);
+        if !opaque {
+            data.push(quantize(p.w));
+        }
+    }
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+    Ok(())
+}
+
+#[cfg(feature = "png")]
+pub fn save_png(image: &Image, path: impl AsRef<Path>) -> Result<(), ImageError> {
+    let file = io::BufWriter::new(fs::File::create(path)?);
+    encode_png(image, file)
+}
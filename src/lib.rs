@@ -0,0 +1,21 @@
+#[macro_use]
+extern crate impl_ops;
+
+pub mod approx;
+pub mod atlas;
+pub mod color;
+pub mod cubemap;
+pub mod format;
+pub mod framebuffer;
+pub mod geometry;
+pub mod image;
+pub mod texture;
+pub mod utils;
+pub mod vector;
+pub mod matrix;
+pub mod procedural;
+pub mod renderer;
+pub mod scalar;
+pub mod shadow;
+pub mod simd;
+pub mod target;
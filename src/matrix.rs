@@ -1,29 +1,234 @@
-use std::ops;
+use std::{fmt, ops};
 
-use crate::vector::Vector4;
+use crate::approx::{ApproxEq, Tolerance};
+use crate::scalar::Scalar;
+use crate::vector::{Quaternion, Vector3, Vector4};
 
-#[derive(Default, Clone, Copy, Debug)]
+/// 4x4 matrix, `mRC` is the element in row `R` and column `C`.
+///
+/// Vectors are columns multiplied from the right, `M * v`, so `A * B` applies `B` first
+/// and translations are stored in the last column, `m03`, `m13` and `m23`.
+/// `Default` is the zero matrix.
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Matrix4 {
-    pub m00: f64,
-    pub m01: f64,
-    pub m02: f64,
-    pub m03: f64,
-    pub m10: f64,
-    pub m11: f64,
-    pub m12: f64,
-    pub m13: f64,
-    pub m20: f64,
-    pub m21: f64,
-    pub m22: f64,
-    pub m23: f64,
-    pub m30: f64,
-    pub m31: f64,
-    pub m32: f64,
-    pub m33: f64,
+    pub m00: Scalar,
+    pub m01: Scalar,
+    pub m02: Scalar,
+    pub m03: Scalar,
+    pub m10: Scalar,
+    pub m11: Scalar,
+    pub m12: Scalar,
+    pub m13: Scalar,
+    pub m20: Scalar,
+    pub m21: Scalar,
+    pub m22: Scalar,
+    pub m23: Scalar,
+    pub m30: Scalar,
+    pub m31: Scalar,
+    pub m32: Scalar,
+    pub m33: Scalar,
 }
 
 impl Matrix4 {
+    pub const fn identity() -> Self {
+        Self {
+            m00: 1.0, m01: 0.0, m02: 0.0, m03: 0.0,
+            m10: 0.0, m11: 1.0, m12: 0.0, m13: 0.0,
+            m20: 0.0, m21: 0.0, m22: 1.0, m23: 0.0,
+            m30: 0.0, m31: 0.0, m32: 0.0, m33: 1.0,
+        }
+    }
+
+    pub fn translation(offset: Vector3) -> Self {
+        Self {
+            m03: offset.x,
+            m13: offset.y,
+            m23: offset.z,
+            ..Self::identity()
+        }
+    }
+
+    pub fn scale(factors: Vector3) -> Self {
+        Self {
+            m00: factors.x,
+            m11: factors.y,
+            m22: factors.z,
+            ..Self::identity()
+        }
+    }
+
+    pub fn rotation_x(angle: Scalar) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self {
+            m11: cos, m12: -sin,
+            m21: sin, m22: cos,
+            ..Self::identity()
+        }
+    }
+
+    pub fn rotation_y(angle: Scalar) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self {
+            m00: cos, m02: sin,
+            m20: -sin, m22: cos,
+            ..Self::identity()
+        }
+    }
+
+    pub fn rotation_z(angle: Scalar) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self {
+            m00: cos, m01: -sin,
+            m10: sin, m11: cos,
+            ..Self::identity()
+        }
+    }
+
+    /// Rotation by `angle` radians around `axis`, which does not have to be normalized.
+    /// Same as `rotation_x` and friends for the coordinate axes.
+    pub fn rotation(axis: Vector3, angle: Scalar) -> Self {
+        let Vector3 { x, y, z } = axis.normalize();
+        let (sin, cos) = angle.sin_cos();
+        let t = 1.0 - cos;
+        Self {
+            m00: t * x * x + cos, m01: t * x * y - sin * z, m02: t * x * z + sin * y, m03: 0.0,
+            m10: t * x * y + sin * z, m11: t * y * y + cos, m12: t * y * z - sin * x, m13: 0.0,
+            m20: t * x * z - sin * y, m21: t * y * z + sin * x, m22: t * z * z + cos, m23: 0.0,
+            m30: 0.0, m31: 0.0, m32: 0.0, m33: 1.0,
+        }
+    }
+
+    /// View matrix of a camera at `eye` looking at `target`. In view space the camera
+    /// looks down +z with +y up and +x to the right, like the projections below expect.
+    pub fn look_at(eye: Vector3, target: Vector3, up: Vector3) -> Self {
+        let forward = (target - eye).normalize();
+        let right = up.cross(forward).normalize();
+        let up = forward.cross(right);
+        Self {
+            m00: right.x, m01: right.y, m02: right.z, m03: -(right * eye),
+            m10: up.x, m11: up.y, m12: up.z, m13: -(up * eye),
+            m20: forward.x, m21: forward.y, m22: forward.z, m23: -(forward * eye),
+            m30: 0.0, m31: 0.0, m32: 0.0, m33: 1.0,
+        }
+    }
+
+    // Projections map view space to clip space. After the division by w, x and y of
+    // the visible volume are in [-1, 1] and depth is in [0, 1], 0 at the near plane,
+    // which suits the rasterizer keeping the smallest depth. `viewport` then maps
+    // x and y to the [0, 1] screen coordinates vertex shaders output.
+
+    /// Perspective projection with a vertical field of view `fov_y` in radians and
+    /// `aspect` equal to width divided by height.
+    pub fn perspective(fov_y: Scalar, aspect: Scalar, near: Scalar, far: Scalar) -> Self {
+        let f = 1.0 / (fov_y * 0.5).tan();
+        Self {
+            m00: f / aspect,
+            m11: f,
+            m22: far / (far - near),
+            m23: -near * far / (far - near),
+            m32: 1.0,
+            ..Self::default()
+        }
+    }
+
+    /// `perspective` with the far plane at infinity.
+    pub fn perspective_infinite(fov_y: Scalar, aspect: Scalar, near: Scalar) -> Self {
+        let f = 1.0 / (fov_y * 0.5).tan();
+        Self {
+            m00: f / aspect,
+            m11: f,
+            m22: 1.0,
+            m23: -near,
+            m32: 1.0,
+            ..Self::default()
+        }
+    }
+
+    /// `perspective` with depth reversed, 1 at the near plane and 0 at the far one, which
+    /// spreads precision evenly when depth is stored as a float, e.g. in a `Depth32F`
+    /// shadow map. The framebuffer keeps the smallest depth, so shaders drawing with it
+    /// have to output `1 - depth`.
+    pub fn perspective_reverse_z(fov_y: Scalar, aspect: Scalar, near: Scalar, far: Scalar) -> Self {
+        Self {
+            m22: near / (near - far),
+            m23: near * far / (far - near),
+            ..Self::perspective(fov_y, aspect, near, far)
+        }
+    }
+
+    /// `perspective_reverse_z` with the far plane at infinity.
+    pub fn perspective_infinite_reverse_z(fov_y: Scalar, aspect: Scalar, near: Scalar) -> Self {
+        Self {
+            m22: 0.0,
+            m23: near,
+            ..Self::perspective_infinite(fov_y, aspect, near)
+        }
+    }
+
+    /// Perspective projection of an off-center frustum, the bounds are given on the near plane.
+    pub fn frustum(left: Scalar, right: Scalar, bottom: Scalar, top: Scalar, near: Scalar, far: Scalar) -> Self {
+        Self {
+            m00: 2.0 * near / (right - left),
+            m02: -(right + left) / (right - left),
+            m11: 2.0 * near / (top - bottom),
+            m12: -(top + bottom) / (top - bottom),
+            m22: far / (far - near),
+            m23: -near * far / (far - near),
+            m32: 1.0,
+            ..Self::default()
+        }
+    }
+
+    pub fn orthographic(left: Scalar, right: Scalar, bottom: Scalar, top: Scalar, near: Scalar, far: Scalar) -> Self {
+        Self {
+            m00: 2.0 / (right - left),
+            m03: -(right + left) / (right - left),
+            m11: 2.0 / (top - bottom),
+            m13: -(top + bottom) / (top - bottom),
+            m22: 1.0 / (far - near),
+            m23: -near / (far - near),
+            m33: 1.0,
+            ..Self::default()
+        }
+    }
+
+    /// Maps x and y from `[-1, 1]` to `[0, 1]` screen coordinates, +y up to rows going down,
+    /// and keeps depth. Can be applied before the division by w.
+    pub fn viewport() -> Self {
+        Self {
+            m00: 0.5,
+            m03: 0.5,
+            m11: -0.5,
+            m13: 0.5,
+            ..Self::identity()
+        }
+    }
+
+    pub fn from_rows(rows: [Vector4; 4]) -> Self {
+        let [r0, r1, r2, r3] = rows;
+        Self {
+            m00: r0.x, m01: r0.y, m02: r0.z, m03: r0.w,
+            m10: r1.x, m11: r1.y, m12: r1.z, m13: r1.w,
+            m20: r2.x, m21: r2.y, m22: r2.z, m23: r2.w,
+            m30: r3.x, m31: r3.y, m32: r3.z, m33: r3.w,
+        }
+    }
+
+    pub fn from_columns(columns: [Vector4; 4]) -> Self {
+        Self::from_rows(columns).transpose()
+    }
+
     pub fn as_rows(&self) -> [Vector4; 4] {
+        [
+            Vector4::new(self.m00, self.m01, self.m02, self.m03),
+            Vector4::new(self.m10, self.m11, self.m12, self.m13),
+            Vector4::new(self.m20, self.m21, self.m22, self.m23),
+            Vector4::new(self.m30, self.m31, self.m32, self.m33),
+        ]
+    }
+
+    pub fn as_columns(&self) -> [Vector4; 4] {
         [
             Vector4::new(self.m00, self.m10, self.m20, self.m30),
             Vector4::new(self.m01, self.m11, self.m21, self.m31),
@@ -32,13 +237,157 @@ impl Matrix4 {
         ]
     }
 
-    pub fn as_columns(&self) -> [Vector4; 4] {
-        [
-            Vector4::new(self.m00, self.m01, self.m02, self.m03),
-            Vector4::new(self.m10, self.m11, self.m12, self.m13),
-            Vector4::new(self.m20, self.m21, self.m22, self.m23),
-            Vector4::new(self.m30, self.m31, self.m32, self.m33),
-        ]
+    pub fn transpose(&self) -> Self {
+        Self {
+            m00: self.m00, m01: self.m10, m02: self.m20, m03: self.m30,
+            m10: self.m01, m11: self.m11, m12: self.m21, m13: self.m31,
+            m20: self.m02, m21: self.m12, m22: self.m22, m23: self.m32,
+            m30: self.m03, m31: self.m13, m32: self.m23, m33: self.m33,
+        }
+    }
+
+    /// Determinants of the 2x2 minors of the upper and lower two rows, shared by
+    /// `determinant` and `inverse`.
+    fn minors(&self) -> ([Scalar; 6], [Scalar; 6]) {
+        let s = [
+            self.m00 * self.m11 - self.m10 * self.m01,
+            self.m00 * self.m12 - self.m10 * self.m02,
+            self.m00 * self.m13 - self.m10 * self.m03,
+            self.m01 * self.m12 - self.m11 * self.m02,
+            self.m01 * self.m13 - self.m11 * self.m03,
+            self.m02 * self.m13 - self.m12 * self.m03,
+        ];
+        let c = [
+            self.m20 * self.m31 - self.m30 * self.m21,
+            self.m20 * self.m32 - self.m30 * self.m22,
+            self.m20 * self.m33 - self.m30 * self.m23,
+            self.m21 * self.m32 - self.m31 * self.m22,
+            self.m21 * self.m33 - self.m31 * self.m23,
+            self.m22 * self.m33 - self.m32 * self.m23,
+        ];
+        (s, c)
+    }
+
+    pub fn determinant(&self) -> Scalar {
+        let (s, c) = self.minors();
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+    }
+
+    /// General inverse, `None` if the matrix is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let (s, c) = self.minors();
+        let determinant = s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0];
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+        let inv = 1.0 / determinant;
+        Some(Self {
+            m00: (self.m11 * c[5] - self.m12 * c[4] + self.m13 * c[3]) * inv,
+            m01: (-self.m01 * c[5] + self.m02 * c[4] - self.m03 * c[3]) * inv,
+            m02: (self.m31 * s[5] - self.m32 * s[4] + self.m33 * s[3]) * inv,
+            m03: (-self.m21 * s[5] + self.m22 * s[4] - self.m23 * s[3]) * inv,
+            m10: (-self.m10 * c[5] + self.m12 * c[2] - self.m13 * c[1]) * inv,
+            m11: (self.m00 * c[5] - self.m02 * c[2] + self.m03 * c[1]) * inv,
+            m12: (-self.m30 * s[5] + self.m32 * s[2] - self.m33 * s[1]) * inv,
+            m13: (self.m20 * s[5] - self.m22 * s[2] + self.m23 * s[1]) * inv,
+            m20: (self.m10 * c[4] - self.m11 * c[2] + self.m13 * c[0]) * inv,
+            m21: (-self.m00 * c[4] + self.m01 * c[2] - self.m03 * c[0]) * inv,
+            m22: (self.m30 * s[4] - self.m31 * s[2] + self.m33 * s[0]) * inv,
+            m23: (-self.m20 * s[4] + self.m21 * s[2] - self.m23 * s[0]) * inv,
+            m30: (-self.m10 * c[3] + self.m11 * c[1] - self.m12 * c[0]) * inv,
+            m31: (self.m00 * c[3] - self.m01 * c[1] + self.m02 * c[0]) * inv,
+            m32: (-self.m30 * s[3] + self.m31 * s[1] - self.m32 * s[0]) * inv,
+            m33: (self.m20 * s[3] - self.m21 * s[1] + self.m22 * s[0]) * inv,
+        })
+    }
+
+    /// Inverse of an affine matrix, one whose last row is `0 0 0 1`, cheaper than `inverse`.
+    /// `None` if the upper 3x3 part is singular.
+    pub fn affine_inverse(&self) -> Option<Self> {
+        let c00 = self.m11 * self.m22 - self.m12 * self.m21;
+        let c01 = self.m12 * self.m20 - self.m10 * self.m22;
+        let c02 = self.m10 * self.m21 - self.m11 * self.m20;
+        let determinant = self.m00 * c00 + self.m01 * c01 + self.m02 * c02;
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+        let inv = 1.0 / determinant;
+
+        // inverse of the linear part is its adjugate divided by the determinant
+        let m00 = c00 * inv;
+        let m01 = (self.m02 * self.m21 - self.m01 * self.m22) * inv;
+        let m02 = (self.m01 * self.m12 - self.m02 * self.m11) * inv;
+        let m10 = c01 * inv;
+        let m11 = (self.m00 * self.m22 - self.m02 * self.m20) * inv;
+        let m12 = (self.m02 * self.m10 - self.m00 * self.m12) * inv;
+        let m20 = c02 * inv;
+        let m21 = (self.m01 * self.m20 - self.m00 * self.m21) * inv;
+        let m22 = (self.m00 * self.m11 - self.m01 * self.m10) * inv;
+
+        // and the translation is undone by the inverted linear part
+        let (tx, ty, tz) = (self.m03, self.m13, self.m23);
+        Some(Self {
+            m00, m01, m02, m03: -(m00 * tx + m01 * ty + m02 * tz),
+            m10, m11, m12, m13: -(m10 * tx + m11 * ty + m12 * tz),
+            m20, m21, m22, m23: -(m20 * tx + m21 * ty + m22 * tz),
+            m30: 0.0, m31: 0.0, m32: 0.0, m33: 1.0,
+        })
+    }
+
+    /// Transforms a point, `w = 1`, dividing by the resulting `w` if it is not 1,
+    /// so projections work as well. Same as `self * point`.
+    pub fn transform_point(&self, point: Vector3) -> Vector3 {
+        let v = self * Vector4::new(point.x, point.y, point.z, 1.0);
+        if v.w == 1.0 {
+            Vector3::new(v.x, v.y, v.z)
+        } else {
+            Vector3::new(v.x / v.w, v.y / v.w, v.z / v.w)
+        }
+    }
+
+    /// Transforms a direction, `w = 0`, unaffected by translation.
+    pub fn transform_direction(&self, direction: Vector3) -> Vector3 {
+        Vector3::new(
+            self.m00 * direction.x + self.m01 * direction.y + self.m02 * direction.z,
+            self.m10 * direction.x + self.m11 * direction.y + self.m12 * direction.z,
+            self.m20 * direction.x + self.m21 * direction.y + self.m22 * direction.z,
+        )
+    }
+
+    /// Upper left 3x3 part, the linear part of an affine matrix.
+    pub fn to_matrix3(&self) -> Matrix3 {
+        Matrix3 {
+            m00: self.m00, m01: self.m01, m02: self.m02,
+            m10: self.m10, m11: self.m11, m12: self.m12,
+            m20: self.m20, m21: self.m21, m22: self.m22,
+        }
+    }
+
+    /// Matrix transforming normals, the inverse transpose of the linear part. Unlike
+    /// `transform_direction` it keeps normals perpendicular to their surface under non
+    /// uniform scale, the results still have to be normalized. `None` if the linear part
+    /// is singular.
+    pub fn normal_matrix(&self) -> Option<Matrix3> {
+        self.to_matrix3().inverse().map(|inverse| inverse.transpose())
+    }
+}
+
+impl ApproxEq for Matrix4 {
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance) -> bool {
+        self.as_rows().approx_eq(&other.as_rows(), tolerance)
+    }
+}
+
+/// One row per line.
+impl fmt::Display for Matrix4 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, row) in self.as_rows().iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            fmt::Display::fmt(row, f)?;
+        }
+        Ok(())
     }
 }
 
@@ -60,14 +409,23 @@ impl_op_ex!(- |lhs: &Matrix4, rhs: &Matrix4| -> Matrix4 {
     }
 });
 
+impl_op_ex!(* |lhs: &Matrix4, rhs: Scalar| -> Matrix4 {
+    Matrix4 {
+        m00: lhs.m00 * rhs, m01: lhs.m01 * rhs, m02: lhs.m02 * rhs, m03: lhs.m03 * rhs,
+        m10: lhs.m10 * rhs, m11: lhs.m11 * rhs, m12: lhs.m12 * rhs, m13: lhs.m13 * rhs,
+        m20: lhs.m20 * rhs, m21: lhs.m21 * rhs, m22: lhs.m22 * rhs, m23: lhs.m23 * rhs,
+        m30: lhs.m30 * rhs, m31: lhs.m31 * rhs, m32: lhs.m32 * rhs, m33: lhs.m33 * rhs,
+    }
+});
+
 impl_op_ex!(* |lhs: &Matrix4, rhs: &Matrix4| -> Matrix4 {
     let rows = lhs.as_rows();
     let columns = rhs.as_columns();
     Matrix4 {
-        m00: columns[0] * rows[0], m01: columns[1] * rows[1], m02: columns[0] * rows[2], m03: columns[0] * rows[3],
-        m10: columns[1] * rows[0], m11: columns[1] * rows[1], m12: columns[1] * rows[2], m13: columns[1] * rows[3],
-        m20: columns[2] * rows[0], m21: columns[2] * rows[1], m22: columns[2] * rows[2], m23: columns[2] * rows[3],
-        m30: columns[3] * rows[0], m31: columns[3] * rows[1], m32: columns[3] * rows[2], m33: columns[3] * rows[3],
+        m00: rows[0] * columns[0], m01: rows[0] * columns[1], m02: rows[0] * columns[2], m03: rows[0] * columns[3],
+        m10: rows[1] * columns[0], m11: rows[1] * columns[1], m12: rows[1] * columns[2], m13: rows[1] * columns[3],
+        m20: rows[2] * columns[0], m21: rows[2] * columns[1], m22: rows[2] * columns[2], m23: rows[2] * columns[3],
+        m30: rows[3] * columns[0], m31: rows[3] * columns[1], m32: rows[3] * columns[2], m33: rows[3] * columns[3],
     }
 });
 
@@ -79,4 +437,289 @@ impl_op_ex!(* |lhs: &Matrix4, rhs: &Vector4| -> Vector4 {
         rows[2] * rhs,
         rows[3] * rhs,
     )
-});
\ No newline at end of file
+});
+
+impl_op_ex!(* |lhs: &Matrix4, rhs: &Vector3| -> Vector3 {
+    lhs.transform_point(*rhs)
+});
+
+/// 3x3 matrix, `mRC` is the element in row `R` and column `C`, with the conventions of
+/// `Matrix4`. Holds the linear part of a transform, e.g. the `Matrix4::normal_matrix`.
+/// `Default` is the zero matrix.
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Matrix3 {
+    pub m00: Scalar,
+    pub m01: Scalar,
+    pub m02: Scalar,
+    pub m10: Scalar,
+    pub m11: Scalar,
+    pub m12: Scalar,
+    pub m20: Scalar,
+    pub m21: Scalar,
+    pub m22: Scalar,
+}
+
+impl Matrix3 {
+    pub const fn identity() -> Self {
+        Self {
+            m00: 1.0, m01: 0.0, m02: 0.0,
+            m10: 0.0, m11: 1.0, m12: 0.0,
+            m20: 0.0, m21: 0.0, m22: 1.0,
+        }
+    }
+
+    pub fn scale(factors: Vector3) -> Self {
+        Self {
+            m00: factors.x,
+            m11: factors.y,
+            m22: factors.z,
+            ..Self::identity()
+        }
+    }
+
+    pub fn from_rows(rows: [Vector3; 3]) -> Self {
+        let [r0, r1, r2] = rows;
+        Self {
+            m00: r0.x, m01: r0.y, m02: r0.z,
+            m10: r1.x, m11: r1.y, m12: r1.z,
+            m20: r2.x, m21: r2.y, m22: r2.z,
+        }
+    }
+
+    pub fn from_columns(columns: [Vector3; 3]) -> Self {
+        Self::from_rows(columns).transpose()
+    }
+
+    pub fn as_rows(&self) -> [Vector3; 3] {
+        [
+            Vector3::new(self.m00, self.m01, self.m02),
+            Vector3::new(self.m10, self.m11, self.m12),
+            Vector3::new(self.m20, self.m21, self.m22),
+        ]
+    }
+
+    pub fn as_columns(&self) -> [Vector3; 3] {
+        [
+            Vector3::new(self.m00, self.m10, self.m20),
+            Vector3::new(self.m01, self.m11, self.m21),
+            Vector3::new(self.m02, self.m12, self.m22),
+        ]
+    }
+
+    /// Affine `Matrix4` with this linear part and no translation.
+    pub fn to_matrix4(&self) -> Matrix4 {
+        Matrix4 {
+            m00: self.m00, m01: self.m01, m02: self.m02, m03: 0.0,
+            m10: self.m10, m11: self.m11, m12: self.m12, m13: 0.0,
+            m20: self.m20, m21: self.m21, m22: self.m22, m23: 0.0,
+            m30: 0.0, m31: 0.0, m32: 0.0, m33: 1.0,
+        }
+    }
+
+    pub fn transpose(&self) -> Self {
+        Self {
+            m00: self.m00, m01: self.m10, m02: self.m20,
+            m10: self.m01, m11: self.m11, m12: self.m21,
+            m20: self.m02, m21: self.m12, m22: self.m22,
+        }
+    }
+
+    pub fn determinant(&self) -> Scalar {
+        self.m00 * (self.m11 * self.m22 - self.m12 * self.m21)
+            + self.m01 * (self.m12 * self.m20 - self.m10 * self.m22)
+            + self.m02 * (self.m10 * self.m21 - self.m11 * self.m20)
+    }
+
+    /// Adjugate divided by the determinant, `None` if the matrix is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let c00 = self.m11 * self.m22 - self.m12 * self.m21;
+        let c01 = self.m12 * self.m20 - self.m10 * self.m22;
+        let c02 = self.m10 * self.m21 - self.m11 * self.m20;
+        let determinant = self.m00 * c00 + self.m01 * c01 + self.m02 * c02;
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+        let inv = 1.0 / determinant;
+        Some(Self {
+            m00: c00 * inv,
+            m01: (self.m02 * self.m21 - self.m01 * self.m22) * inv,
+            m02: (self.m01 * self.m12 - self.m02 * self.m11) * inv,
+            m10: c01 * inv,
+            m11: (self.m00 * self.m22 - self.m02 * self.m20) * inv,
+            m12: (self.m02 * self.m10 - self.m00 * self.m12) * inv,
+            m20: c02 * inv,
+            m21: (self.m01 * self.m20 - self.m00 * self.m21) * inv,
+            m22: (self.m00 * self.m11 - self.m01 * self.m10) * inv,
+        })
+    }
+}
+
+impl ApproxEq for Matrix3 {
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance) -> bool {
+        self.as_rows().approx_eq(&other.as_rows(), tolerance)
+    }
+}
+
+/// One row per line.
+impl fmt::Display for Matrix3 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, row) in self.as_rows().iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            fmt::Display::fmt(row, f)?;
+        }
+        Ok(())
+    }
+}
+
+impl_op_ex!(+ |lhs: &Matrix3, rhs: &Matrix3| -> Matrix3 {
+    Matrix3 {
+        m00: lhs.m00 + rhs.m00, m01: lhs.m01 + rhs.m01, m02: lhs.m02 + rhs.m02,
+        m10: lhs.m10 + rhs.m10, m11: lhs.m11 + rhs.m11, m12: lhs.m12 + rhs.m12,
+        m20: lhs.m20 + rhs.m20, m21: lhs.m21 + rhs.m21, m22: lhs.m22 + rhs.m22,
+    }
+});
+
+impl_op_ex!(- |lhs: &Matrix3, rhs: &Matrix3| -> Matrix3 {
+    Matrix3 {
+        m00: lhs.m00 - rhs.m00, m01: lhs.m01 - rhs.m01, m02: lhs.m02 - rhs.m02,
+        m10: lhs.m10 - rhs.m10, m11: lhs.m11 - rhs.m11, m12: lhs.m12 - rhs.m12,
+        m20: lhs.m20 - rhs.m20, m21: lhs.m21 - rhs.m21, m22: lhs.m22 - rhs.m22,
+    }
+});
+
+impl_op_ex!(* |lhs: &Matrix3, rhs: Scalar| -> Matrix3 {
+    Matrix3 {
+        m00: lhs.m00 * rhs, m01: lhs.m01 * rhs, m02: lhs.m02 * rhs,
+        m10: lhs.m10 * rhs, m11: lhs.m11 * rhs, m12: lhs.m12 * rhs,
+        m20: lhs.m20 * rhs, m21: lhs.m21 * rhs, m22: lhs.m22 * rhs,
+    }
+});
+
+impl_op_ex!(* |lhs: &Matrix3, rhs: &Matrix3| -> Matrix3 {
+    let [r0, r1, r2] = lhs.as_rows();
+    let [c0, c1, c2] = rhs.as_columns();
+    Matrix3 {
+        m00: r0 * c0, m01: r0 * c1, m02: r0 * c2,
+        m10: r1 * c0, m11: r1 * c1, m12: r1 * c2,
+        m20: r2 * c0, m21: r2 * c1, m22: r2 * c2,
+    }
+});
+
+impl_op_ex!(* |lhs: &Matrix3, rhs: &Vector3| -> Vector3 {
+    Vector3::new(
+        lhs.m00 * rhs.x + lhs.m01 * rhs.y + lhs.m02 * rhs.z,
+        lhs.m10 * rhs.x + lhs.m11 * rhs.y + lhs.m12 * rhs.z,
+        lhs.m20 * rhs.x + lhs.m21 * rhs.y + lhs.m22 * rhs.z,
+    )
+});
+
+/// Scale, then rotation, then translation, the usual way to place objects, cameras and
+/// the bones of a skeleton. Unlike a `Matrix4` it interpolates and inverts cheaply.
+///
+/// Composing a rotation after a non uniform scale can shear, which a `Transform` cannot
+/// hold, so `*` and `inverse` are exact for uniform scales only. Go through
+/// `to_matrix4` when that matters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transform {
+    pub translation: Vector3,
+    pub rotation: Quaternion,
+    pub scale: Vector3,
+}
+
+impl Transform {
+    pub const fn identity() -> Self {
+        Self {
+            translation: Vector3::zero(),
+            rotation: Quaternion::identity(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    pub fn with_translation(self, translation: Vector3) -> Self {
+        Self { translation, ..self }
+    }
+
+    pub fn with_rotation(self, rotation: Quaternion) -> Self {
+        Self { rotation, ..self }
+    }
+
+    pub fn with_scale(self, scale: Vector3) -> Self {
+        Self { scale, ..self }
+    }
+
+    pub fn to_matrix4(&self) -> Matrix4 {
+        Matrix4::translation(self.translation) * self.rotation.to_matrix4() * Matrix4::scale(self.scale)
+    }
+
+    pub fn transform_point(&self, point: Vector3) -> Vector3 {
+        self.transform_direction(point) + self.translation
+    }
+
+    pub fn transform_direction(&self, direction: Vector3) -> Vector3 {
+        self.rotation * direction.component_mul(self.scale)
+    }
+
+    /// Undoes the transform, `None` if a scale factor is 0.
+    pub fn inverse(&self) -> Option<Self> {
+        if self.scale.x == 0.0 || self.scale.y == 0.0 || self.scale.z == 0.0 {
+            return None;
+        }
+        let rotation = self.rotation.conjugate();
+        let scale_factors = Vector3::new(1.0 / self.scale.x, 1.0 / self.scale.y, 1.0 / self.scale.z);
+        Some(Self {
+            translation: -(rotation * self.translation).component_mul(scale_factors),
+            rotation,
+            scale: scale_factors,
+        })
+    }
+
+    /// Interpolates translation and scale linearly and rotation with `Quaternion::slerp`.
+    pub fn lerp(&self, other: &Self, t: Scalar) -> Self {
+        Self {
+            translation: self.translation * (1.0 - t) + other.translation * t,
+            rotation: self.rotation.slerp(other.rotation, t),
+            scale: self.scale * (1.0 - t) + other.scale * t,
+        }
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl ApproxEq for Transform {
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance) -> bool {
+        self.translation.approx_eq(&other.translation, tolerance)
+            && self.rotation.approx_eq(&other.rotation, tolerance)
+            && self.scale.approx_eq(&other.scale, tolerance)
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "translation ")?;
+        fmt::Display::fmt(&self.translation, f)?;
+        write!(f, ", rotation ")?;
+        fmt::Display::fmt(&self.rotation, f)?;
+        write!(f, ", scale ")?;
+        fmt::Display::fmt(&self.scale, f)
+    }
+}
+
+impl_op_ex!(* |lhs: &Transform, rhs: &Transform| -> Transform {
+    Transform {
+        translation: lhs.transform_point(rhs.translation),
+        rotation: lhs.rotation * rhs.rotation,
+        scale: lhs.scale.component_mul(rhs.scale),
+    }
+});
+
+impl_op_ex!(* |lhs: &Transform, rhs: &Vector3| -> Vector3 {
+    lhs.transform_point(*rhs)
+});
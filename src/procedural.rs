@@ -0,0 +1,441 @@
+use crate::scalar::{consts, Scalar};
+use crate::texture::Texture;
+use crate::vector::{Vector2, Vector3};
+
+/// Hashes integer lattice coordinates together with a seed.
+fn hash(x: i64, y: i64, seed: u32) -> u32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6_b343) ^ (y as u32).wrapping_mul(0xd816_3841) ^ seed.wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^ (h >> 16)
+}
+
+/// Maps a hash to `[0, 1)`.
+fn unit(h: u32) -> Scalar {
+    h as Scalar / 4_294_967_296.0
+}
+
+fn gradient(h: u32, x: Scalar, y: Scalar) -> Scalar {
+    match h & 7 {
+        0 => x + y,
+        1 => x - y,
+        2 => -x + y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+fn fade(t: Scalar) -> Scalar {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: Scalar, b: Scalar, t: Scalar) -> Scalar {
+    a + (b - a) * t
+}
+
+/// Classic gradient noise, roughly in `[-1, 1]` and zero at integer coordinates.
+pub fn perlin(p: Vector2, seed: u32) -> Scalar {
+    let (x0, y0) = (p.x.floor(), p.y.floor());
+    let (fx, fy) = (p.x - x0, p.y - y0);
+    let (x0, y0) = (x0 as i64, y0 as i64);
+
+    let n00 = gradient(hash(x0, y0, seed), fx, fy);
+    let n10 = gradient(hash(x0 + 1, y0, seed), fx - 1.0, fy);
+    let n01 = gradient(hash(x0, y0 + 1, seed), fx, fy - 1.0);
+    let n11 = gradient(hash(x0 + 1, y0 + 1, seed), fx - 1.0, fy - 1.0);
+
+    let (u, v) = (fade(fx), fade(fy));
+    lerp(lerp(n00, n10, u), lerp(n01, n11, u), v)
+}
+
+/// Simplex noise on a triangular lattice, roughly in `[-1, 1]`.
+/// Cheaper than `perlin` and free of its axis aligned artifacts.
+pub fn simplex(p: Vector2, seed: u32) -> Scalar {
+    #[allow(clippy::excessive_precision)]
+    const SKEW: Scalar = 0.366_025_403_784_438_6; // (sqrt(3) - 1) / 2
+    #[allow(clippy::excessive_precision)]
+    const UNSKEW: Scalar = 0.211_324_865_405_187_1; // (3 - sqrt(3)) / 6
+
+    let s = (p.x + p.y) * SKEW;
+    let (i, j) = ((p.x + s).floor(), (p.y + s).floor());
+    let t = (i + j) * UNSKEW;
+    let (x0, y0) = (p.x - (i - t), p.y - (j - t));
+    let (i, j) = (i as i64, j as i64);
+
+    // second corner depends on the half of the skewed cell
+    let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+    let corners = [
+        (0, 0, x0, y0),
+        (i1, j1, x0 - i1 as Scalar + UNSKEW, y0 - j1 as Scalar + UNSKEW),
+        (1, 1, x0 - 1.0 + 2.0 * UNSKEW, y0 - 1.0 + 2.0 * UNSKEW),
+    ];
+
+    let mut value = 0.0;
+    for &(di, dj, x, y) in corners.iter() {
+        let falloff = 0.5 - x * x - y * y;
+        if falloff > 0.0 {
+            value += falloff.powi(4) * gradient(hash(i + di, j + dj, seed), x, y);
+        }
+    }
+    70.0 * value
+}
+
+/// Distances to the nearest and second nearest feature point of cellular noise,
+/// every unit cell holds one feature point.
+pub fn worley(p: Vector2, seed: u32) -> (Scalar, Scalar) {
+    let (cx, cy) = (p.x.floor() as i64, p.y.floor() as i64);
+    let mut nearest = (Scalar::INFINITY, Scalar::INFINITY);
+    for y in cy - 1..=cy + 1 {
+        for x in cx - 1..=cx + 1 {
+            let h = hash(x, y, seed);
+            let feature = Vector2::new(x as Scalar + unit(h), y as Scalar + unit(hash(x, y, h)));
+            let d = p - feature;
+            let distance = d.length();
+            if distance < nearest.0 {
+                nearest = (distance, nearest.0);
+            } else if distance < nearest.1 {
+                nearest.1 = distance;
+            }
+        }
+    }
+    nearest
+}
+
+/// Gradient noise basis used by fractal textures.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Noise {
+    Perlin,
+    Simplex,
+}
+
+impl Noise {
+    pub fn at(self, p: Vector2, seed: u32) -> Scalar {
+        match self {
+            Noise::Perlin => perlin(p, seed),
+            Noise::Simplex => simplex(p, seed),
+        }
+    }
+}
+
+/// Fractal Brownian motion, a sum of noise octaves of rising frequency and falling amplitude.
+#[derive(Clone, Copy, Debug)]
+pub struct Fbm {
+    pub noise: Noise,
+    pub octaves: u32,
+    /// Frequency multiplier between octaves.
+    pub lacunarity: Scalar,
+    /// Amplitude multiplier between octaves.
+    pub gain: Scalar,
+    pub seed: u32,
+}
+
+impl Fbm {
+    pub fn new(noise: Noise, octaves: u32) -> Self {
+        Self {
+            noise,
+            octaves,
+            lacunarity: 2.0,
+            gain: 0.5,
+            seed: 0,
+        }
+    }
+
+    pub fn with_seed(self, seed: u32) -> Self {
+        Self { seed, ..self }
+    }
+
+    /// Sum of the octaves normalized to roughly `[-1, 1]`.
+    pub fn at(&self, p: Vector2) -> Scalar {
+        self.octaves_sum(p, |n| n)
+    }
+
+    /// Sum of absolute values of the octaves, roughly in `[0, 1]`.
+    pub fn turbulence(&self, p: Vector2) -> Scalar {
+        self.octaves_sum(p, Scalar::abs)
+    }
+
+    fn octaves_sum(&self, p: Vector2, shape: impl Fn(Scalar) -> Scalar) -> Scalar {
+        let mut value = 0.0;
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        for octave in 0..self.octaves {
+            value += amplitude * shape(self.noise.at(frequency * p, self.seed.wrapping_add(octave)));
+            total += amplitude;
+            amplitude *= self.gain;
+            frequency *= self.lacunarity;
+        }
+        if total > 0.0 {
+            value / total
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Texture for Fbm {
+    fn sample(&self, uv: Vector2) -> Vector3 {
+        let value = 0.5 + 0.5 * self.at(uv);
+        Vector3::new(value, value, value)
+    }
+}
+
+/// Alternating squares of two colors, `frequency` squares along each axis of the unit square.
+#[derive(Clone, Copy, Debug)]
+pub struct Checkerboard {
+    pub even: Vector3,
+    pub odd: Vector3,
+    pub frequency: Scalar,
+}
+
+impl Checkerboard {
+    pub fn new(even: Vector3, odd: Vector3, frequency: Scalar) -> Self {
+        Self { even, odd, frequency }
+    }
+}
+
+impl Texture for Checkerboard {
+    fn sample(&self, uv: Vector2) -> Vector3 {
+        let x = (uv.x * self.frequency).floor() as i64;
+        let y = (uv.y * self.frequency).floor() as i64;
+        if (x + y) & 1 == 0 {
+            self.even
+        } else {
+            self.odd
+        }
+    }
+}
+
+/// Single octave of gradient noise remapped to grayscale in `[0, 1]`.
+#[derive(Clone, Copy, Debug)]
+pub struct GradientNoise {
+    pub noise: Noise,
+    pub frequency: Scalar,
+    pub seed: u32,
+}
+
+impl GradientNoise {
+    pub fn new(noise: Noise, frequency: Scalar) -> Self {
+        Self { noise, frequency, seed: 0 }
+    }
+
+    pub fn with_seed(self, seed: u32) -> Self {
+        Self { seed, ..self }
+    }
+}
+
+impl Texture for GradientNoise {
+    fn sample(&self, uv: Vector2) -> Vector3 {
+        let value = 0.5 + 0.5 * self.noise.at(self.frequency * uv, self.seed);
+        Vector3::new(value, value, value)
+    }
+}
+
+/// Quantity of cellular noise output by `Worley`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cellular {
+    /// Distance to the nearest feature point, dark spots in every cell.
+    Nearest,
+    /// Distance to the second nearest feature point.
+    SecondNearest,
+    /// Difference of the two distances, dark lines along cell borders.
+    Border,
+}
+
+/// Cellular (Worley) noise as grayscale, `frequency` cells along each axis of the unit square.
+#[derive(Clone, Copy, Debug)]
+pub struct Worley {
+    pub output: Cellular,
+    pub frequency: Scalar,
+    pub seed: u32,
+}
+
+impl Worley {
+    pub fn new(output: Cellular, frequency: Scalar) -> Self {
+        Self { output, frequency, seed: 0 }
+    }
+
+    pub fn with_seed(self, seed: u32) -> Self {
+        Self { seed, ..self }
+    }
+}
+
+impl Texture for Worley {
+    fn sample(&self, uv: Vector2) -> Vector3 {
+        let (f1, f2) = worley(self.frequency * uv, self.seed);
+        let value = match self.output {
+            Cellular::Nearest => f1,
+            Cellular::SecondNearest => f2,
+            Cellular::Border => f2 - f1,
+        }
+        .min(1.0);
+        Vector3::new(value, value, value)
+    }
+}
+
+/// Veins along `u` distorted by turbulence.
+#[derive(Clone, Copy, Debug)]
+pub struct Marble {
+    pub base: Vector3,
+    pub vein: Vector3,
+    /// Number of veins across the unit square.
+    pub frequency: Scalar,
+    pub turbulence: Scalar,
+    pub fbm: Fbm,
+}
+
+impl Marble {
+    pub fn new(base: Vector3, vein: Vector3) -> Self {
+        Self {
+            base,
+            vein,
+            frequency: 4.0,
+            turbulence: 5.0,
+            fbm: Fbm::new(Noise::Perlin, 5),
+        }
+    }
+}
+
+impl Texture for Marble {
+    fn sample(&self, uv: Vector2) -> Vector3 {
+        let phase = uv.x * self.frequency + self.turbulence * self.fbm.turbulence(4.0 * uv);
+        let t = 0.5 + 0.5 * (phase * consts::PI).sin();
+        self.vein.lerp(self.base, t.sqrt())
+    }
+}
+
+/// Growth rings around the center of the unit square, perturbed by noise.
+#[derive(Clone, Copy, Debug)]
+pub struct Wood {
+    pub light: Vector3,
+    pub dark: Vector3,
+    /// Number of rings between the center and the edge.
+    pub rings: Scalar,
+    pub turbulence: Scalar,
+    pub fbm: Fbm,
+}
+
+impl Wood {
+    pub fn new(light: Vector3, dark: Vector3) -> Self {
+        Self {
+            light,
+            dark,
+            rings: 12.0,
+            turbulence: 0.1,
+            fbm: Fbm::new(Noise::Perlin, 3),
+        }
+    }
+}
+
+impl Texture for Wood {
+    fn sample(&self, uv: Vector2) -> Vector3 {
+        let d = uv - Vector2::new(0.5, 0.5);
+        let distance = d.length() + self.turbulence * self.fbm.at(8.0 * uv);
+        let ring = (distance * self.rings).fract();
+        // sharp edge on the outer side of every ring
+        self.light.lerp(self.dark, ring * ring)
+    }
+}
+
+/// Bitwise xor-like pattern of the original demo, scrolled along `u` by `offset`.
+/// `resolution` is the number of pattern cells across the unit square.
+#[derive(Clone, Copy, Debug)]
+pub struct Sierpinski {
+    pub resolution: Vector2,
+    pub offset: Scalar,
+}
+
+impl Texture for Sierpinski {
+    fn sample(&self, uv: Vector2) -> Vector3 {
+        let x = (self.resolution.x * (uv.x + self.offset)) as usize;
+        let y = (self.resolution.y * uv.y) as usize;
+        Vector3::new((x & y) as Scalar, (x & 15) as Scalar / 15.0, (y & 15) as Scalar / 15.0)
+    }
+}
+
+/// Rotating tunnel of the original demo, animated by `time`.
+#[derive(Clone, Copy, Debug)]
+pub struct Ojascki {
+    pub time: Scalar,
+    /// Height divided by width of the surface, keeps the tunnel round.
+    pub aspect: Scalar,
+}
+
+impl Texture for Ojascki {
+    fn sample(&self, uv: Vector2) -> Vector3 {
+        let t = self.time;
+        let mut x = uv.x - 0.5;
+        let mut y = (uv.y - 0.5) * self.aspect;
+
+        let a = t + (t * 0.5).sin() * 0.25;
+        let sina = a.sin();
+        let cosa = a.cos();
+
+        x = x * cosa - y * sina;
+        y = x * sina + y * cosa;
+        let mut length = (x * x + y * y).sqrt();
+        let factor = (length * (6.0 + (a * 0.4).sin() * 3.0)).powi(2);
+        x /= factor;
+        y /= factor;
+
+        length = (x * x + y * y).sqrt();
+        let x1 = x;
+        x = x.atan2(y);
+        y = length * 20.0;
+
+        x += (y - x).floor() + 1.0;
+        y = (y - x1).fract();
+
+        x = (x * x * 4.0 - (0.25 - y * y).sqrt()) * 3.0;
+
+        Vector3::new(x, y, 0.0)
+    }
+}
+
+/// Sum of two textures.
+#[derive(Clone, Copy, Debug)]
+pub struct Sum<A, B>(pub A, pub B);
+
+impl<A: Texture, B: Texture> Texture for Sum<A, B> {
+    fn sample(&self, uv: Vector2) -> Vector3 {
+        self.0.sample(uv) + self.1.sample(uv)
+    }
+}
+
+/// Component-wise product of two textures, e.g. to modulate a color with noise.
+#[derive(Clone, Copy, Debug)]
+pub struct Product<A, B>(pub A, pub B);
+
+impl<A: Texture, B: Texture> Texture for Product<A, B> {
+    fn sample(&self, uv: Vector2) -> Vector3 {
+        self.0.sample(uv).component_mul(self.1.sample(uv))
+    }
+}
+
+/// Texture with its coordinates displaced by another one, red and green of `by`
+/// centered around zero and scaled by `strength` are added to `uv`.
+#[derive(Clone, Copy, Debug)]
+pub struct Warp<T, W> {
+    pub texture: T,
+    pub by: W,
+    pub strength: Scalar,
+}
+
+impl<T: Texture, W: Texture> Warp<T, W> {
+    pub fn new(texture: T, by: W, strength: Scalar) -> Self {
+        Self { texture, by, strength }
+    }
+}
+
+impl<T: Texture, W: Texture> Texture for Warp<T, W> {
+    fn sample(&self, uv: Vector2) -> Vector3 {
+        let offset = self.by.sample(uv);
+        let offset = Vector2::new(offset.x - 0.5, offset.y - 0.5);
+        self.texture.sample(uv + self.strength * offset)
+    }
+}
@@ -1,67 +1,78 @@
 use std::marker::PhantomData;
 use std::ops::{Add, Mul};
 
-use crate::framebuffer::{Framebuffer, RegionBuffer};
-use crate::vector::Vector3;
+use crate::framebuffer::{GBufferSample, RegionBuffer};
+use crate::geometry::{Aabb, Frustum};
+use crate::scalar::Scalar;
+use crate::simd::{Barycentric, Level, Row};
+use crate::vector::{Vector3, Vector4};
 
 #[derive(Clone, Debug)]
 struct Triangle {
     indices: (usize, usize, usize),
     // cached values
-    d00: f64,
-    d01: f64,
-    d11: f64,
-    inv: f64,
-    zx: f64,
-    zy: f64,
-    v0: Vector3,
-    v1: Vector3,
-    a: Vector3,
+    barycentric: Barycentric,
+    z_min: Scalar,
+    z_max: Scalar,
+    object_id: u32,
 }
 
 impl Triangle {
-    pub fn interpolate<Attr>(&self, point: Vector3, a0: &Attr, a1: &Attr, a2: &Attr) -> Option<Attr>
+    pub fn interpolate<Attr>(&self, (u, v, w): (Scalar, Scalar, Scalar), a0: &Attr, a1: &Attr, a2: &Attr) -> Attr
         where
-                for<'a> &'a Attr: Add<Output=Attr> + Mul<f64, Output=Attr>,
+                for<'a> &'a Attr: Add<Output=Attr> + Mul<Scalar, Output=Attr>,
                 Attr: Add<Output=Attr> {
-        let v2 = point - self.a;
-        let d20 = v2 * self.v0;
-        let d21 = v2 * self.v1;
+        a0 * u + a1 * v + a2 * w
+    }
 
-        let v = (self.d11 * d20 - self.d01 * d21) * self.inv;
-        let w = (self.d00 * d21 - self.d01 * d20) * self.inv;
-        let u = 1.0 - v - w;
+    pub fn depth_at(&self, x: Scalar, y: Scalar) -> Scalar {
+        self.barycentric.depth_at(x, y)
+    }
 
-        if v < 0.0 || w < 0.0 || u < 0.0 {
-            None
-        } else {
-            Some(a0 * v + a1 * w + a2 * u)
-        }
+    /// Conservative depth range of the triangle inside the normalized rectangle
+    /// `(x0, y0)..(x1, y1)`. Depth is linear in screen space, so the extremes of the
+    /// plane lie in the corners; the vertex range bounds it from the other side.
+    pub fn depth_range(&self, x0: Scalar, y0: Scalar, x1: Scalar, y1: Scalar) -> (Scalar, Scalar) {
+        let corners = [
+            self.depth_at(x0, y0),
+            self.depth_at(x1, y0),
+            self.depth_at(x0, y1),
+            self.depth_at(x1, y1),
+        ];
+        let plane_min = corners.iter().cloned().fold(Scalar::INFINITY, Scalar::min);
+        let plane_max = corners.iter().cloned().fold(Scalar::NEG_INFINITY, Scalar::max);
+
+        (plane_min.max(self.z_min), plane_max.min(self.z_max))
     }
 }
 
 
-pub struct Program<In, U, Attr>
+pub struct Program<In, U, Attr, const N: usize = 1>
     where
         U: Clone,
-        for<'a> &'a Attr: Add<&'a Attr, Output=Attr> + Mul<f64, Output=Attr>,
-        Attr: Add<Attr, Output=Attr> + Mul<f64, Output=Attr> + Clone {
+        for<'a> &'a Attr: Add<&'a Attr, Output=Attr> + Mul<Scalar, Output=Attr>,
+        Attr: Add<Attr, Output=Attr> + Mul<Scalar, Output=Attr> + Clone {
     vertex_shader: fn(In, &U) -> (Vector3, Attr),
-    fragment_shader: fn(Vector3, Attr, &U) -> Vector3,
+    shading: Shading<U, Attr, N>,
+    depth_prepass: bool,
+    simd: Level,
+    frustum: Option<Frustum>,
     pub uniform: U,
+    /// Id written to the id plane of the framebuffer by triangles enqueued from now on.
+    pub object_id: u32,
     width: usize,
     height: usize,
     region_width: usize,
     region_height: usize,
-    pub regions: Vec<Vec<RegionRenderer<U, Attr>>>,
+    pub regions: Vec<Vec<RegionRenderer<U, Attr, N>>>,
     _marker: PhantomData<In>,
 }
 
 impl<In, U, Attr> Program<In, U, Attr>
     where
         U: Clone,
-        Attr: Add<Output=Attr> + Clone + Mul<f64, Output=Attr>,
-        for<'a> &'a Attr: Add<Output=Attr> + Clone + Mul<f64, Output=Attr> {
+        Attr: Add<Output=Attr> + Clone + Mul<Scalar, Output=Attr>,
+        for<'a> &'a Attr: Add<Output=Attr> + Clone + Mul<Scalar, Output=Attr> {
     pub fn new(
         vertex_shader: fn(In, &U) -> (Vector3, Attr),
         fragment_shader: fn(Vector3, Attr, &U) -> Vector3,
@@ -71,39 +82,118 @@ impl<In, U, Attr> Program<In, U, Attr>
         region_width: usize,
         region_height: usize,
     ) -> Self {
-        let count_x = if width % region_width != 0 {
-            width / region_width + 1
-        } else {
-            width / region_width
-        };
-        let count_y = if height % region_height != 0 {
-            height / region_height + 1
-        } else {
-            height / region_height
-        };
-
-        //println!("{} {}", count_x, count_y);
+        Self::with_shading(vertex_shader, Shading::Forward(fragment_shader), uniform, width, height, region_width, region_height)
+    }
 
-        let mut regions =
-            vec![vec![RegionRenderer::without_dimensions(fragment_shader, width, height, region_width, region_height); count_x]; count_y];
+    /// Program rendering in deferred mode: after a depth pre-pass `geometry_shader` runs
+    /// once for every visible pixel and fills the g-buffer planes of the framebuffer,
+    /// then `lighting_shader` runs once per pixel on the stored samples.
+    /// The framebuffer has to be created with [`Framebuffer::with_gbuffer`], rendering panics otherwise.
+    ///
+    /// [`Framebuffer::with_gbuffer`]: ../framebuffer/struct.Framebuffer.html#method.with_gbuffer
+    #[allow(clippy::too_many_arguments)]
+    pub fn deferred(
+        vertex_shader: fn(In, &U) -> (Vector3, Attr),
+        geometry_shader: fn(Vector3, Attr, &U) -> GBufferSample,
+        lighting_shader: fn(Vector3, &GBufferSample, &U) -> Vector3,
+        uniform: U,
+        width: usize,
+        height: usize,
+        region_width: usize,
+        region_height: usize,
+    ) -> Self {
+        Self::with_shading(vertex_shader, Shading::Deferred(geometry_shader, lighting_shader), uniform, width, height, region_width, region_height)
+    }
+}
 
-        for x in 0..count_x {
-            for y in 0..count_y {
-                regions[y][x].from = (x * region_width, y * region_height);
-            }
-        }
+impl<In, U, Attr, const N: usize> Program<In, U, Attr, N>
+    where
+        U: Clone,
+        Attr: Add<Output=Attr> + Clone + Mul<Scalar, Output=Attr>,
+        for<'a> &'a Attr: Add<Output=Attr> + Clone + Mul<Scalar, Output=Attr> {
+    /// Program whose fragment shader writes `N` render targets at once. The framebuffer
+    /// has to be created with [`Framebuffer::with_targets`] describing at least `N` targets,
+    /// rendering panics otherwise. Outputs go to the targets in the order of their descriptions.
+    ///
+    /// [`Framebuffer::with_targets`]: ../framebuffer/struct.Framebuffer.html#method.with_targets
+    pub fn with_targets(
+        vertex_shader: fn(In, &U) -> (Vector3, Attr),
+        fragment_shader: fn(Vector3, Attr, &U) -> [Vector4; N],
+        uniform: U,
+        width: usize,
+        height: usize,
+        region_width: usize,
+        region_height: usize,
+    ) -> Self {
+        Self::with_shading(vertex_shader, Shading::Targets(fragment_shader), uniform, width, height, region_width, region_height)
+    }
 
-        Self {
+    fn with_shading(
+        vertex_shader: fn(In, &U) -> (Vector3, Attr),
+        shading: Shading<U, Attr, N>,
+        uniform: U,
+        width: usize,
+        height: usize,
+        region_width: usize,
+        region_height: usize,
+    ) -> Self {
+        let mut program = Self {
             vertex_shader,
-            fragment_shader,
+            shading,
+            depth_prepass: false,
+            simd: Level::detect(),
+            frustum: None,
             uniform,
+            object_id: 0,
             width,
             height,
             region_width,
             region_height,
-            regions,
+            regions: Vec::new(),
             _marker: PhantomData,
+        };
+        program.reset();
+        program
+    }
+
+    /// Rasterizes depth of all triangles before shading, so that forward shading runs
+    /// the fragment shader exactly once per pixel. Deferred programs always do it.
+    pub fn set_depth_prepass(&mut self, enabled: bool) {
+        self.depth_prepass = enabled;
+        for region in self.regions.iter_mut().flatten() {
+            region.depth_prepass = enabled;
+        }
+    }
+
+    /// Instruction set rasterization uses, lowered to what the CPU supports. Every level
+    /// renders the same image, the best one is picked by default.
+    pub fn set_simd(&mut self, level: Level) {
+        self.simd = level.supported();
+        for region in self.regions.iter_mut().flatten() {
+            region.simd = self.simd;
+        }
+    }
+
+    /// Frustum `enqueue_object` culls against, in the space of the object bounds, e.g.
+    /// `Frustum::from_matrix(&(projection * view))` for bounds in world space. `None`, the
+    /// default, culls nothing.
+    pub fn set_frustum(&mut self, frustum: Option<Frustum>) {
+        self.frustum = frustum;
+    }
+
+    /// Enqueues the triangles of an object unless its `bounds` are outside of the frustum,
+    /// in which case the vertex shader does not run on them. Returns whether they were enqueued.
+    pub fn enqueue_object<I>(&mut self, bounds: &Aabb, triangles: I) -> bool
+        where I: IntoIterator<Item = [In; 3]> {
+        if let Some(frustum) = &self.frustum {
+            if !frustum.intersects_aabb(bounds) {
+                return false;
+            }
         }
+        for [i0, i1, i2] in triangles {
+            self.enqueue_triangle(i0, i1, i2);
+        }
+        true
     }
 
     pub fn enqueue_triangle(&mut self, i0: In, i1: In, i2: In) {
@@ -121,8 +211,8 @@ impl<In, U, Attr> Program<In, U, Attr>
             return;
         }
 
-        let width = self.width as f64 - 1.0;
-        let height = self.height as f64 - 1.0;
+        let width = self.width as Scalar - 1.0;
+        let height = self.height as Scalar - 1.0;
 
 
         let x_min = (x_min.max(0.0) * width) as usize;
@@ -156,15 +246,20 @@ impl<In, U, Attr> Program<In, U, Attr>
                 let attr_len = region.triangles_attrs.len();
                 region.triangles.push(Triangle {
                     indices: (attr_len, attr_len + 1, attr_len + 2),
-                    d00,
-                    d01,
-                    d11,
-                    zx,
-                    zy,
-                    v0: edge0,
-                    v1: edge1,
-                    a: v0.0,
-                    inv,
+                    barycentric: Barycentric {
+                        a: v0.0,
+                        v0: edge0,
+                        v1: edge1,
+                        d00,
+                        d01,
+                        d11,
+                        inv,
+                        zx,
+                        zy,
+                    },
+                    z_min: v0.0.z.min(v1.0.z.min(v2.0.z)),
+                    z_max: v0.0.z.max(v1.0.z.max(v2.0.z)),
+                    object_id: self.object_id,
                 });
                 region.triangles_attrs.push(v0.1.clone());
                 region.triangles_attrs.push(v1.1.clone());
@@ -178,23 +273,19 @@ impl<In, U, Attr> Program<In, U, Attr>
     }
 
     pub fn reset(&mut self) {
-        let count_y = self.regions.len();
-        let count_x = self.regions[0].len();
-        self.regions = vec![vec![RegionRenderer::without_dimensions(self.fragment_shader, self.width, self.height, self.region_width, self.region_height); count_x]; count_y];
-
-
-        for x in 0..count_x {
-            for y in 0..count_y {
-                self.regions[y][x].from = (x * self.region_width, y * self.region_height);
-            }
-            if self.height % self.region_height != 0 {
-                self.regions[count_y - 1][x].region_height = self.height % self.region_height;
-            }
-        }
-
-        if self.width % self.region_width != 0 {
-            for y in 0..count_y {
-                self.regions[y][count_x - 1].region_width = self.width % self.region_width;
+        let count_x = self.width.div_ceil(self.region_width);
+        let count_y = self.height.div_ceil(self.region_height);
+
+        let mut renderer = RegionRenderer::without_dimensions(self.shading, self.width, self.height, self.region_width, self.region_height);
+        renderer.depth_prepass = self.depth_prepass;
+        renderer.simd = self.simd;
+        self.regions = vec![vec![renderer; count_x]; count_y];
+
+        for (y, row) in self.regions.iter_mut().enumerate() {
+            for (x, region) in row.iter_mut().enumerate() {
+                region.from = (x * self.region_width, y * self.region_height);
+                region.region_width = self.region_width.min(self.width - region.from.0);
+                region.region_height = self.region_height.min(self.height - region.from.1);
             }
         }
     }
@@ -208,77 +299,300 @@ impl<In, U, Attr> Program<In, U, Attr>
     }
 }
 
+/// How rasterized fragments become colors.
+pub enum Shading<U, Attr, const N: usize = 1> {
+    /// Fragment shader runs for every fragment passing the depth test.
+    Forward(fn(Vector3, Attr, &U) -> Vector3),
+    /// Geometry shader fills the g-buffer for visible fragments, lighting shader
+    /// turns the g-buffer into colors once per pixel.
+    Deferred(fn(Vector3, Attr, &U) -> GBufferSample, fn(Vector3, &GBufferSample, &U) -> Vector3),
+    /// Fragment shader writes `N` render targets of the framebuffer.
+    Targets(fn(Vector3, Attr, &U) -> [Vector4; N]),
+}
+
+impl<U, Attr, const N: usize> Clone for Shading<U, Attr, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<U, Attr, const N: usize> Copy for Shading<U, Attr, N> {}
+
 #[derive(Clone)]
-pub struct RegionRenderer<U, Attr>
+pub struct RegionRenderer<U, Attr, const N: usize = 1>
     where
         U: Clone,
-        Attr: Add<Output=Attr> + Clone + Mul<f64, Output=Attr>,
-        for<'a> &'a Attr: Add<Output=Attr> + Clone + Mul<f64, Output=Attr> {
+        Attr: Add<Output=Attr> + Clone + Mul<Scalar, Output=Attr>,
+        for<'a> &'a Attr: Add<Output=Attr> + Clone + Mul<Scalar, Output=Attr> {
     uniforms: Vec<U>,
     triangles_attrs: Vec<Attr>,
     vertices_positions: Vec<Vector3>,
     triangles: Vec<Triangle>,
-    fragment_shader: fn(Vector3, Attr, &U) -> Vector3,
+    shading: Shading<U, Attr, N>,
+    depth_prepass: bool,
+    simd: Level,
     from: (usize, usize),
     width: usize,
     height: usize,
     region_width: usize,
     region_height: usize,
+    depth_bounds: DepthBounds,
+    // kept between renders so that rasterizing doesn't allocate
+    row: Row,
 }
 
-impl<U, Attr> RegionRenderer<U, Attr>
+impl<U, Attr, const N: usize> RegionRenderer<U, Attr, N>
     where
         U: Clone,
-        Attr: Add<Output=Attr> + Clone + Mul<f64, Output=Attr>,
-        for<'a> &'a Attr: Add<Output=Attr> + Clone + Mul<f64, Output=Attr> {
-    pub fn without_dimensions(fragment_shader: fn(Vector3, Attr, &U) -> Vector3, width: usize, height: usize, region_width: usize, region_height: usize) -> Self {
+        Attr: Add<Output=Attr> + Clone + Mul<Scalar, Output=Attr>,
+        for<'a> &'a Attr: Add<Output=Attr> + Clone + Mul<Scalar, Output=Attr> {
+    pub fn without_dimensions(shading: Shading<U, Attr, N>, width: usize, height: usize, region_width: usize, region_height: usize) -> Self {
         Self {
             uniforms: Vec::new(),
             triangles_attrs: Vec::new(),
             triangles: Vec::new(),
             vertices_positions: Vec::new(),
-            fragment_shader,
+            shading,
+            depth_prepass: false,
+            simd: Level::detect(),
             from: (0, 0),
             width,
             height,
             region_width,
             region_height,
+            depth_bounds: DepthBounds::default(),
+            row: Row::default(),
+        }
+    }
+
+    fn tile(&self) -> Option<Tile> {
+        let to = (
+            (self.from.0 + self.region_width).min(self.width),
+            (self.from.1 + self.region_height).min(self.height),
+        );
+        if self.from.0 >= to.0 || self.from.1 >= to.1 {
+            None
+        } else {
+            Some(Tile { from: self.from, to, size: (self.width, self.height) })
+        }
+    }
+}
+
+/// Pixels covered by a region renderer.
+#[derive(Clone, Copy, Debug)]
+struct Tile {
+    from: (usize, usize),
+    to: (usize, usize),
+    // size of the whole framebuffer
+    size: (usize, usize),
+}
+
+impl Tile {
+    fn normalize(&self, x: usize, y: usize) -> (Scalar, Scalar) {
+        (x as Scalar / self.size.0 as Scalar, y as Scalar / self.size.1 as Scalar)
+    }
+
+    fn len(&self) -> usize {
+        (self.to.0 - self.from.0) * (self.to.1 - self.from.1)
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        (y - self.from.1) * (self.to.0 - self.from.0) + x - self.from.0
+    }
+}
+
+/// Hierarchical z of a single tile, the nearest and the farthest depth stored in it.
+#[derive(Clone, Copy, Debug)]
+struct DepthBounds {
+    min: Scalar,
+    max: Scalar,
+    // number of samples stored at `max`, the bound is exact until it drops to zero
+    at_max: usize,
+}
+
+impl DepthBounds {
+    fn scan(&mut self, buffer: &RegionBuffer, tile: &Tile) {
+        self.min = Scalar::INFINITY;
+        self.max = Scalar::NEG_INFINITY;
+        self.at_max = 0;
+        for y in tile.from.1..tile.to.1 {
+            for x in tile.from.0..tile.to.0 {
+                let depth = buffer.depth(x, y);
+                self.min = self.min.min(depth);
+                if depth > self.max {
+                    self.max = depth;
+                    self.at_max = 1;
+                } else if depth == self.max {
+                    self.at_max += 1;
+                }
+            }
+        }
+    }
+}
+
+impl Default for DepthBounds {
+    fn default() -> Self {
+        Self {
+            min: Scalar::INFINITY,
+            max: Scalar::INFINITY,
+            at_max: 0,
+        }
+    }
+}
+
+/// Walks all fragments of `triangles` inside `tile` which pass the depth test and hands
+/// them to `fragment` together with the index of their triangle and barycentric weights.
+/// `fragment` is expected to store the depth of every fragment it receives.
+fn rasterize<F>(triangles: &[Triangle], bounds: &mut DepthBounds, row: &mut Row, tile: &Tile, level: Level, buffer: &mut RegionBuffer, mut fragment: F)
+    where F: FnMut(&mut RegionBuffer, usize, usize, usize, Vector3, (Scalar, Scalar, Scalar)) {
+    let (tile_x0, tile_y0) = tile.normalize(tile.from.0, tile.from.1);
+    let (tile_x1, tile_y1) = tile.normalize(tile.to.0 - 1, tile.to.1 - 1);
+    row.resize(tile.to.0 - tile.from.0);
+
+    bounds.scan(buffer, tile);
+
+    for (i, triangle) in triangles.iter().enumerate() {
+        let (z_min, z_max) = triangle.depth_range(tile_x0, tile_y0, tile_x1, tile_y1);
+
+        // whole triangle is behind everything already stored in the tile
+        if z_min >= bounds.max {
+            continue;
+        }
+        // whole triangle is in front of everything, per pixel test can be skipped
+        let always_passes = z_max < bounds.min;
+
+        for y in tile.from.1..tile.to.1 {
+            let (_, norm_y) = tile.normalize(tile.from.0, y);
+            // depth and weights of the whole row at once, vectorized
+            triangle.barycentric.row(level, norm_y, tile.from.0, tile.size.0, row);
+
+            for x in tile.from.0..tile.to.0 {
+                let column = x - tile.from.0;
+                let (u, v, w) = (row.u[column], row.v[column], row.w[column]);
+                if v < 0.0 || w < 0.0 || u < 0.0 {
+                    continue;
+                }
+                let weights = (u, v, w);
+                let point = Vector3::new(row.x[column], norm_y, row.depth[column]);
+
+                // early z, occluded fragments never reach the shaders
+                let stored = buffer.depth(x, y);
+                if !always_passes && stored <= point.z {
+                    continue;
+                }
+
+                fragment(buffer, i, x, y, point, weights);
+                bounds.min = bounds.min.min(point.z);
+                if stored >= bounds.max {
+                    // saturating, a miscount only costs the rescan below
+                    bounds.at_max = bounds.at_max.saturating_sub(1);
+                }
+            }
+        }
+
+        // every sample at the farthest depth got replaced, the maximum has to be found again
+        if bounds.at_max == 0 {
+            bounds.scan(buffer, tile);
         }
     }
 }
 
 pub trait RenderRegion {
-    fn render_region(&self, buffer: &mut RegionBuffer);
+    fn render_region(&mut self, buffer: &mut RegionBuffer);
 }
 
 
-impl<U, Attr> RenderRegion for RegionRenderer<U, Attr>
+impl<U, Attr, const N: usize> RenderRegion for RegionRenderer<U, Attr, N>
     where
         U: Clone,
-        Attr: Add<Output=Attr> + Clone + Mul<f64, Output=Attr>,
-        for<'a> &'a Attr: Add<Output=Attr> + Clone + Mul<f64, Output=Attr> {
-    fn render_region(&self, buffer: &mut RegionBuffer) {
-        let mut i = 0;
-        for triangle in self.triangles.iter() {
-            //println!("{} {} {} {}", self.from.0, (self.from.0 + self.region_width), self.from.1, (self.from.1 + self.region_height));
-            let a0 = &self.triangles_attrs[triangle.indices.0];
-            let a1 = &self.triangles_attrs[triangle.indices.1];
-            let a2 = &self.triangles_attrs[triangle.indices.2];
-            for y in self.from.1..(self.from.1 + self.region_height) {
-                for x in self.from.0..(self.from.0 + self.region_width) {
-                    let norm_x = x as f64 / self.width as f64;
-                    let norm_y = y as f64 / self.height as f64;
-
-                    //println!("{} {} {}", norm_x, norm_y, triangle.zx * norm_x + triangle.zy * norm_y);
-                    let point = Vector3::new(norm_x, norm_y, triangle.a.z + triangle.zx * (norm_x - triangle.a.x) + triangle.zy * (norm_y - triangle.a.y));
-                    let interpolated = triangle.interpolate(point, &a0, &a1, &a2);
-
-                    if let Some(attr) = interpolated {
-                        buffer.set_color(x, y, (self.fragment_shader)(point, attr, &self.uniforms[i]), point.z);
-                    }
+        Attr: Add<Output=Attr> + Clone + Mul<Scalar, Output=Attr>,
+        for<'a> &'a Attr: Add<Output=Attr> + Clone + Mul<Scalar, Output=Attr> {
+    fn render_region(&mut self, buffer: &mut RegionBuffer) {
+        let tile = match self.tile() {
+            Some(tile) => tile,
+            None => return,
+        };
+        match self.shading {
+            Shading::Deferred(..) => {
+                assert!(buffer.has_gbuffer(), "deferred programs need a framebuffer created with Framebuffer::with_gbuffer");
+            }
+            Shading::Targets(..) => {
+                assert!(buffer.target_count() >= N, "program writes {} render targets, the framebuffer has {}", N, buffer.target_count());
+            }
+            Shading::Forward(..) => {}
+        }
+
+        let triangles = &self.triangles;
+        let attrs = &self.triangles_attrs;
+        let uniforms = &self.uniforms;
+        let interpolate = |i: usize, weights| {
+            let triangle = &triangles[i];
+            let (i0, i1, i2) = triangle.indices;
+            triangle.interpolate(weights, &attrs[i0], &attrs[i1], &attrs[i2])
+        };
+
+        if !self.depth_prepass {
+            match self.shading {
+                Shading::Forward(fragment_shader) => {
+                    rasterize(triangles, &mut self.depth_bounds, &mut self.row, &tile, self.simd, buffer, |buffer, i, x, y, point, weights| {
+                        buffer.set_color(x, y, fragment_shader(point, interpolate(i, weights), &uniforms[i]), point.z);
+                        buffer.overwrite_id(x, y, triangles[i].object_id);
+                    });
+                    return;
+                }
+                Shading::Targets(fragment_shader) => {
+                    rasterize(triangles, &mut self.depth_bounds, &mut self.row, &tile, self.simd, buffer, |buffer, i, x, y, point, weights| {
+                        buffer.set_targets(x, y, &fragment_shader(point, interpolate(i, weights), &uniforms[i]), point.z);
+                        buffer.overwrite_id(x, y, triangles[i].object_id);
+                    });
+                    return;
+                }
+                Shading::Deferred(..) => {}
+            }
+        }
+
+        // depth pre-pass, remembers which triangle ended up visible in every pixel
+        let mut visible = vec![None; tile.len()];
+        rasterize(triangles, &mut self.depth_bounds, &mut self.row, &tile, self.simd, buffer, |buffer, i, x, y, point, weights| {
+            buffer.set_depth(x, y, point.z);
+            buffer.overwrite_id(x, y, triangles[i].object_id);
+            visible[tile.index(x, y)] = Some((i, weights));
+        });
+
+        let fragments = || (tile.from.1..tile.to.1)
+            .flat_map(move |y| (tile.from.0..tile.to.0).map(move |x| (x, y)))
+            .filter_map(|(x, y)| visible[tile.index(x, y)].map(|(i, weights)| (x, y, i, weights)));
+
+        match self.shading {
+            Shading::Forward(fragment_shader) => {
+                for (x, y, i, weights) in fragments() {
+                    let (norm_x, norm_y) = tile.normalize(x, y);
+                    let point = Vector3::new(norm_x, norm_y, buffer.depth(x, y));
+                    buffer.overwrite_color(x, y, fragment_shader(point, interpolate(i, weights), &uniforms[i]));
+                }
+            }
+            Shading::Targets(fragment_shader) => {
+                for (x, y, i, weights) in fragments() {
+                    let (norm_x, norm_y) = tile.normalize(x, y);
+                    let point = Vector3::new(norm_x, norm_y, buffer.depth(x, y));
+                    buffer.overwrite_targets(x, y, &fragment_shader(point, interpolate(i, weights), &uniforms[i]));
+                }
+            }
+            Shading::Deferred(geometry_shader, lighting_shader) => {
+                for (x, y, i, weights) in fragments() {
+                    let (norm_x, norm_y) = tile.normalize(x, y);
+                    let point = Vector3::new(norm_x, norm_y, buffer.depth(x, y));
+                    buffer.set_gbuffer(x, y, geometry_shader(point, interpolate(i, weights), &uniforms[i]));
+                }
+
+                // lighting pass, runs once per covered pixel no matter the overdraw
+                for (x, y, i, _) in fragments() {
+                    let (norm_x, norm_y) = tile.normalize(x, y);
+                    let point = Vector3::new(norm_x, norm_y, buffer.depth(x, y));
+                    let sample = buffer.gbuffer(x, y);
+                    buffer.overwrite_color(x, y, lighting_shader(point, &sample, &uniforms[i]));
                 }
             }
-            i += 1;
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,15 @@
+//! Floating point type of the math types and of the framebuffer planes.
+//!
+//! `f64` by default, the `f32` feature switches everything to `f32`, which halves the
+//! memory traffic of rasterization at the cost of precision, mostly in depth.
+
+#[cfg(not(feature = "f32"))]
+pub type Scalar = f64;
+#[cfg(feature = "f32")]
+pub type Scalar = f32;
+
+/// Mathematical constants of `Scalar`.
+#[cfg(not(feature = "f32"))]
+pub use std::f64::consts;
+#[cfg(feature = "f32")]
+pub use std::f32::consts;
@@ -0,0 +1,206 @@
+use std::sync::Arc;
+
+use crate::format::{TexelBuffer, TexelFormat};
+use crate::scalar::Scalar;
+use crate::texture::{Filter, Sampler};
+use crate::vector::{Vector2, Vector4};
+
+/// Test between the reference depth and a stored depth, `true` means lit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compare {
+    Never,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Always,
+}
+
+impl Compare {
+    /// Compares `reference <op> stored`.
+    pub fn test(self, reference: Scalar, stored: Scalar) -> bool {
+        match self {
+            Compare::Never => false,
+            Compare::Less => reference < stored,
+            Compare::LessEqual => reference <= stored,
+            Compare::Greater => reference > stored,
+            Compare::GreaterEqual => reference >= stored,
+            Compare::Always => true,
+        }
+    }
+}
+
+/// Tap positions of the Poisson disk kernel, within the unit circle.
+#[allow(clippy::excessive_precision)] // rounded when `Scalar` is `f32`
+const POISSON_DISK: [(Scalar, Scalar); 16] = [
+    (-0.942_016_24, -0.399_062_16),
+    (0.945_586_09, -0.768_907_25),
+    (-0.094_184_101, -0.929_388_70),
+    (0.344_959_38, 0.293_877_60),
+    (-0.915_885_81, 0.457_714_32),
+    (-0.815_442_32, -0.879_124_64),
+    (-0.382_775_43, 0.276_768_45),
+    (0.974_843_98, 0.756_483_79),
+    (0.443_233_25, -0.975_115_54),
+    (0.537_429_81, -0.473_734_20),
+    (-0.264_969_11, -0.418_930_23),
+    (0.791_975_14, 0.190_901_88),
+    (-0.241_888_40, 0.997_065_07),
+    (-0.814_099_55, 0.914_375_90),
+    (0.199_841_26, 0.786_413_67),
+    (0.143_831_61, -0.141_007_90),
+];
+
+/// Where `DepthTexture::sample_compare` takes its taps, each of them filtered
+/// by the sampler, so `Filter::Bilinear` smooths every tap with 2x2 PCF.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kernel {
+    Single,
+    /// `(2 * radius + 1)` squared taps one texel apart.
+    Grid { radius: usize },
+    /// Up to 16 taps of a Poisson disk with `radius` in texels, softer and less
+    /// banded than a grid of the same cost.
+    Poisson { samples: usize, radius: Scalar },
+}
+
+/// Depth plane used as a shadow map, sampled by comparing against a reference depth.
+///
+/// Cloning is cheap, depths are shared. Texels outside of the texture with
+/// `Wrap::ClampToBorder` have the depth `border_color.x` of the sampler.
+#[derive(Clone, Debug)]
+pub struct DepthTexture {
+    width: usize,
+    height: usize,
+    depths: Arc<TexelBuffer>,
+    pub sampler: Sampler,
+    pub compare: Compare,
+    pub kernel: Kernel,
+    /// Subtracted from reference depths before comparing, keeps surfaces from shadowing
+    /// themselves because of the resolution and precision of the map. Negative for
+    /// `Compare::Greater` and `Compare::GreaterEqual`.
+    pub bias: Scalar,
+}
+
+impl DepthTexture {
+    /// Shadow map stored as `TexelFormat::Depth32F`.
+    pub fn new(width: usize, height: usize, depths: Vec<Scalar>) -> Self {
+        let depths = depths.into_iter().map(|depth| Vector4::new(depth, 0.0, 0.0, 1.0));
+        Self::from_buffer(width, height, TexelBuffer::from_values(TexelFormat::Depth32F, depths))
+    }
+
+    /// Shadow map of depths in a depth format. `Depth16` and `Depth24` only hold
+    /// depths in `[0, 1]`, the reference depth has to be in the same range.
+    pub fn from_buffer(width: usize, height: usize, depths: TexelBuffer) -> Self {
+        assert!(depths.format().is_depth(), "shadow maps need a depth format");
+        assert!(width > 0 && height > 0, "textures need at least one texel");
+        assert_eq!(depths.len(), width * height, "depth count does not match texture dimensions");
+        Self {
+            width,
+            height,
+            depths: Arc::new(depths),
+            sampler: Sampler::default().with_filter(Filter::Bilinear),
+            compare: Compare::LessEqual,
+            kernel: Kernel::Single,
+            bias: 0.0,
+        }
+    }
+
+    pub fn with_sampler(self, sampler: Sampler) -> Self {
+        Self { sampler, ..self }
+    }
+
+    pub fn with_compare(self, compare: Compare) -> Self {
+        Self { compare, ..self }
+    }
+
+    pub fn with_kernel(self, kernel: Kernel) -> Self {
+        Self { kernel, ..self }
+    }
+
+    pub fn with_bias(self, bias: Scalar) -> Self {
+        Self { bias, ..self }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn format(&self) -> TexelFormat {
+        self.depths.format()
+    }
+
+    pub fn depth(&self, x: usize, y: usize) -> Scalar {
+        self.depths.get(y * self.width + x).x
+    }
+
+    /// Depth at possibly out of range coordinates, resolved with the wrap modes of the sampler.
+    pub fn fetch(&self, x: i64, y: i64) -> Scalar {
+        match (self.sampler.wrap_u.apply(x, self.width), self.sampler.wrap_v.apply(y, self.height)) {
+            (Some(x), Some(y)) => self.depth(x, y),
+            _ => self.sampler.border_color.x,
+        }
+    }
+
+    fn test(&self, x: i64, y: i64, reference: Scalar) -> Scalar {
+        if self.compare.test(reference, self.fetch(x, y)) {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Single tap, with bilinear filtering the results of the four nearest comparisons are
+    /// blended, not the depths.
+    fn compare_tap(&self, uv: Vector2, reference: Scalar) -> Scalar {
+        let x = uv.x * self.width as Scalar;
+        let y = uv.y * self.height as Scalar;
+        match self.sampler.filter {
+            Filter::Nearest => self.test(x.floor() as i64, y.floor() as i64, reference),
+            Filter::Bilinear => {
+                let x = x - 0.5;
+                let y = y - 0.5;
+                let x0 = x.floor();
+                let y0 = y.floor();
+                let fx = x - x0;
+                let fy = y - y0;
+                let (x0, y0) = (x0 as i64, y0 as i64);
+
+                let top = self.test(x0, y0, reference) * (1.0 - fx) + self.test(x0 + 1, y0, reference) * fx;
+                let bottom = self.test(x0, y0 + 1, reference) * (1.0 - fx) + self.test(x0 + 1, y0 + 1, reference) * fx;
+                top * (1.0 - fy) + bottom * fy
+            }
+        }
+    }
+
+    /// Fraction of the kernel passing the comparison against `reference`, 1 is fully lit.
+    pub fn sample_compare(&self, uv: Vector2, reference: Scalar) -> Scalar {
+        let reference = reference - self.bias;
+        let texel = Vector2::new(1.0 / self.width as Scalar, 1.0 / self.height as Scalar);
+        let offset = |x: Scalar, y: Scalar| uv + Vector2::new(x * texel.x, y * texel.y);
+        match self.kernel {
+            Kernel::Single => self.compare_tap(uv, reference),
+            Kernel::Grid { radius } => {
+                let radius = radius as i64;
+                let mut sum = 0.0;
+                for y in -radius..=radius {
+                    for x in -radius..=radius {
+                        sum += self.compare_tap(offset(x as Scalar, y as Scalar), reference);
+                    }
+                }
+                sum / ((2 * radius + 1) * (2 * radius + 1)) as Scalar
+            }
+            Kernel::Poisson { samples, radius } => {
+                let samples = samples.clamp(1, POISSON_DISK.len());
+                let sum: Scalar = POISSON_DISK[..samples]
+                    .iter()
+                    .map(|&(x, y)| self.compare_tap(offset(x * radius, y * radius), reference))
+                    .sum();
+                sum / samples as Scalar
+            }
+        }
+    }
+}
@@ -0,0 +1,491 @@
+//! Explicit SIMD paths of the rasterizer and of the matrix and vector operations, picked at
+//! runtime. The vector operations work on slices, a single product is faster in the scalar
+//! code of the operators than behind the dispatch.
+//!
+//! Every lane does the operations of the scalar code in the same order and without fused
+//! multiply-adds, so all levels produce bit for bit the same results.
+
+use std::slice;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::matrix::Matrix4;
+use crate::scalar::Scalar;
+use crate::vector::{Vector3, Vector4};
+
+/// Instruction sets the SIMD paths are written for, in increasing order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Scalar,
+    Sse2,
+    Avx2,
+}
+
+impl Level {
+    /// Best level the running CPU supports, detected on the first call only.
+    pub fn detect() -> Self {
+        // 0 until the first detection, then the level plus one
+        static DETECTED: AtomicU8 = AtomicU8::new(0);
+        match DETECTED.load(Ordering::Relaxed) {
+            0 => {
+                let level = Self::detect_uncached();
+                DETECTED.store(level as u8 + 1, Ordering::Relaxed);
+                level
+            }
+            1 => Level::Scalar,
+            2 => Level::Sse2,
+            _ => Level::Avx2,
+        }
+    }
+
+    fn detect_uncached() -> Self {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx2") {
+                return Level::Avx2;
+            }
+            if is_x86_feature_detected!("sse2") {
+                return Level::Sse2;
+            }
+        }
+        Level::Scalar
+    }
+
+    /// `self` lowered to what the running CPU supports.
+    pub fn supported(self) -> Self {
+        self.min(Self::detect())
+    }
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Self::detect()
+    }
+}
+
+/// Depth plane and barycentric setup of a screen space triangle.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Barycentric {
+    pub a: Vector3,
+    pub v0: Vector3,
+    pub v1: Vector3,
+    pub d00: Scalar,
+    pub d01: Scalar,
+    pub d11: Scalar,
+    pub inv: Scalar,
+    pub zx: Scalar,
+    pub zy: Scalar,
+}
+
+/// Values of a row of pixels evaluated by `Barycentric::row`, indexed from the first pixel.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Row {
+    pub x: Vec<Scalar>,
+    pub depth: Vec<Scalar>,
+    pub u: Vec<Scalar>,
+    pub v: Vec<Scalar>,
+    pub w: Vec<Scalar>,
+}
+
+impl Row {
+    /// Makes room for `len` pixels, allocating only when the row grows past its capacity.
+    pub fn resize(&mut self, len: usize) {
+        for values in [&mut self.x, &mut self.depth, &mut self.u, &mut self.v, &mut self.w] {
+            values.resize(len, 0.0);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+}
+
+impl Barycentric {
+    pub fn depth_at(&self, x: Scalar, y: Scalar) -> Scalar {
+        self.a.z + self.zx * (x - self.a.x) + self.zy * (y - self.a.y)
+    }
+
+    /// Weights of the three vertices at `point`, negative ones outside of the triangle.
+    pub fn weights(&self, point: Vector3) -> (Scalar, Scalar, Scalar) {
+        let v2 = point - self.a;
+        let d20 = v2 * self.v0;
+        let d21 = v2 * self.v1;
+
+        let v = (self.d11 * d20 - self.d01 * d21) * self.inv;
+        let w = (self.d00 * d21 - self.d01 * d20) * self.inv;
+        (1.0 - v - w, v, w)
+    }
+
+    /// Evaluates the pixels `x0..x0 + row.len()` of the row at the normalized height `y`,
+    /// pixel `x` is at the normalized `x / width`. `level` has to be supported by the CPU.
+    pub fn row(&self, level: Level, y: Scalar, x0: usize, width: usize, row: &mut Row) {
+        let done = match level {
+            Level::Scalar => 0,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Level::Sse2 => unsafe { x86::row_sse2(self, y, x0, width, row) },
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Level::Avx2 => unsafe { x86::row_avx2(self, y, x0, width, row) },
+            #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+            _ => 0,
+        };
+        // pixels left over by the lanes
+        for i in done..row.len() {
+            let x = (x0 + i) as Scalar / width as Scalar;
+            let depth = self.depth_at(x, y);
+            let (u, v, w) = self.weights(Vector3::new(x, y, depth));
+            row.x[i] = x;
+            row.depth[i] = depth;
+            row.u[i] = u;
+            row.v[i] = v;
+            row.w[i] = w;
+        }
+    }
+}
+
+/// `a * b` at the given level. A single product is faster in the scalar code of the
+/// operators, the vector levels are here to check them against it.
+pub fn mul_matrix4(level: Level, a: &Matrix4, b: &Matrix4) -> Matrix4 {
+    match level.supported() {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        level @ (Level::Sse2 | Level::Avx2) => unsafe { x86::mul_matrix4(level, a, b) },
+        _ => a * b,
+    }
+}
+
+/// `m * v` at the given level, like `mul_matrix4` slower than the operator unless the level
+/// is `Scalar`. Use `mul_matrix4_vector4s` for many vectors.
+pub fn mul_matrix4_vector4(level: Level, m: &Matrix4, v: &Vector4) -> Vector4 {
+    match level.supported() {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        level @ (Level::Sse2 | Level::Avx2) => unsafe { x86::mul_matrix4_vector4(level, m, v) },
+        _ => m * v,
+    }
+}
+
+/// `out[i] = m * vectors[i]` at the given level.
+pub fn mul_matrix4_vector4s(level: Level, m: &Matrix4, vectors: &[Vector4], out: &mut [Vector4]) {
+    assert_eq!(vectors.len(), out.len(), "input and output lengths differ");
+    match level.supported() {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        level @ (Level::Sse2 | Level::Avx2) => unsafe { x86::mul_matrix4_vector4s(level, m, vectors, out) },
+        _ => {
+            for (v, out) in vectors.iter().zip(out.iter_mut()) {
+                *out = m * v;
+            }
+        }
+    }
+}
+
+/// `out[i] = a[i] + b[i]` at the given level.
+pub fn add_vector4s(level: Level, a: &[Vector4], b: &[Vector4], out: &mut [Vector4]) {
+    elementwise(level, Elementwise::Add, a, b, out)
+}
+
+/// `out[i] = a[i] - b[i]` at the given level.
+pub fn sub_vector4s(level: Level, a: &[Vector4], b: &[Vector4], out: &mut [Vector4]) {
+    elementwise(level, Elementwise::Sub, a, b, out)
+}
+
+/// `out[i] = a[i] * factor` at the given level.
+pub fn scale_vector4s(level: Level, a: &[Vector4], factor: Scalar, out: &mut [Vector4]) {
+    elementwise(level, Elementwise::Scale(factor), a, a, out)
+}
+
+/// Operation applied to every component of slices of vectors.
+#[derive(Clone, Copy, Debug)]
+enum Elementwise {
+    Add,
+    Sub,
+    // of the first operand, the second one is ignored
+    Scale(Scalar),
+}
+
+impl Elementwise {
+    fn apply(self, a: Scalar, b: Scalar) -> Scalar {
+        match self {
+            Elementwise::Add => a + b,
+            Elementwise::Sub => a - b,
+            Elementwise::Scale(factor) => a * factor,
+        }
+    }
+}
+
+fn elementwise(level: Level, op: Elementwise, a: &[Vector4], b: &[Vector4], out: &mut [Vector4]) {
+    assert!(a.len() == out.len() && b.len() == out.len(), "input and output lengths differ");
+    // `Vector4` is `repr(C)`, four scalars without padding
+    let a = unsafe { slice::from_raw_parts(a.as_ptr() as *const Scalar, a.len() * 4) };
+    let b = unsafe { slice::from_raw_parts(b.as_ptr() as *const Scalar, b.len() * 4) };
+    let out = unsafe { slice::from_raw_parts_mut(out.as_mut_ptr() as *mut Scalar, out.len() * 4) };
+
+    let done = match level.supported() {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Level::Sse2 => unsafe { x86::elementwise_sse2(op, a, b, out) },
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Level::Avx2 => unsafe { x86::elementwise_avx2(op, a, b, out) },
+        _ => 0,
+    };
+    // components left over by the lanes
+    for i in done..out.len() {
+        out[i] = op.apply(a[i], b[i]);
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86 {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
+
+    use super::{Barycentric, Elementwise, Level, Row};
+    use crate::matrix::Matrix4;
+    use crate::scalar::Scalar;
+    use crate::vector::Vector4;
+
+    /// One register of `Scalar` lanes. Only called from functions enabling the
+    /// instruction set of the register, where everything gets inlined.
+    trait Lanes: Copy {
+        const COUNT: usize;
+        unsafe fn splat(value: Scalar) -> Self;
+        /// `0, 1, 2, ...`
+        unsafe fn ramp() -> Self;
+        unsafe fn load(from: *const Scalar) -> Self;
+        unsafe fn store(self, to: *mut Scalar);
+        unsafe fn add(self, rhs: Self) -> Self;
+        unsafe fn sub(self, rhs: Self) -> Self;
+        unsafe fn mul(self, rhs: Self) -> Self;
+        unsafe fn div(self, rhs: Self) -> Self;
+    }
+
+    #[derive(Clone, Copy)]
+    struct Sse(#[cfg(not(feature = "f32"))] __m128d, #[cfg(feature = "f32")] __m128);
+
+    #[derive(Clone, Copy)]
+    struct Avx(#[cfg(not(feature = "f32"))] __m256d, #[cfg(feature = "f32")] __m256);
+
+    #[cfg(not(feature = "f32"))]
+    impl Lanes for Sse {
+        const COUNT: usize = 2;
+        #[inline(always)]
+        unsafe fn splat(value: Scalar) -> Self { Sse(_mm_set1_pd(value)) }
+        #[inline(always)]
+        unsafe fn ramp() -> Self { Sse(_mm_set_pd(1.0, 0.0)) }
+        #[inline(always)]
+        unsafe fn load(from: *const Scalar) -> Self { Sse(_mm_loadu_pd(from)) }
+        #[inline(always)]
+        unsafe fn store(self, to: *mut Scalar) { _mm_storeu_pd(to, self.0) }
+        #[inline(always)]
+        unsafe fn add(self, rhs: Self) -> Self { Sse(_mm_add_pd(self.0, rhs.0)) }
+        #[inline(always)]
+        unsafe fn sub(self, rhs: Self) -> Self { Sse(_mm_sub_pd(self.0, rhs.0)) }
+        #[inline(always)]
+        unsafe fn mul(self, rhs: Self) -> Self { Sse(_mm_mul_pd(self.0, rhs.0)) }
+        #[inline(always)]
+        unsafe fn div(self, rhs: Self) -> Self { Sse(_mm_div_pd(self.0, rhs.0)) }
+    }
+
+    #[cfg(feature = "f32")]
+    impl Lanes for Sse {
+        const COUNT: usize = 4;
+        #[inline(always)]
+        unsafe fn splat(value: Scalar) -> Self { Sse(_mm_set1_ps(value)) }
+        #[inline(always)]
+        unsafe fn ramp() -> Self { Sse(_mm_set_ps(3.0, 2.0, 1.0, 0.0)) }
+        #[inline(always)]
+        unsafe fn load(from: *const Scalar) -> Self { Sse(_mm_loadu_ps(from)) }
+        #[inline(always)]
+        unsafe fn store(self, to: *mut Scalar) { _mm_storeu_ps(to, self.0) }
+        #[inline(always)]
+        unsafe fn add(self, rhs: Self) -> Self { Sse(_mm_add_ps(self.0, rhs.0)) }
+        #[inline(always)]
+        unsafe fn sub(self, rhs: Self) -> Self { Sse(_mm_sub_ps(self.0, rhs.0)) }
+        #[inline(always)]
+        unsafe fn mul(self, rhs: Self) -> Self { Sse(_mm_mul_ps(self.0, rhs.0)) }
+        #[inline(always)]
+        unsafe fn div(self, rhs: Self) -> Self { Sse(_mm_div_ps(self.0, rhs.0)) }
+    }
+
+    #[cfg(not(feature = "f32"))]
+    impl Lanes for Avx {
+        const COUNT: usize = 4;
+        #[inline(always)]
+        unsafe fn splat(value: Scalar) -> Self { Avx(_mm256_set1_pd(value)) }
+        #[inline(always)]
+        unsafe fn ramp() -> Self { Avx(_mm256_set_pd(3.0, 2.0, 1.0, 0.0)) }
+        #[inline(always)]
+        unsafe fn load(from: *const Scalar) -> Self { Avx(_mm256_loadu_pd(from)) }
+        #[inline(always)]
+        unsafe fn store(self, to: *mut Scalar) { _mm256_storeu_pd(to, self.0) }
+        #[inline(always)]
+        unsafe fn add(self, rhs: Self) -> Self { Avx(_mm256_add_pd(self.0, rhs.0)) }
+        #[inline(always)]
+        unsafe fn sub(self, rhs: Self) -> Self { Avx(_mm256_sub_pd(self.0, rhs.0)) }
+        #[inline(always)]
+        unsafe fn mul(self, rhs: Self) -> Self { Avx(_mm256_mul_pd(self.0, rhs.0)) }
+        #[inline(always)]
+        unsafe fn div(self, rhs: Self) -> Self { Avx(_mm256_div_pd(self.0, rhs.0)) }
+    }
+
+    #[cfg(feature = "f32")]
+    impl Lanes for Avx {
+        const COUNT: usize = 8;
+        #[inline(always)]
+        unsafe fn splat(value: Scalar) -> Self { Avx(_mm256_set1_ps(value)) }
+        #[inline(always)]
+        unsafe fn ramp() -> Self { Avx(_mm256_set_ps(7.0, 6.0, 5.0, 4.0, 3.0, 2.0, 1.0, 0.0)) }
+        #[inline(always)]
+        unsafe fn load(from: *const Scalar) -> Self { Avx(_mm256_loadu_ps(from)) }
+        #[inline(always)]
+        unsafe fn store(self, to: *mut Scalar) { _mm256_storeu_ps(to, self.0) }
+        #[inline(always)]
+        unsafe fn add(self, rhs: Self) -> Self { Avx(_mm256_add_ps(self.0, rhs.0)) }
+        #[inline(always)]
+        unsafe fn sub(self, rhs: Self) -> Self { Avx(_mm256_sub_ps(self.0, rhs.0)) }
+        #[inline(always)]
+        unsafe fn mul(self, rhs: Self) -> Self { Avx(_mm256_mul_ps(self.0, rhs.0)) }
+        #[inline(always)]
+        unsafe fn div(self, rhs: Self) -> Self { Avx(_mm256_div_ps(self.0, rhs.0)) }
+    }
+
+    /// `Barycentric::row` for whole registers, returns the number of pixels done.
+    #[inline(always)]
+    unsafe fn row<L: Lanes>(b: &Barycentric, y: Scalar, x0: usize, width: usize, row: &mut Row) -> usize {
+        let (ax, az) = (L::splat(b.a.x), L::splat(b.a.z));
+        let (zx, zy) = (L::splat(b.zx), L::splat(b.zy));
+        let (v0x, v0y, v0z) = (L::splat(b.v0.x), L::splat(b.v0.y), L::splat(b.v0.z));
+        let (v1x, v1y, v1z) = (L::splat(b.v1.x), L::splat(b.v1.y), L::splat(b.v1.z));
+        let (d00, d01, d11, inv) = (L::splat(b.d00), L::splat(b.d01), L::splat(b.d11), L::splat(b.inv));
+        let width = L::splat(width as Scalar);
+        let one = L::splat(1.0);
+
+        // the same operations as the scalar code, in the same order
+        let dy = L::splat(y - b.a.y);
+        let zy_dy = zy.mul(dy);
+        let ramp = L::ramp();
+        let mut i = 0;
+        while i + L::COUNT <= row.len() {
+            let x = L::splat((x0 + i) as Scalar).add(ramp).div(width);
+            let dx = x.sub(ax);
+            let depth = az.add(zx.mul(dx)).add(zy_dy);
+            let dz = depth.sub(az);
+            let d20 = dx.mul(v0x).add(dy.mul(v0y)).add(dz.mul(v0z));
+            let d21 = dx.mul(v1x).add(dy.mul(v1y)).add(dz.mul(v1z));
+            let v = d11.mul(d20).sub(d01.mul(d21)).mul(inv);
+            let w = d00.mul(d21).sub(d01.mul(d20)).mul(inv);
+            let u = one.sub(v).sub(w);
+
+            x.store(row.x.as_mut_ptr().add(i));
+            depth.store(row.depth.as_mut_ptr().add(i));
+            u.store(row.u.as_mut_ptr().add(i));
+            v.store(row.v.as_mut_ptr().add(i));
+            w.store(row.w.as_mut_ptr().add(i));
+            i += L::COUNT;
+        }
+        i
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn row_sse2(b: &Barycentric, y: Scalar, x0: usize, width: usize, out: &mut Row) -> usize {
+        row::<Sse>(b, y, x0, width, out)
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn row_avx2(b: &Barycentric, y: Scalar, x0: usize, width: usize, out: &mut Row) -> usize {
+        row::<Avx>(b, y, x0, width, out)
+    }
+
+    /// Column `j` of `a * b` is the sum of the columns of `a` weighted by column `j` of `b`,
+    /// summed in the order of the scalar dot products. Registers hold parts of a column.
+    #[inline(always)]
+    unsafe fn mul_columns<L: Lanes>(a: &[Vector4; 4], b: &[Vector4], out: &mut [Vector4]) {
+        for (column, out) in b.iter().zip(out.iter_mut()) {
+            let weights = [column.x, column.y, column.z, column.w];
+            let mut offset = 0;
+            while offset < 4 {
+                // `Vector4` is `repr(C)`, the pointers come from the whole vector so that they
+                // may reach all of its components
+                let part = |i: usize| L::load((&a[i] as *const Vector4 as *const Scalar).add(offset));
+                let sum = part(0).mul(L::splat(weights[0]))
+                    .add(part(1).mul(L::splat(weights[1])))
+                    .add(part(2).mul(L::splat(weights[2])))
+                    .add(part(3).mul(L::splat(weights[3])));
+                sum.store((out as *mut Vector4 as *mut Scalar).add(offset));
+                offset += L::COUNT;
+            }
+        }
+    }
+
+    #[target_feature(enable = "sse2")]
+    unsafe fn mul_columns_sse2(a: &[Vector4; 4], b: &[Vector4], out: &mut [Vector4]) {
+        mul_columns::<Sse>(a, b, out)
+    }
+
+    #[cfg(not(feature = "f32"))]
+    #[target_feature(enable = "avx2")]
+    unsafe fn mul_columns_avx2(a: &[Vector4; 4], b: &[Vector4], out: &mut [Vector4]) {
+        mul_columns::<Avx>(a, b, out)
+    }
+
+    /// A column of four `f32` fits in an SSE register already.
+    #[cfg(feature = "f32")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn mul_columns_avx2(a: &[Vector4; 4], b: &[Vector4], out: &mut [Vector4]) {
+        mul_columns::<Sse>(a, b, out)
+    }
+
+    unsafe fn mul_columns_at(level: Level, a: &[Vector4; 4], b: &[Vector4], out: &mut [Vector4]) {
+        if level == Level::Avx2 {
+            mul_columns_avx2(a, b, out)
+        } else {
+            mul_columns_sse2(a, b, out)
+        }
+    }
+
+    pub unsafe fn mul_matrix4(level: Level, a: &Matrix4, b: &Matrix4) -> Matrix4 {
+        let mut columns = [Vector4::zero(); 4];
+        mul_columns_at(level, &a.as_columns(), &b.as_columns(), &mut columns);
+        Matrix4::from_columns(columns)
+    }
+
+    pub unsafe fn mul_matrix4_vector4(level: Level, m: &Matrix4, v: &Vector4) -> Vector4 {
+        let mut out = [Vector4::zero()];
+        mul_columns_at(level, &m.as_columns(), &[*v], &mut out);
+        out[0]
+    }
+
+    pub unsafe fn mul_matrix4_vector4s(level: Level, m: &Matrix4, vectors: &[Vector4], out: &mut [Vector4]) {
+        mul_columns_at(level, &m.as_columns(), vectors, out)
+    }
+
+    /// `Elementwise::apply` for whole registers, returns the number of components done.
+    #[inline(always)]
+    unsafe fn elementwise<L: Lanes>(op: Elementwise, a: &[Scalar], b: &[Scalar], out: &mut [Scalar]) -> usize {
+        #[inline(always)]
+        unsafe fn zip<L: Lanes>(a: &[Scalar], b: &[Scalar], out: &mut [Scalar], f: impl Fn(L, L) -> L) -> usize {
+            let mut i = 0;
+            while i + L::COUNT <= out.len() {
+                f(L::load(a.as_ptr().add(i)), L::load(b.as_ptr().add(i))).store(out.as_mut_ptr().add(i));
+                i += L::COUNT;
+            }
+            i
+        }
+
+        match op {
+            Elementwise::Add => zip::<L>(a, b, out, |a, b| a.add(b)),
+            Elementwise::Sub => zip::<L>(a, b, out, |a, b| a.sub(b)),
+            Elementwise::Scale(factor) => {
+                let factor = L::splat(factor);
+                zip::<L>(a, b, out, |a, _| a.mul(factor))
+            }
+        }
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn elementwise_sse2(op: Elementwise, a: &[Scalar], b: &[Scalar], out: &mut [Scalar]) -> usize {
+        elementwise::<Sse>(op, a, b, out)
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn elementwise_avx2(op: Elementwise, a: &[Scalar], b: &[Scalar], out: &mut [Scalar]) -> usize {
+        elementwise::<Avx>(op, a, b, out)
+    }
+}
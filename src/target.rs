@@ -0,0 +1,92 @@
+use crate::format::{TexelBuffer, TexelFormat};
+use crate::vector::Vector4;
+
+/// How a new value is combined with the one already stored in a render target.
+/// Integer targets are always replaced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Blend {
+    Replace,
+    /// `src * src.w + dst * (1 - src.w)`
+    Alpha,
+    /// `src + dst`
+    Additive,
+    /// `src * dst`
+    Multiply,
+}
+
+impl Blend {
+    pub fn apply(self, src: Vector4, dst: Vector4) -> Vector4 {
+        match self {
+            Blend::Replace => src,
+            Blend::Alpha => src * src.w + dst * (1.0 - src.w),
+            Blend::Additive => src + dst,
+            Blend::Multiply => src.component_mul(dst),
+        }
+    }
+}
+
+/// Description of a single render target, used to allocate its plane.
+#[derive(Clone, Copy, Debug)]
+pub struct TargetDescription {
+    pub format: TexelFormat,
+    pub blend: Blend,
+    pub clear_value: Vector4,
+}
+
+impl TargetDescription {
+    pub fn new(format: TexelFormat) -> Self {
+        Self {
+            format,
+            blend: Blend::Replace,
+            clear_value: Vector4::zero(),
+        }
+    }
+
+    pub fn with_blend(self, blend: Blend) -> Self {
+        Self { blend, ..self }
+    }
+
+    pub fn with_clear_value(self, clear_value: Vector4) -> Self {
+        Self { clear_value, ..self }
+    }
+}
+
+/// Plane of a framebuffer written by multiple render target fragment shaders.
+pub struct RenderTarget {
+    description: TargetDescription,
+    values: TexelBuffer,
+}
+
+impl RenderTarget {
+    pub fn new(description: TargetDescription, len: usize) -> Self {
+        Self {
+            values: TexelBuffer::new(description.format, len, description.clear_value),
+            description,
+        }
+    }
+
+    pub fn description(&self) -> &TargetDescription {
+        &self.description
+    }
+
+    pub fn values(&self) -> &TexelBuffer {
+        &self.values
+    }
+
+    pub fn get(&self, index: usize) -> Vector4 {
+        self.values.get(index)
+    }
+
+    pub fn write(&mut self, index: usize, value: Vector4) {
+        let blended = if self.description.format.is_integer() {
+            value
+        } else {
+            self.description.blend.apply(value, self.values.get(index))
+        };
+        self.values.set(index, blended);
+    }
+
+    pub fn clear(&mut self) {
+        self.values.fill(self.description.clear_value);
+    }
+}
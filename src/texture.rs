@@ -1,5 +1,608 @@
-use crate::vector::{Vector2, Vector3};
+use std::sync::Arc;
+
+use crate::format::{TexelBuffer, TexelFormat};
+use crate::scalar::{consts, Scalar};
+use crate::utils::clamp;
+use crate::vector::{Vector2, Vector3, Vector4};
 
 pub trait Texture {
     fn sample(&self, uv: Vector2) -> Vector3;
-}
\ No newline at end of file
+
+    /// Samples the given level of detail, textures without mipmaps ignore it.
+    fn sample_lod(&self, uv: Vector2, _lod: Scalar) -> Vector3 {
+        self.sample(uv)
+    }
+
+    /// Samples with the level of detail derived from derivatives of `uv` along screen x and y.
+    fn sample_grad(&self, uv: Vector2, _ddx: Vector2, _ddy: Vector2) -> Vector3 {
+        self.sample(uv)
+    }
+}
+
+impl<T: Texture + ?Sized> Texture for &T {
+    fn sample(&self, uv: Vector2) -> Vector3 {
+        (**self).sample(uv)
+    }
+
+    fn sample_lod(&self, uv: Vector2, lod: Scalar) -> Vector3 {
+        (**self).sample_lod(uv, lod)
+    }
+
+    fn sample_grad(&self, uv: Vector2, ddx: Vector2, ddy: Vector2) -> Vector3 {
+        (**self).sample_grad(uv, ddx, ddy)
+    }
+}
+
+impl<T: Texture + ?Sized> Texture for Box<T> {
+    fn sample(&self, uv: Vector2) -> Vector3 {
+        (**self).sample(uv)
+    }
+
+    fn sample_lod(&self, uv: Vector2, lod: Scalar) -> Vector3 {
+        (**self).sample_lod(uv, lod)
+    }
+
+    fn sample_grad(&self, uv: Vector2, ddx: Vector2, ddy: Vector2) -> Vector3 {
+        (**self).sample_grad(uv, ddx, ddy)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+}
+
+/// How levels of detail are combined when sampling a texture with mipmaps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mipmap {
+    /// Only the base level is used.
+    None,
+    Nearest,
+    /// Blends two nearest levels, with bilinear filtering gives trilinear filtering.
+    Linear,
+}
+
+/// Filter used to downsample the levels of a mip chain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MipmapFilter {
+    /// Average of the texels covered by the footprint of the smaller texel.
+    Box,
+    /// Kaiser windowed sinc, `alpha` controls the window shape (4 is a good default).
+    Kaiser { alpha: Scalar },
+}
+
+impl MipmapFilter {
+    // support in texels of the smaller level
+    fn radius(self) -> Scalar {
+        match self {
+            MipmapFilter::Box => 0.5,
+            MipmapFilter::Kaiser { .. } => 3.0,
+        }
+    }
+
+    /// Weight of a source texel spanning `from..to` for a texel centered at zero,
+    /// distances are in texels of the smaller level.
+    fn weight(self, from: Scalar, to: Scalar) -> Scalar {
+        match self {
+            MipmapFilter::Box => (to.min(0.5) - from.max(-0.5)).max(0.0),
+            MipmapFilter::Kaiser { alpha } => {
+                let t = (from + to) * 0.5;
+                let radius = self.radius();
+                if t.abs() >= radius {
+                    return 0.0;
+                }
+                let window = bessel_i0(alpha * (1.0 - (t / radius).powi(2)).sqrt()) / bessel_i0(alpha);
+                sinc(t) * window * (to - from)
+            }
+        }
+    }
+}
+
+fn sinc(x: Scalar) -> Scalar {
+    if x.abs() < 1e-9 {
+        1.0
+    } else {
+        let x = x * consts::PI;
+        x.sin() / x
+    }
+}
+
+// zeroth order modified bessel function of the first kind
+fn bessel_i0(x: Scalar) -> Scalar {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half = x * 0.5;
+    for k in 1..32 {
+        term *= half / k as Scalar;
+        sum += term * term;
+    }
+    sum
+}
+
+/// Normalized filter weights of the source texels contributing to every texel of a line
+/// shrunk from `length` to `new_length` texels.
+fn resample_weights(length: usize, new_length: usize, filter: MipmapFilter) -> Vec<Vec<(usize, Scalar)>> {
+    let scale = length as Scalar / new_length as Scalar;
+    (0..new_length)
+        .map(|i| {
+            let center = (i as Scalar + 0.5) * scale;
+            let reach = filter.radius() * scale;
+            let from = (center - reach).floor().max(0.0) as usize;
+            let to = ((center + reach).ceil() as usize).min(length);
+            let mut weights: Vec<(usize, Scalar)> = (from..to)
+                .map(|j| (j, filter.weight((j as Scalar - center) / scale, (j as Scalar + 1.0 - center) / scale)))
+                .filter(|&(_, weight)| weight != 0.0)
+                .collect();
+            let total: Scalar = weights.iter().map(|&(_, weight)| weight).sum();
+            for (_, weight) in weights.iter_mut() {
+                *weight /= total;
+            }
+            weights
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+struct MipLevel {
+    width: usize,
+    height: usize,
+    texels: TexelBuffer,
+}
+
+impl MipLevel {
+    /// Filters linear values, so sRGB levels are decoded before and encoded after filtering.
+    fn downsample(&self, filter: MipmapFilter) -> MipLevel {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+
+        let columns = resample_weights(self.width, width, filter);
+        let mut horizontal = vec![Vector4::zero(); width * self.height];
+        for y in 0..self.height {
+            for (x, weights) in columns.iter().enumerate() {
+                horizontal[y * width + x] = weights.iter()
+                    .fold(Vector4::zero(), |sum, &(i, weight)| sum + self.texels.get(y * self.width + i) * weight);
+            }
+        }
+
+        let rows = resample_weights(self.height, height, filter);
+        let mut texels = vec![Vector4::zero(); width * height];
+        for (y, weights) in rows.iter().enumerate() {
+            for x in 0..width {
+                texels[y * width + x] = weights.iter()
+                    .fold(Vector4::zero(), |sum, &(i, weight)| sum + horizontal[i * width + x] * weight);
+            }
+        }
+
+        MipLevel { width, height, texels: TexelBuffer::from_values(self.texels.format(), texels) }
+    }
+}
+
+/// What happens with texture coordinates outside of `[0, 1]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wrap {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+    /// Texels outside of the texture have the border color of the sampler.
+    ClampToBorder,
+}
+
+impl Wrap {
+    /// Maps a texel index to the range `0..size`, `None` means the border.
+    pub fn apply(self, index: i64, size: usize) -> Option<usize> {
+        let size = size as i64;
+        match self {
+            Wrap::Repeat => Some(index.rem_euclid(size) as usize),
+            Wrap::MirroredRepeat => {
+                let index = index.rem_euclid(2 * size);
+                Some(if index < size { index } else { 2 * size - 1 - index } as usize)
+            }
+            Wrap::ClampToEdge => Some(clamp(index, 0, size - 1) as usize),
+            Wrap::ClampToBorder => if index >= 0 && index < size {
+                Some(index as usize)
+            } else {
+                None
+            },
+        }
+    }
+}
+
+/// Filtering and addressing state used when sampling a texture.
+#[derive(Clone, Copy, Debug)]
+pub struct Sampler {
+    pub filter: Filter,
+    pub mipmap: Mipmap,
+    /// Maximal number of samples taken along the longer axis of the footprint
+    /// by `sample_grad`, 1 disables anisotropic filtering.
+    pub max_anisotropy: usize,
+    pub wrap_u: Wrap,
+    pub wrap_v: Wrap,
+    /// Wrap mode of the third coordinate of volume textures.
+    pub wrap_w: Wrap,
+    pub border_color: Vector3,
+}
+
+impl Sampler {
+    pub fn with_filter(self, filter: Filter) -> Self {
+        Self { filter, ..self }
+    }
+
+    pub fn with_mipmap(self, mipmap: Mipmap) -> Self {
+        Self { mipmap, ..self }
+    }
+
+    pub fn with_max_anisotropy(self, max_anisotropy: usize) -> Self {
+        Self { max_anisotropy: max_anisotropy.max(1), ..self }
+    }
+
+    pub fn with_wrap(self, wrap_u: Wrap, wrap_v: Wrap) -> Self {
+        Self { wrap_u, wrap_v, ..self }
+    }
+
+    pub fn with_wrap_w(self, wrap_w: Wrap) -> Self {
+        Self { wrap_w, ..self }
+    }
+
+    pub fn with_border_color(self, border_color: Vector3) -> Self {
+        Self { border_color, ..self }
+    }
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self {
+            filter: Filter::Nearest,
+            mipmap: Mipmap::None,
+            max_anisotropy: 1,
+            wrap_u: Wrap::ClampToEdge,
+            wrap_v: Wrap::ClampToEdge,
+            wrap_w: Wrap::ClampToEdge,
+            border_color: Vector3::zero(),
+        }
+    }
+}
+
+/// Texture holding its texels in memory, e.g. a snapshot of a framebuffer plane.
+///
+/// Cloning is cheap, texels are shared, so the texture can be stored in uniforms.
+/// Texel centers lie at `(i + 0.5) / size`.
+#[derive(Clone, Debug)]
+pub struct ImageTexture {
+    levels: Arc<Vec<MipLevel>>,
+    pub sampler: Sampler,
+}
+
+impl ImageTexture {
+    /// Texture stored as `TexelFormat::Rgb32F`.
+    pub fn new(width: usize, height: usize, texels: Vec<Vector3>) -> Self {
+        Self::from_buffer(width, height, TexelBuffer::from_colors(TexelFormat::Rgb32F, texels))
+    }
+
+    /// Texture of texels in any color format, e.g. `TexelFormat::Rgba8Srgb` for images
+    /// authored in sRGB, which are then filtered in linear space.
+    pub fn from_buffer(width: usize, height: usize, texels: TexelBuffer) -> Self {
+        assert!(width > 0 && height > 0, "textures need at least one texel");
+        assert_eq!(texels.len(), width * height, "texel count does not match texture dimensions");
+        Self {
+            levels: Arc::new(vec![MipLevel { width, height, texels }]),
+            sampler: Sampler::default(),
+        }
+    }
+
+    pub fn with_sampler(self, sampler: Sampler) -> Self {
+        Self { sampler, ..self }
+    }
+
+    /// Replaces the mip chain with one built from the base level, halving (rounding down)
+    /// both dimensions until the level is 1x1.
+    pub fn generate_mipmaps(self, filter: MipmapFilter) -> Self {
+        let mut levels = Vec::new();
+        let base = &self.levels[0];
+        levels.push(MipLevel {
+            width: base.width,
+            height: base.height,
+            texels: base.texels.clone(),
+        });
+        loop {
+            let last = &levels[levels.len() - 1];
+            if last.width == 1 && last.height == 1 {
+                break;
+            }
+            let next = last.downsample(filter);
+            levels.push(next);
+        }
+        Self {
+            levels: Arc::new(levels),
+            ..self
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.levels[0].width
+    }
+
+    pub fn height(&self) -> usize {
+        self.levels[0].height
+    }
+
+    pub fn format(&self) -> TexelFormat {
+        self.levels[0].texels.format()
+    }
+
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    pub fn level_size(&self, level: usize) -> (usize, usize) {
+        (self.levels[level].width, self.levels[level].height)
+    }
+
+    pub fn texel(&self, x: usize, y: usize) -> Vector3 {
+        self.texel_level(0, x, y)
+    }
+
+    pub fn texel_level(&self, level: usize, x: usize, y: usize) -> Vector3 {
+        let level = &self.levels[level];
+        level.texels.color(y * level.width + x)
+    }
+
+    /// All channels of a texel of the base level, alpha is one for formats without it.
+    pub fn texel_value(&self, x: usize, y: usize) -> Vector4 {
+        let level = &self.levels[0];
+        level.texels.get(y * level.width + x)
+    }
+
+    /// Texel at possibly out of range coordinates, resolved with the wrap modes of the sampler.
+    pub fn fetch(&self, x: i64, y: i64) -> Vector3 {
+        self.fetch_level(0, x, y)
+    }
+
+    pub fn fetch_level(&self, level: usize, x: i64, y: i64) -> Vector3 {
+        self.fetch_with(&self.sampler, level, x, y)
+    }
+
+    /// Like `fetch_level` with an explicit sampler, so that texture arrays can share theirs
+    /// between all layers.
+    fn fetch_with(&self, sampler: &Sampler, level: usize, x: i64, y: i64) -> Vector3 {
+        let (width, height) = self.level_size(level);
+        match (sampler.wrap_u.apply(x, width), sampler.wrap_v.apply(y, height)) {
+            (Some(x), Some(y)) => self.texel_level(level, x, y),
+            _ => sampler.border_color,
+        }
+    }
+
+    fn sample_level(&self, sampler: &Sampler, level: usize, uv: Vector2) -> Vector3 {
+        let (width, height) = self.level_size(level);
+        let x = uv.x * width as Scalar;
+        let y = uv.y * height as Scalar;
+        match sampler.filter {
+            Filter::Nearest => self.fetch_with(sampler, level, x.floor() as i64, y.floor() as i64),
+            Filter::Bilinear => {
+                let x = x - 0.5;
+                let y = y - 0.5;
+                let x0 = x.floor();
+                let y0 = y.floor();
+                let fx = x - x0;
+                let fy = y - y0;
+                let (x0, y0) = (x0 as i64, y0 as i64);
+
+                let fetch = |x, y| self.fetch_with(sampler, level, x, y);
+                let top = fetch(x0, y0) * (1.0 - fx) + fetch(x0 + 1, y0) * fx;
+                let bottom = fetch(x0, y0 + 1) * (1.0 - fx) + fetch(x0 + 1, y0 + 1) * fx;
+                top * (1.0 - fy) + bottom * fy
+            }
+        }
+    }
+
+    fn sample_lod_with(&self, sampler: &Sampler, uv: Vector2, lod: Scalar) -> Vector3 {
+        let max_level = (self.levels.len() - 1) as Scalar;
+        let lod = clamp(lod, 0.0, max_level);
+        match sampler.mipmap {
+            Mipmap::None => self.sample_level(sampler, 0, uv),
+            Mipmap::Nearest => self.sample_level(sampler, lod.round() as usize, uv),
+            Mipmap::Linear => {
+                let level = lod.floor();
+                let t = lod - level;
+                let near = self.sample_level(sampler, level as usize, uv);
+                if t == 0.0 {
+                    near
+                } else {
+                    near * (1.0 - t) + self.sample_level(sampler, level as usize + 1, uv) * t
+                }
+            }
+        }
+    }
+
+    fn sample_grad_with(&self, sampler: &Sampler, uv: Vector2, ddx: Vector2, ddy: Vector2) -> Vector3 {
+        // footprint of the pixel in texels of the base level
+        let size = Vector2::new(self.width() as Scalar, self.height() as Scalar);
+        let length_x = ddx.component_mul(size).length();
+        let length_y = ddy.component_mul(size).length();
+        let (major, length_major, length_minor) = if length_x >= length_y {
+            (ddx, length_x, length_y)
+        } else {
+            (ddy, length_y, length_x)
+        };
+
+        if sampler.max_anisotropy <= 1 || length_major <= length_minor {
+            return self.sample_lod_with(sampler, uv, length_major.max(1e-12).log2());
+        }
+
+        let samples = ((length_major / length_minor.max(1e-12)).ceil() as usize).min(sampler.max_anisotropy);
+        let lod = (length_major / samples as Scalar).max(1e-12).log2();
+        let mut sum = Vector3::zero();
+        for i in 0..samples {
+            let offset = (i as Scalar + 0.5) / samples as Scalar - 0.5;
+            sum += self.sample_lod_with(sampler, uv + offset * major, lod);
+        }
+        sum * (1.0 / samples as Scalar)
+    }
+}
+
+impl Texture for ImageTexture {
+    fn sample(&self, uv: Vector2) -> Vector3 {
+        self.sample_lod(uv, 0.0)
+    }
+
+    fn sample_lod(&self, uv: Vector2, lod: Scalar) -> Vector3 {
+        self.sample_lod_with(&self.sampler, uv, lod)
+    }
+
+    fn sample_grad(&self, uv: Vector2, ddx: Vector2, ddy: Vector2) -> Vector3 {
+        self.sample_grad_with(&self.sampler, uv, ddx, ddy)
+    }
+}
+
+/// Layers of equal size sampled by a layer index and a `Vector2`, e.g. terrain materials.
+/// All layers are filtered with the sampler of the array, the layer itself is never filtered.
+#[derive(Clone, Debug)]
+pub struct TextureArray {
+    layers: Vec<ImageTexture>,
+    pub sampler: Sampler,
+}
+
+impl TextureArray {
+    /// Layers have to be of equal size, their own samplers are ignored.
+    pub fn from_layers(layers: Vec<ImageTexture>) -> Self {
+        assert!(!layers.is_empty(), "texture array needs at least one layer");
+        let (width, height) = (layers[0].width(), layers[0].height());
+        for layer in layers.iter() {
+            assert!(layer.width() == width && layer.height() == height, "texture array layers have to be of equal size");
+        }
+        Self {
+            layers,
+            sampler: Sampler::default(),
+        }
+    }
+
+    pub fn with_sampler(self, sampler: Sampler) -> Self {
+        Self { sampler, ..self }
+    }
+
+    pub fn generate_mipmaps(self, filter: MipmapFilter) -> Self {
+        Self {
+            layers: self.layers.into_iter().map(|layer| layer.generate_mipmaps(filter)).collect(),
+            ..self
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.layers[0].width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.layers[0].height()
+    }
+
+    pub fn layer_count(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn layer(&self, layer: usize) -> &ImageTexture {
+        &self.layers[layer]
+    }
+
+    /// Index of the layer nearest to `layer`, clamped to the existing ones.
+    fn layer_index(&self, layer: Scalar) -> usize {
+        clamp(layer.round(), 0.0, (self.layers.len() - 1) as Scalar) as usize
+    }
+
+    pub fn sample(&self, layer: Scalar, uv: Vector2) -> Vector3 {
+        self.sample_lod(layer, uv, 0.0)
+    }
+
+    pub fn sample_lod(&self, layer: Scalar, uv: Vector2, lod: Scalar) -> Vector3 {
+        self.layers[self.layer_index(layer)].sample_lod_with(&self.sampler, uv, lod)
+    }
+
+    pub fn sample_grad(&self, layer: Scalar, uv: Vector2, ddx: Vector2, ddy: Vector2) -> Vector3 {
+        self.layers[self.layer_index(layer)].sample_grad_with(&self.sampler, uv, ddx, ddy)
+    }
+}
+
+/// Volume of texels sampled by a `Vector3`, e.g. medical scans or fog densities.
+///
+/// `Filter::Bilinear` filters trilinearly, `wrap_w` of the sampler addresses the depth.
+/// There are no mipmaps, the mipmap and anisotropy settings are ignored.
+#[derive(Clone, Debug)]
+pub struct Texture3D {
+    width: usize,
+    height: usize,
+    depth: usize,
+    texels: Arc<TexelBuffer>,
+    pub sampler: Sampler,
+}
+
+impl Texture3D {
+    /// Texels are given in slices of rows, `texels[(z * height + y) * width + x]`,
+    /// and stored as `TexelFormat::Rgb32F`.
+    pub fn new(width: usize, height: usize, depth: usize, texels: Vec<Vector3>) -> Self {
+        Self::from_buffer(width, height, depth, TexelBuffer::from_colors(TexelFormat::Rgb32F, texels))
+    }
+
+    /// Volume of texels in any color format, e.g. `TexelFormat::R8` for scalar densities.
+    pub fn from_buffer(width: usize, height: usize, depth: usize, texels: TexelBuffer) -> Self {
+        assert!(width > 0 && height > 0 && depth > 0, "textures need at least one texel");
+        assert_eq!(texels.len(), width * height * depth, "texel count does not match texture dimensions");
+        Self {
+            width,
+            height,
+            depth,
+            texels: Arc::new(texels),
+            sampler: Sampler::default(),
+        }
+    }
+
+    pub fn with_sampler(self, sampler: Sampler) -> Self {
+        Self { sampler, ..self }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn texel(&self, x: usize, y: usize, z: usize) -> Vector3 {
+        self.texels.color((z * self.height + y) * self.width + x)
+    }
+
+    /// Texel at possibly out of range coordinates, resolved with the wrap modes of the sampler.
+    pub fn fetch(&self, x: i64, y: i64, z: i64) -> Vector3 {
+        let sampler = &self.sampler;
+        match (sampler.wrap_u.apply(x, self.width), sampler.wrap_v.apply(y, self.height), sampler.wrap_w.apply(z, self.depth)) {
+            (Some(x), Some(y), Some(z)) => self.texel(x, y, z),
+            _ => sampler.border_color,
+        }
+    }
+
+    pub fn sample(&self, uvw: Vector3) -> Vector3 {
+        let x = uvw.x * self.width as Scalar;
+        let y = uvw.y * self.height as Scalar;
+        let z = uvw.z * self.depth as Scalar;
+        match self.sampler.filter {
+            Filter::Nearest => self.fetch(x.floor() as i64, y.floor() as i64, z.floor() as i64),
+            Filter::Bilinear => {
+                let (x, y, z) = (x - 0.5, y - 0.5, z - 0.5);
+                let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+                let (fx, fy, fz) = (x - x0, y - y0, z - z0);
+                let (x0, y0, z0) = (x0 as i64, y0 as i64, z0 as i64);
+
+                let slice = |z| {
+                    let top = self.fetch(x0, y0, z) * (1.0 - fx) + self.fetch(x0 + 1, y0, z) * fx;
+                    let bottom = self.fetch(x0, y0 + 1, z) * (1.0 - fx) + self.fetch(x0 + 1, y0 + 1, z) * fx;
+                    top * (1.0 - fy) + bottom * fy
+                };
+                slice(z0) * (1.0 - fz) + slice(z0 + 1) * fz
+            }
+        }
+    }
+}
+
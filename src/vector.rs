@@ -1,22 +1,111 @@
-use std::ops::{self, Add, Mul, Sub};
+use std::{fmt, ops};
 
-#[derive(Copy, Clone, Debug)]
+use crate::approx::{ApproxEq, Tolerance};
+use crate::matrix::Matrix4;
+use crate::scalar::Scalar;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Vector4 {
-    pub x: f64,
-    pub y: f64,
-    pub z: f64,
-    pub w: f64,
+    pub x: Scalar,
+    pub y: Scalar,
+    pub z: Scalar,
+    pub w: Scalar,
 }
 
 impl Vector4 {
-    pub const fn new(x: f64, y: f64, z: f64, w: f64) -> Self {
+    pub const fn new(x: Scalar, y: Scalar, z: Scalar, w: Scalar) -> Self {
         Self { x, y, z, w }
     }
 
     pub const fn zero() -> Self {
         Self::new(0.0, 0.0, 0.0, 0.0)
     }
+
+    /// Vector with all components set to `value`.
+    pub const fn splat(value: Scalar) -> Self {
+        Self::new(value, value, value, value)
+    }
+
+    /// Same as `self * rhs`.
+    pub fn dot(self, rhs: Self) -> Scalar {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z + self.w * rhs.w
+    }
+
+    pub fn length_squared(self) -> Scalar {
+        self.dot(self)
+    }
+
+    pub fn length(self) -> Scalar {
+        self.dot(self).sqrt()
+    }
+
+    pub fn distance(self, rhs: Self) -> Scalar {
+        (self - rhs).length()
+    }
+
+    /// Same direction with length 1, components are NaN for the zero vector.
+    pub fn normalize(self) -> Self {
+        self * (1.0 / self.length())
+    }
+
+    pub fn component_mul(self, rhs: Self) -> Self {
+        Self::new(self.x * rhs.x, self.y * rhs.y, self.z * rhs.z, self.w * rhs.w)
+    }
+
+    pub fn component_div(self, rhs: Self) -> Self {
+        Self::new(self.x / rhs.x, self.y / rhs.y, self.z / rhs.z, self.w / rhs.w)
+    }
+
+    pub fn min(self, rhs: Self) -> Self {
+        Self::new(self.x.min(rhs.x), self.y.min(rhs.y), self.z.min(rhs.z), self.w.min(rhs.w))
+    }
+
+    pub fn max(self, rhs: Self) -> Self {
+        Self::new(self.x.max(rhs.x), self.y.max(rhs.y), self.z.max(rhs.z), self.w.max(rhs.w))
+    }
+
+    pub fn abs(self) -> Self {
+        Self::new(self.x.abs(), self.y.abs(), self.z.abs(), self.w.abs())
+    }
+
+    /// Clamps every component between the ones of `min` and `max`.
+    pub fn clamp(self, min: Self, max: Self) -> Self {
+        self.max(min).min(max)
+    }
+
+    /// `self` for `t = 0` and `other` for `t = 1`.
+    pub fn lerp(self, other: Self, t: Scalar) -> Self {
+        self + (other - self) * t
+    }
+
+    /// Drops `w`.
+    pub fn truncate(self) -> Vector3 {
+        Vector3::new(self.x, self.y, self.z)
+    }
+
+    // swizzles for the common cases, others are built with `new`
+
+    pub fn xy(self) -> Vector2 {
+        Vector2::new(self.x, self.y)
+    }
+
+    pub fn zw(self) -> Vector2 {
+        Vector2::new(self.z, self.w)
+    }
+
+    pub fn xyz(self) -> Vector3 {
+        Vector3::new(self.x, self.y, self.z)
+    }
+
+    pub fn zyx(self) -> Vector3 {
+        Vector3::new(self.z, self.y, self.x)
+    }
+
+    pub fn wzyx(self) -> Vector4 {
+        Vector4::new(self.w, self.z, self.y, self.x)
+    }
 }
 
 impl_op_ex!(+ |lhs: &Vector4, rhs: &Vector4| -> Vector4 {
@@ -24,7 +113,7 @@ impl_op_ex!(+ |lhs: &Vector4, rhs: &Vector4| -> Vector4 {
         x: lhs.x + rhs.x,
         y: lhs.y + rhs.y,
         z: lhs.z + rhs.z,
-        w : lhs.w + rhs.w
+        w: lhs.w + rhs.w
     }
 });
 
@@ -33,15 +122,15 @@ impl_op_ex!(- |lhs: &Vector4, rhs: &Vector4| -> Vector4 {
         x: lhs.x - rhs.x,
         y: lhs.y - rhs.y,
         z: lhs.z - rhs.z,
-        w : lhs.w - rhs.w
+        w: lhs.w - rhs.w
     }
 });
 
-impl_op_ex!(* |lhs: &Vector4, rhs: &Vector4| -> f64 {
+impl_op_ex!(* |lhs: &Vector4, rhs: &Vector4| -> Scalar {
     lhs.x * rhs.x + lhs.y * rhs.y + lhs.z * rhs.z + lhs.w * rhs.w
 });
 
-impl_op_ex!(* |lhs: &Vector4, rhs: f64| -> Vector4 {
+impl_op_ex!(* |lhs: &Vector4, rhs: Scalar| -> Vector4 {
     Vector4 {
         x: lhs.x * rhs,
         y: lhs.y * rhs,
@@ -50,22 +139,251 @@ impl_op_ex!(* |lhs: &Vector4, rhs: f64| -> Vector4 {
     }
 });
 
-#[derive(Copy, Clone, Debug)]
+impl_op_ex!(* |lhs: Scalar, rhs: &Vector4| -> Vector4 {
+    Vector4 {
+        x: lhs * rhs.x,
+        y: lhs * rhs.y,
+        z: lhs * rhs.z,
+        w: lhs * rhs.w
+    }
+});
+
+impl_op_ex!(/ |lhs: &Vector4, rhs: Scalar| -> Vector4 {
+    Vector4 {
+        x: lhs.x / rhs,
+        y: lhs.y / rhs,
+        z: lhs.z / rhs,
+        w: lhs.w / rhs
+    }
+});
+
+impl_op_ex!(- |v: &Vector4| -> Vector4 {
+    Vector4 {
+        x: -v.x,
+        y: -v.y,
+        z: -v.z,
+        w: -v.w
+    }
+});
+
+impl_op_ex!(+= |lhs: &mut Vector4, rhs: &Vector4| {
+    lhs.x += rhs.x;
+    lhs.y += rhs.y;
+    lhs.z += rhs.z;
+    lhs.w += rhs.w;
+});
+
+impl_op_ex!(-= |lhs: &mut Vector4, rhs: &Vector4| {
+    lhs.x -= rhs.x;
+    lhs.y -= rhs.y;
+    lhs.z -= rhs.z;
+    lhs.w -= rhs.w;
+});
+
+impl_op_ex!(*= |lhs: &mut Vector4, rhs: Scalar| {
+    lhs.x *= rhs;
+    lhs.y *= rhs;
+    lhs.z *= rhs;
+    lhs.w *= rhs;
+});
+
+impl_op_ex!(/= |lhs: &mut Vector4, rhs: Scalar| {
+    lhs.x /= rhs;
+    lhs.y /= rhs;
+    lhs.z /= rhs;
+    lhs.w /= rhs;
+});
+
+impl From<[Scalar; 4]> for Vector4 {
+    fn from([x, y, z, w]: [Scalar; 4]) -> Self {
+        Self::new(x, y, z, w)
+    }
+}
+
+impl From<Vector4> for [Scalar; 4] {
+    fn from(v: Vector4) -> Self {
+        [v.x, v.y, v.z, v.w]
+    }
+}
+
+impl From<(Scalar, Scalar, Scalar, Scalar)> for Vector4 {
+    fn from((x, y, z, w): (Scalar, Scalar, Scalar, Scalar)) -> Self {
+        Self::new(x, y, z, w)
+    }
+}
+
+impl From<Vector4> for (Scalar, Scalar, Scalar, Scalar) {
+    fn from(v: Vector4) -> Self {
+        (v.x, v.y, v.z, v.w)
+    }
+}
+
+impl ApproxEq for Vector4 {
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance) -> bool {
+        <[Scalar; 4]>::from(*self).approx_eq(&(*other).into(), tolerance)
+    }
+}
+
+impl fmt::Display for Vector4 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_components(f, &[self.x, self.y, self.z, self.w])
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Vector3 {
-    pub x: f64,
-    pub y: f64,
-    pub z: f64,
+    pub x: Scalar,
+    pub y: Scalar,
+    pub z: Scalar,
 }
 
 impl Vector3 {
-    pub const fn new(x: f64, y: f64, z: f64) -> Self {
+    pub const fn new(x: Scalar, y: Scalar, z: Scalar) -> Self {
         Self { x, y, z }
     }
 
     pub const fn zero() -> Self {
         Self::new(0.0, 0.0, 0.0)
     }
+
+    /// Vector with all components set to `value`.
+    pub const fn splat(value: Scalar) -> Self {
+        Self::new(value, value, value)
+    }
+
+    /// Same as `self * rhs`.
+    pub fn dot(self, rhs: Self) -> Scalar {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+    }
+
+    pub fn cross(self, rhs: Self) -> Self {
+        Self::new(
+            self.y * rhs.z - self.z * rhs.y,
+            self.z * rhs.x - self.x * rhs.z,
+            self.x * rhs.y - self.y * rhs.x,
+        )
+    }
+
+    pub fn length_squared(self) -> Scalar {
+        self.dot(self)
+    }
+
+    pub fn length(self) -> Scalar {
+        self.dot(self).sqrt()
+    }
+
+    pub fn distance(self, rhs: Self) -> Scalar {
+        (self - rhs).length()
+    }
+
+    /// Same direction with length 1, components are NaN for the zero vector.
+    pub fn normalize(self) -> Self {
+        self * (1.0 / self.length())
+    }
+
+    pub fn component_mul(self, rhs: Self) -> Self {
+        Self::new(self.x * rhs.x, self.y * rhs.y, self.z * rhs.z)
+    }
+
+    pub fn component_div(self, rhs: Self) -> Self {
+        Self::new(self.x / rhs.x, self.y / rhs.y, self.z / rhs.z)
+    }
+
+    pub fn min(self, rhs: Self) -> Self {
+        Self::new(self.x.min(rhs.x), self.y.min(rhs.y), self.z.min(rhs.z))
+    }
+
+    pub fn max(self, rhs: Self) -> Self {
+        Self::new(self.x.max(rhs.x), self.y.max(rhs.y), self.z.max(rhs.z))
+    }
+
+    pub fn abs(self) -> Self {
+        Self::new(self.x.abs(), self.y.abs(), self.z.abs())
+    }
+
+    /// Clamps every component between the ones of `min` and `max`.
+    pub fn clamp(self, min: Self, max: Self) -> Self {
+        self.max(min).min(max)
+    }
+
+    /// `self` for `t = 0` and `other` for `t = 1`.
+    pub fn lerp(self, other: Self, t: Scalar) -> Self {
+        self + (other - self) * t
+    }
+
+    /// Mirrors the direction at the surface with the unit `normal`.
+    pub fn reflect(self, normal: Self) -> Self {
+        self - normal * (2.0 * self.dot(normal))
+    }
+
+    /// Bends the unit direction entering the surface with the unit `normal`, `eta` is
+    /// the ratio of the refractive indices outside and inside. `None` on total internal
+    /// reflection.
+    pub fn refract(self, normal: Self, eta: Scalar) -> Option<Self> {
+        let cos = self.dot(normal);
+        let k = 1.0 - eta * eta * (1.0 - cos * cos);
+        if k < 0.0 {
+            return None;
+        }
+        Some(self * eta - normal * (eta * cos + k.sqrt()))
+    }
+
+    pub fn extend(self, w: Scalar) -> Vector4 {
+        Vector4::new(self.x, self.y, self.z, w)
+    }
+
+    /// Drops `z`.
+    pub fn truncate(self) -> Vector2 {
+        Vector2::new(self.x, self.y)
+    }
+
+    // swizzles for the common cases, others are built with `new`
+
+    pub fn xy(self) -> Vector2 {
+        Vector2::new(self.x, self.y)
+    }
+
+    pub fn xz(self) -> Vector2 {
+        Vector2::new(self.x, self.z)
+    }
+
+    pub fn yx(self) -> Vector2 {
+        Vector2::new(self.y, self.x)
+    }
+
+    pub fn yz(self) -> Vector2 {
+        Vector2::new(self.y, self.z)
+    }
+
+    pub fn zx(self) -> Vector2 {
+        Vector2::new(self.z, self.x)
+    }
+
+    pub fn zy(self) -> Vector2 {
+        Vector2::new(self.z, self.y)
+    }
+
+    pub fn xzy(self) -> Vector3 {
+        Vector3::new(self.x, self.z, self.y)
+    }
+
+    pub fn yxz(self) -> Vector3 {
+        Vector3::new(self.y, self.x, self.z)
+    }
+
+    pub fn yzx(self) -> Vector3 {
+        Vector3::new(self.y, self.z, self.x)
+    }
+
+    pub fn zxy(self) -> Vector3 {
+        Vector3::new(self.z, self.x, self.y)
+    }
+
+    pub fn zyx(self) -> Vector3 {
+        Vector3::new(self.z, self.y, self.x)
+    }
 }
 
 impl_op_ex!(+ |lhs: &Vector3, rhs: &Vector3| -> Vector3 {
@@ -84,11 +402,11 @@ impl_op_ex!(- |lhs: &Vector3, rhs: &Vector3| -> Vector3 {
     }
 });
 
-impl_op_ex!(* |lhs: &Vector3, rhs: &Vector3| -> f64 {
+impl_op_ex!(* |lhs: &Vector3, rhs: &Vector3| -> Scalar {
     lhs.x * rhs.x + lhs.y * rhs.y + lhs.z * rhs.z
 });
 
-impl_op_ex!(* |lhs: &Vector3, rhs: f64| -> Vector3 {
+impl_op_ex!(* |lhs: &Vector3, rhs: Scalar| -> Vector3 {
     Vector3 {
         x: lhs.x * rhs,
         y: lhs.y * rhs,
@@ -96,60 +414,510 @@ impl_op_ex!(* |lhs: &Vector3, rhs: f64| -> Vector3 {
     }
 });
 
-#[derive(Copy, Clone, Debug)]
+impl_op_ex!(* |lhs: Scalar, rhs: &Vector3| -> Vector3 {
+    Vector3 {
+        x: lhs * rhs.x,
+        y: lhs * rhs.y,
+        z: lhs * rhs.z
+    }
+});
+
+impl_op_ex!(/ |lhs: &Vector3, rhs: Scalar| -> Vector3 {
+    Vector3 {
+        x: lhs.x / rhs,
+        y: lhs.y / rhs,
+        z: lhs.z / rhs
+    }
+});
+
+impl_op_ex!(- |v: &Vector3| -> Vector3 {
+    Vector3 {
+        x: -v.x,
+        y: -v.y,
+        z: -v.z
+    }
+});
+
+impl_op_ex!(+= |lhs: &mut Vector3, rhs: &Vector3| {
+    lhs.x += rhs.x;
+    lhs.y += rhs.y;
+    lhs.z += rhs.z;
+});
+
+impl_op_ex!(-= |lhs: &mut Vector3, rhs: &Vector3| {
+    lhs.x -= rhs.x;
+    lhs.y -= rhs.y;
+    lhs.z -= rhs.z;
+});
+
+impl_op_ex!(*= |lhs: &mut Vector3, rhs: Scalar| {
+    lhs.x *= rhs;
+    lhs.y *= rhs;
+    lhs.z *= rhs;
+});
+
+impl_op_ex!(/= |lhs: &mut Vector3, rhs: Scalar| {
+    lhs.x /= rhs;
+    lhs.y /= rhs;
+    lhs.z /= rhs;
+});
+
+impl From<[Scalar; 3]> for Vector3 {
+    fn from([x, y, z]: [Scalar; 3]) -> Self {
+        Self::new(x, y, z)
+    }
+}
+
+impl From<Vector3> for [Scalar; 3] {
+    fn from(v: Vector3) -> Self {
+        [v.x, v.y, v.z]
+    }
+}
+
+impl From<(Scalar, Scalar, Scalar)> for Vector3 {
+    fn from((x, y, z): (Scalar, Scalar, Scalar)) -> Self {
+        Self::new(x, y, z)
+    }
+}
+
+impl From<Vector3> for (Scalar, Scalar, Scalar) {
+    fn from(v: Vector3) -> Self {
+        (v.x, v.y, v.z)
+    }
+}
+
+impl ApproxEq for Vector3 {
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance) -> bool {
+        <[Scalar; 3]>::from(*self).approx_eq(&(*other).into(), tolerance)
+    }
+}
+
+impl fmt::Display for Vector3 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_components(f, &[self.x, self.y, self.z])
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Vector2 {
-    pub x: f64,
-    pub y: f64,
+    pub x: Scalar,
+    pub y: Scalar,
 }
 
 impl Vector2 {
-    pub const fn new(x: f64, y: f64) -> Self {
+    pub const fn new(x: Scalar, y: Scalar) -> Self {
         Self { x, y }
     }
 
     pub const fn zero() -> Self {
         Self::new(0.0, 0.0)
     }
-}
 
-impl Add for Vector2 {
-    type Output = Self;
+    /// Vector with all components set to `value`.
+    pub const fn splat(value: Scalar) -> Self {
+        Self::new(value, value)
+    }
+
+    /// Same as `self * rhs`.
+    pub fn dot(self, rhs: Self) -> Scalar {
+        self.x * rhs.x + self.y * rhs.y
+    }
+
+    pub fn length_squared(self) -> Scalar {
+        self.dot(self)
+    }
+
+    pub fn length(self) -> Scalar {
+        self.dot(self).sqrt()
+    }
+
+    pub fn distance(self, rhs: Self) -> Scalar {
+        (self - rhs).length()
+    }
+
+    /// Same direction with length 1, components are NaN for the zero vector.
+    pub fn normalize(self) -> Self {
+        self * (1.0 / self.length())
+    }
+
+    pub fn component_mul(self, rhs: Self) -> Self {
+        Self::new(self.x * rhs.x, self.y * rhs.y)
+    }
+
+    pub fn component_div(self, rhs: Self) -> Self {
+        Self::new(self.x / rhs.x, self.y / rhs.y)
+    }
+
+    pub fn min(self, rhs: Self) -> Self {
+        Self::new(self.x.min(rhs.x), self.y.min(rhs.y))
+    }
+
+    pub fn max(self, rhs: Self) -> Self {
+        Self::new(self.x.max(rhs.x), self.y.max(rhs.y))
+    }
+
+    pub fn abs(self) -> Self {
+        Self::new(self.x.abs(), self.y.abs())
+    }
+
+    /// Clamps every component between the ones of `min` and `max`.
+    pub fn clamp(self, min: Self, max: Self) -> Self {
+        self.max(min).min(max)
+    }
+
+    /// `self` for `t = 0` and `other` for `t = 1`.
+    pub fn lerp(self, other: Self, t: Scalar) -> Self {
+        self + (other - self) * t
+    }
+
+    /// Mirrors the direction at the surface with the unit `normal`.
+    pub fn reflect(self, normal: Self) -> Self {
+        self - normal * (2.0 * self.dot(normal))
+    }
 
-    fn add(self, rhs: Self) -> Self::Output {
-        Self {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
+    /// Bends the unit direction entering the surface with the unit `normal`, `eta` is
+    /// the ratio of the refractive indices outside and inside. `None` on total internal
+    /// reflection.
+    pub fn refract(self, normal: Self, eta: Scalar) -> Option<Self> {
+        let cos = self.dot(normal);
+        let k = 1.0 - eta * eta * (1.0 - cos * cos);
+        if k < 0.0 {
+            return None;
         }
+        Some(self * eta - normal * (eta * cos + k.sqrt()))
+    }
+
+    pub fn extend(self, z: Scalar) -> Vector3 {
+        Vector3::new(self.x, self.y, z)
+    }
+
+    // swizzles for the common cases, others are built with `new`
+
+    pub fn yx(self) -> Vector2 {
+        Vector2::new(self.y, self.x)
     }
 }
 
-impl Sub for Vector2 {
-    type Output = Self;
+impl_op_ex!(+ |lhs: &Vector2, rhs: &Vector2| -> Vector2 {
+    Vector2 {
+        x: lhs.x + rhs.x,
+        y: lhs.y + rhs.y
+    }
+});
 
-    fn sub(self, rhs: Self) -> Self::Output {
-        Self {
-            x: self.x - rhs.x,
-            y: self.y - rhs.y,
+impl_op_ex!(- |lhs: &Vector2, rhs: &Vector2| -> Vector2 {
+    Vector2 {
+        x: lhs.x - rhs.x,
+        y: lhs.y - rhs.y
+    }
+});
+
+impl_op_ex!(* |lhs: &Vector2, rhs: &Vector2| -> Scalar {
+    lhs.x * rhs.x + lhs.y * rhs.y
+});
+
+impl_op_ex!(* |lhs: &Vector2, rhs: Scalar| -> Vector2 {
+    Vector2 {
+        x: lhs.x * rhs,
+        y: lhs.y * rhs
+    }
+});
+
+impl_op_ex!(* |lhs: Scalar, rhs: &Vector2| -> Vector2 {
+    Vector2 {
+        x: lhs * rhs.x,
+        y: lhs * rhs.y
+    }
+});
+
+impl_op_ex!(/ |lhs: &Vector2, rhs: Scalar| -> Vector2 {
+    Vector2 {
+        x: lhs.x / rhs,
+        y: lhs.y / rhs
+    }
+});
+
+impl_op_ex!(- |v: &Vector2| -> Vector2 {
+    Vector2 {
+        x: -v.x,
+        y: -v.y
+    }
+});
+
+impl_op_ex!(+= |lhs: &mut Vector2, rhs: &Vector2| {
+    lhs.x += rhs.x;
+    lhs.y += rhs.y;
+});
+
+impl_op_ex!(-= |lhs: &mut Vector2, rhs: &Vector2| {
+    lhs.x -= rhs.x;
+    lhs.y -= rhs.y;
+});
+
+impl_op_ex!(*= |lhs: &mut Vector2, rhs: Scalar| {
+    lhs.x *= rhs;
+    lhs.y *= rhs;
+});
+
+impl_op_ex!(/= |lhs: &mut Vector2, rhs: Scalar| {
+    lhs.x /= rhs;
+    lhs.y /= rhs;
+});
+
+impl From<[Scalar; 2]> for Vector2 {
+    fn from([x, y]: [Scalar; 2]) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl From<Vector2> for [Scalar; 2] {
+    fn from(v: Vector2) -> Self {
+        [v.x, v.y]
+    }
+}
+
+impl From<(Scalar, Scalar)> for Vector2 {
+    fn from((x, y): (Scalar, Scalar)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl From<Vector2> for (Scalar, Scalar) {
+    fn from(v: Vector2) -> Self {
+        (v.x, v.y)
+    }
+}
+
+impl ApproxEq for Vector2 {
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance) -> bool {
+        <[Scalar; 2]>::from(*self).approx_eq(&(*other).into(), tolerance)
+    }
+}
+
+impl fmt::Display for Vector2 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_components(f, &[self.x, self.y])
+    }
+}
+
+/// Quaternion `w + xi + yj + zk`, rotations are unit quaternions.
+///
+/// Products compose like matrices, `a * b` rotates by `b` first. `q` and `-q` are the
+/// same rotation, interpolations take the shorter of the two paths.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quaternion {
+    pub x: Scalar,
+    pub y: Scalar,
+    pub z: Scalar,
+    pub w: Scalar,
+}
+
+impl Quaternion {
+    pub const fn new(x: Scalar, y: Scalar, z: Scalar, w: Scalar) -> Self {
+        Self { x, y, z, w }
+    }
+
+    pub const fn identity() -> Self {
+        Self::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    /// Rotation by `angle` radians around `axis`, which does not have to be normalized.
+    pub fn from_axis_angle(axis: Vector3, angle: Scalar) -> Self {
+        let (sin, cos) = (angle * 0.5).sin_cos();
+        let axis = axis.normalize() * sin;
+        Self::new(axis.x, axis.y, axis.z, cos)
+    }
+
+    /// Axis and angle in `[0, 2 pi]` of the rotation, the axis is +x for the identity.
+    pub fn to_axis_angle(self) -> (Vector3, Scalar) {
+        let q = self.normalize();
+        let sin = (q.x * q.x + q.y * q.y + q.z * q.z).sqrt();
+        if sin == 0.0 {
+            return (Vector3::new(1.0, 0.0, 0.0), 0.0);
+        }
+        (Vector3::new(q.x, q.y, q.z) * (1.0 / sin), 2.0 * sin.atan2(q.w))
+    }
+
+    /// Rotation by the Euler angles `x`, `y` and `z` in radians, roll around z first,
+    /// then pitch around x and yaw around y last. Same as
+    /// `Matrix4::rotation_y(y) * Matrix4::rotation_x(x) * Matrix4::rotation_z(z)`.
+    pub fn from_euler(x: Scalar, y: Scalar, z: Scalar) -> Self {
+        Self::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), y)
+            * Self::from_axis_angle(Vector3::new(1.0, 0.0, 0.0), x)
+            * Self::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), z)
+    }
+
+    /// Euler angles of `from_euler`, pitch is in `[-pi/2, pi/2]`. When it is at either
+    /// end, yaw and roll turn around the same axis and roll is reported as 0.
+    pub fn to_euler(self) -> Vector3 {
+        let m = self.to_matrix4();
+        let x = (-m.m12).clamp(-1.0, 1.0).asin();
+        if m.m12.abs() < 1.0 - 1e-12 {
+            Vector3::new(x, m.m02.atan2(m.m22), m.m10.atan2(m.m11))
+        } else {
+            Vector3::new(x, (-m.m20).atan2(m.m00), 0.0)
         }
     }
+
+    pub fn to_matrix4(self) -> Matrix4 {
+        let Self { x, y, z, w } = self;
+        Matrix4 {
+            m00: 1.0 - 2.0 * (y * y + z * z), m01: 2.0 * (x * y - w * z), m02: 2.0 * (x * z + w * y), m03: 0.0,
+            m10: 2.0 * (x * y + w * z), m11: 1.0 - 2.0 * (x * x + z * z), m12: 2.0 * (y * z - w * x), m13: 0.0,
+            m20: 2.0 * (x * z - w * y), m21: 2.0 * (y * z + w * x), m22: 1.0 - 2.0 * (x * x + y * y), m23: 0.0,
+            m30: 0.0, m31: 0.0, m32: 0.0, m33: 1.0,
+        }
+    }
+
+    pub fn dot(self, rhs: Self) -> Scalar {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z + self.w * rhs.w
+    }
+
+    pub fn length(self) -> Scalar {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(self) -> Self {
+        self * (1.0 / self.length())
+    }
+
+    /// Inverse of a unit quaternion.
+    pub fn conjugate(self) -> Self {
+        Self::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    /// Inverse of any non zero quaternion, `conjugate` is cheaper for rotations.
+    pub fn inverse(self) -> Self {
+        self.conjugate() * (1.0 / self.dot(self))
+    }
+
+    /// Same as `self * v`.
+    pub fn rotate(self, v: Vector3) -> Vector3 {
+        let u = Vector3::new(self.x, self.y, self.z);
+        let t = u.cross(v) * 2.0;
+        v + t * self.w + u.cross(t)
+    }
+
+    /// Linear interpolation normalized again, cheap and fine for close rotations but
+    /// its speed is not constant.
+    pub fn nlerp(self, other: Self, t: Scalar) -> Self {
+        let other = if self.dot(other) < 0.0 { other * -1.0 } else { other };
+        (self * (1.0 - t) + other * t).normalize()
+    }
+
+    /// Spherical linear interpolation, turning at constant speed.
+    pub fn slerp(self, other: Self, t: Scalar) -> Self {
+        let other = if self.dot(other) < 0.0 { other * -1.0 } else { other };
+        self.slerp_path(other, t)
+    }
+
+    /// `slerp` without choosing the shorter path, squad needs the path it is given.
+    fn slerp_path(self, other: Self, t: Scalar) -> Self {
+        let cos = self.dot(other);
+        if cos.abs() > 0.9995 {
+            // too close for the division by the sine
+            return (self * (1.0 - t) + other * t).normalize();
+        }
+        let angle = cos.clamp(-1.0, 1.0).acos();
+        let sin = angle.sin();
+        self * (((1.0 - t) * angle).sin() / sin) + other * ((t * angle).sin() / sin)
+    }
+
+    /// Spherical cubic interpolation from `self` to `other` with the control points
+    /// `a` and `b` from `squad_control`, smooth across the keys of a sequence.
+    pub fn squad(self, other: Self, a: Self, b: Self, t: Scalar) -> Self {
+        self.slerp_path(other, t).slerp_path(a.slerp_path(b, t), 2.0 * t * (1.0 - t))
+    }
+
+    /// Control point of `current` in a sequence of keys, for `squad` from `current`
+    /// as `a` and to it as `b`. Neighbouring keys have to be on the same side, a
+    /// negative dot product with `current` means a key should be negated first.
+    pub fn squad_control(previous: Self, current: Self, next: Self) -> Self {
+        let inverse = current.conjugate();
+        let sum = (inverse * next).log() + (inverse * previous).log();
+        current * (sum * -0.25).exp()
+    }
+
+    /// Logarithm of a unit quaternion, half the rotation vector.
+    fn log(self) -> Self {
+        let sin = (self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+        if sin < 1e-12 {
+            return Self::new(self.x, self.y, self.z, 0.0);
+        }
+        let scale = sin.atan2(self.w) / sin;
+        Self::new(self.x * scale, self.y * scale, self.z * scale, 0.0)
+    }
+
+    /// Exponential of a quaternion with `w = 0`, the inverse of `log`.
+    fn exp(self) -> Self {
+        let angle = (self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+        let (sin, cos) = angle.sin_cos();
+        let scale = if angle < 1e-12 { 1.0 } else { sin / angle };
+        Self::new(self.x * scale, self.y * scale, self.z * scale, cos)
+    }
 }
 
-impl Mul for Vector2 {
-    type Output = f64;
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
 
-    fn mul(self, rhs: Self) -> f64 {
-        self.x * rhs.x + self.y * rhs.y
+impl_op_ex!(+ |lhs: &Quaternion, rhs: &Quaternion| -> Quaternion {
+    Quaternion {
+        x: lhs.x + rhs.x,
+        y: lhs.y + rhs.y,
+        z: lhs.z + rhs.z,
+        w: lhs.w + rhs.w
+    }
+});
+
+impl_op_ex!(* |lhs: &Quaternion, rhs: Scalar| -> Quaternion {
+    Quaternion {
+        x: lhs.x * rhs,
+        y: lhs.y * rhs,
+        z: lhs.z * rhs,
+        w: lhs.w * rhs
+    }
+});
+
+impl_op_ex!(* |lhs: &Quaternion, rhs: &Quaternion| -> Quaternion {
+    Quaternion {
+        x: lhs.w * rhs.x + lhs.x * rhs.w + lhs.y * rhs.z - lhs.z * rhs.y,
+        y: lhs.w * rhs.y - lhs.x * rhs.z + lhs.y * rhs.w + lhs.z * rhs.x,
+        z: lhs.w * rhs.z + lhs.x * rhs.y - lhs.y * rhs.x + lhs.z * rhs.w,
+        w: lhs.w * rhs.w - lhs.x * rhs.x - lhs.y * rhs.y - lhs.z * rhs.z
+    }
+});
+
+impl_op_ex!(* |lhs: &Quaternion, rhs: &Vector3| -> Vector3 {
+    lhs.rotate(*rhs)
+});
+
+impl ApproxEq for Quaternion {
+    /// Component by component, `q` and `-q` differ although they are the same rotation.
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance) -> bool {
+        [self.x, self.y, self.z, self.w].approx_eq(&[other.x, other.y, other.z, other.w], tolerance)
     }
 }
 
-impl Mul<Vector2> for f64 {
-    type Output = Vector2;
+impl fmt::Display for Quaternion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_components(f, &[self.x, self.y, self.z, self.w])
+    }
+}
 
-    fn mul(self, rhs: Vector2) -> Vector2 {
-        Vector2 {
-            x: self * rhs.x,
-            y: self * rhs.y,
+/// Writes `(a, b, ...)`, passing formatting options like the precision on to the components.
+pub(crate) fn fmt_components(f: &mut fmt::Formatter, components: &[Scalar]) -> fmt::Result {
+    write!(f, "(")?;
+    for (i, component) in components.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
         }
+        fmt::Display::fmt(component, f)?;
     }
+    write!(f, ")")
 }
@@ -0,0 +1,121 @@
+use cpu_renderer::approx::{ApproxEq, Tolerance};
+use cpu_renderer::geometry::{Aabb, Frustum};
+use cpu_renderer::matrix::{Matrix3, Matrix4, Transform};
+use cpu_renderer::scalar::{consts, Scalar};
+use cpu_renderer::vector::{Quaternion, Vector2, Vector3, Vector4};
+
+/// The next representable value above `value`, for positive values.
+fn next_up(value: Scalar) -> Scalar {
+    Scalar::from_bits(value.to_bits() + 1)
+}
+
+#[test]
+fn ulps_scale_with_the_magnitude() {
+    let ulps = Tolerance::ulps(2);
+    for &value in [1e-20, 1.0, 1e20].iter() {
+        let value = value as Scalar;
+        let two = next_up(next_up(value));
+        assert!(value.approx_eq(&two, ulps));
+        assert!(!value.approx_eq(&next_up(two), ulps));
+    }
+    // both zeros are the same, and neighbours across zero are counted through it
+    assert!(0.0.approx_eq(&-0.0, Tolerance::ulps(0)));
+    let tiny = Scalar::from_bits(1);
+    assert!(tiny.approx_eq(&-tiny, Tolerance::ulps(2)));
+    assert!(!tiny.approx_eq(&-tiny, Tolerance::ulps(1)));
+}
+
+#[test]
+fn epsilon_is_absolute() {
+    let tolerance = Tolerance::absolute(0.1);
+    assert!((1.0 as Scalar).approx_eq(&1.05, tolerance));
+    assert!(!(1.0 as Scalar).approx_eq(&1.2, tolerance));
+    assert!(!(1e20 as Scalar).approx_eq(&next_up(1e20), tolerance.with_epsilon(0.0)));
+    assert!((1e20 as Scalar).approx_eq(&next_up(1e20), tolerance.with_ulps(1)));
+}
+
+#[test]
+fn special_values() {
+    let tolerance = Tolerance::new(Scalar::MAX, u32::MAX);
+    assert!(!Scalar::NAN.approx_eq(&Scalar::NAN, tolerance));
+    assert!(Scalar::INFINITY.approx_eq(&Scalar::INFINITY, Tolerance::ulps(0)));
+    assert!(!Scalar::INFINITY.approx_eq(&Scalar::MAX, tolerance));
+    assert!(!Scalar::INFINITY.approx_eq(&Scalar::NEG_INFINITY, tolerance));
+}
+
+#[test]
+fn math_types_compare_every_component() {
+    let tolerance = Tolerance::default();
+    let rotation = Matrix4::rotation_z(consts::FRAC_PI_2);
+    let quarter = Matrix4::from_rows([
+        Vector4::new(0.0, -1.0, 0.0, 0.0),
+        Vector4::new(1.0, 0.0, 0.0, 0.0),
+        Vector4::new(0.0, 0.0, 1.0, 0.0),
+        Vector4::new(0.0, 0.0, 0.0, 1.0),
+    ]);
+    assert_ne!(rotation, quarter);
+    assert!(rotation.approx_eq(&quarter, tolerance));
+    assert!(rotation.to_matrix3().approx_eq(&quarter.to_matrix3(), tolerance));
+    assert!(!rotation.approx_eq(&Matrix4 { m23: 1e-3, ..quarter }, tolerance));
+    assert!(!Matrix3::identity().approx_eq(&Matrix3 { m12: 1e-3, ..Matrix3::identity() }, tolerance));
+
+    let v = Vector3::new(1.0, 2.0, 3.0);
+    assert!((rotation * rotation * rotation * rotation * v).approx_eq(&v, tolerance));
+    assert!(!v.approx_eq(&Vector3::new(1.0, 2.0, 3.001), tolerance));
+    assert!(Vector2::new(0.1 + 0.2, 1.0).approx_eq(&Vector2::new(0.3, 1.0), tolerance));
+    assert!(!Vector4::zero().approx_eq(&Vector4::new(0.0, 0.0, 0.0, 1e-3), tolerance));
+
+    let q = Quaternion::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), consts::FRAC_PI_2);
+    assert!((q * q.inverse()).approx_eq(&Quaternion::identity(), tolerance));
+    let transform = Transform::identity().with_rotation(q).with_translation(v);
+    assert!((transform * transform.inverse().unwrap()).approx_eq(&Transform::identity(), tolerance));
+
+    let aabb = Aabb::new(Vector3::splat(-1.0), Vector3::splat(1.0));
+    assert!(aabb.transform(&rotation).approx_eq(&aabb, tolerance));
+    let frustum = Frustum::from_matrix(&Matrix4::perspective(consts::FRAC_PI_2, 1.0, 1.0, 10.0));
+    assert!(frustum.approx_eq(&frustum, Tolerance::ulps(0)));
+}
+
+#[test]
+fn display_passes_the_precision_on() {
+    let v = Vector3::new(1.0, -0.5, 1.0 / 3.0);
+    assert_eq!(v.to_string(), format!("(1, -0.5, {})", 1.0 as Scalar / 3.0));
+    assert_eq!(format!("{:.2}", v), "(1.00, -0.50, 0.33)");
+    assert_eq!(format!("{:.1}", Vector2::new(1.0, 2.0)), "(1.0, 2.0)");
+    assert_eq!(format!("{:.1}", Vector4::new(1.0, 2.0, 3.0, 4.0)), "(1.0, 2.0, 3.0, 4.0)");
+    assert_eq!(Quaternion::identity().to_string(), "(0, 0, 0, 1)");
+    assert_eq!(Matrix3::identity().to_string(), "(1, 0, 0)\n(0, 1, 0)\n(0, 0, 1)");
+    assert_eq!(
+        format!("{:.1}", Matrix4::translation(Vector3::new(1.0, 2.0, 3.0))),
+        "(1.0, 0.0, 0.0, 1.0)\n(0.0, 1.0, 0.0, 2.0)\n(0.0, 0.0, 1.0, 3.0)\n(0.0, 0.0, 0.0, 1.0)"
+    );
+    assert_eq!(Transform::identity().to_string(), "translation (0, 0, 0), rotation (0, 0, 0, 1), scale (1, 1, 1)");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn math_types_round_trip_through_serde() {
+    use cpu_renderer::geometry::{Plane, Ray, Sphere};
+
+    let v = Vector3::new(1.0, -2.0, 0.5);
+    assert_eq!(serde_json::to_string(&v).unwrap(), r#"{"x":1.0,"y":-2.0,"z":0.5}"#);
+    assert_eq!(serde_json::from_str::<Vector3>(r#"{"x":1.0,"y":-2.0,"z":0.5}"#).unwrap(), v);
+
+    fn round_trip<T: serde::Serialize + serde::de::DeserializeOwned + PartialEq + std::fmt::Debug>(value: T) {
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(serde_json::from_str::<T>(&json).unwrap(), value, "{}", json);
+    }
+    let matrix = Matrix4::perspective(consts::FRAC_PI_3, 1.5, 0.1, 100.0) * Matrix4::rotation_x(0.3);
+    round_trip(Vector2::new(0.25, 4.0));
+    round_trip(Vector4::new(1.0, 2.0, 3.0, 4.0));
+    round_trip(Quaternion::from_axis_angle(v, 0.7));
+    round_trip(matrix);
+    round_trip(matrix.to_matrix3());
+    round_trip(Transform::identity().with_translation(v).with_scale(Vector3::splat(2.0)));
+    round_trip(Aabb::new(-v, v));
+    round_trip(Plane::from_point_normal(v, Vector3::new(0.0, 1.0, 0.0)));
+    round_trip(Ray::new(v, Vector3::new(0.0, 0.0, 1.0)));
+    round_trip(Sphere::new(v, 3.0));
+    round_trip(Frustum::from_matrix(&matrix));
+    round_trip(cpu_renderer::color::Color::new(0.1, 0.2, 0.3, 0.5));
+}
@@ -0,0 +1,45 @@
+use cpu_renderer::atlas::{Atlas, AtlasBuilder, Rect};
+use cpu_renderer::format::{TexelBuffer, TexelFormat};
+use cpu_renderer::texture::ImageTexture;
+use cpu_renderer::vector::{Vector3, Vector4};
+
+#[test]
+fn packed_images_keep_their_texels_and_alpha() {
+    let translucent = Vector4::new(1.0, 0.0, 0.0, 0.25);
+    let clear = Vector4::new(0.0, 1.0, 0.0, 0.0);
+    let sprite = ImageTexture::from_buffer(2, 1, TexelBuffer::from_values(TexelFormat::Rgba32F, vec![translucent, clear]));
+    let opaque = ImageTexture::new(1, 1, vec![Vector3::new(0.0, 0.0, 1.0)]);
+
+    let mut builder = AtlasBuilder::new().with_padding(1).with_extrude(2);
+    let first = builder.add(sprite);
+    let second = builder.add(opaque);
+    let atlas = builder.build();
+    assert_eq!(atlas.len(), 2);
+
+    let rect = atlas.rect(first);
+    assert_eq!((rect.width, rect.height), (2, 1));
+    assert_eq!(atlas.texture.texel_value(rect.x, rect.y), translucent);
+    assert_eq!(atlas.texture.texel_value(rect.x + 1, rect.y), clear);
+    // the extruded border repeats the edge texels, alpha included
+    assert_eq!(atlas.texture.texel_value(rect.x - 2, rect.y + 1), translucent);
+    assert_eq!(atlas.texture.texel_value(rect.x + 3, rect.y - 1), clear);
+
+    // formats without alpha are opaque
+    let rect = atlas.rect(second);
+    assert_eq!(atlas.texture.texel_value(rect.x, rect.y), Vector4::new(0.0, 0.0, 1.0, 1.0));
+}
+
+#[test]
+fn grids_split_sheets_row_by_row() {
+    let sheet = ImageTexture::new(6, 4, vec![Vector3::zero(); 24]);
+    let atlas = Atlas::from_grid(sheet, 3, 2);
+    assert_eq!(atlas.len(), 6);
+    assert_eq!(atlas.rect(0), Rect { x: 0, y: 0, width: 2, height: 2 });
+    assert_eq!(atlas.rect(4), Rect { x: 2, y: 2, width: 2, height: 2 });
+}
+
+#[test]
+#[should_panic(expected = "at least one column and one row, got 0x2")]
+fn grids_need_columns() {
+    Atlas::from_grid(ImageTexture::new(1, 1, vec![Vector3::zero()]), 0, 2);
+}
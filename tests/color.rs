@@ -0,0 +1,111 @@
+use cpu_renderer::approx::{ApproxEq, Tolerance};
+use cpu_renderer::color::Color;
+use cpu_renderer::framebuffer::Framebuffer;
+use cpu_renderer::scalar::Scalar;
+use cpu_renderer::vector::{Vector3, Vector4};
+
+mod common;
+
+use common::{assert_close, EPSILON};
+
+/// For the published matrices, which are rounded to a few digits.
+const REFERENCE: Scalar = 1e-4;
+
+/// Colors spread over the RGB cube.
+fn samples() -> Vec<Color> {
+    let steps = [0.0, 0.05, 0.3, 0.5, 0.8, 1.0];
+    let mut colors = Vec::new();
+    for &r in steps.iter() {
+        for &g in steps.iter() {
+            for &b in steps.iter() {
+                colors.push(Color::rgb(r, g, b));
+            }
+        }
+    }
+    colors
+}
+
+#[test]
+fn srgb_round_trips() {
+    for color in samples() {
+        assert_close(Color::from_srgb(color.to_srgb()), color, EPSILON);
+    }
+    let orange = Color::from_srgb8(255, 128, 0).with_alpha(0.5);
+    assert_eq!(orange.to_srgb8(), [255, 128, 0, 128]);
+    assert!((orange.g - 0.2158605).abs() < 1e-6);
+    assert_eq!(Color::from_srgb8(255, 255, 255), Color::WHITE);
+}
+
+#[test]
+fn hsv_and_hsl() {
+    assert_close(Color::RED.to_hsv(), Vector3::new(0.0, 1.0, 1.0), EPSILON);
+    assert_close(Color::from_hsv(Vector3::new(120.0, 1.0, 1.0)), Color::GREEN, EPSILON);
+    assert_close(Color::from_hsl(Vector3::new(-120.0, 1.0, 0.5)), Color::BLUE, EPSILON);
+    assert_close(Color::gray(0.2).to_hsl(), Vector3::new(0.0, 0.0, Color::gray(0.2).to_srgb().x), EPSILON);
+
+    let purple = Color::from_srgb(Vector3::new(0.5, 0.25, 0.75));
+    assert_close(purple.to_hsv(), Vector3::new(270.0, 2.0 / 3.0, 0.75), EPSILON);
+    assert_close(purple.to_hsl(), Vector3::new(270.0, 0.5, 0.5), EPSILON);
+
+    for color in samples() {
+        assert_close(Color::from_hsv(color.to_hsv()), color, EPSILON);
+        assert_close(Color::from_hsl(color.to_hsl()), color, EPSILON);
+    }
+}
+
+#[test]
+fn xyz_and_luminance() {
+    assert_close(Color::WHITE.to_xyz(), Vector3::new(0.95047, 1.0, 1.08883), REFERENCE);
+    assert!((Color::WHITE.luminance() - 1.0).abs() < REFERENCE);
+    assert!(Color::GREEN.luminance() > Color::RED.luminance() && Color::RED.luminance() > Color::BLUE.luminance());
+    for color in samples() {
+        assert!((color.luminance() - color.to_xyz().y).abs() < EPSILON);
+        assert_close(Color::from_xyz(color.to_xyz()), color, REFERENCE);
+    }
+}
+
+#[test]
+fn oklab() {
+    assert_close(Color::WHITE.to_oklab(), Vector3::new(1.0, 0.0, 0.0), REFERENCE);
+    assert_close(Color::BLACK.to_oklab(), Vector3::zero(), EPSILON);
+    assert_close(Color::RED.to_oklab(), Vector3::new(0.627955, 0.224863, 0.125846), REFERENCE);
+    for color in samples() {
+        assert_close(Color::from_oklab(color.to_oklab()), color, REFERENCE);
+    }
+}
+
+#[test]
+fn packing_matches_the_framebuffer() {
+    assert_eq!(Color::rgb(1.0, 0.5, 0.0).to_u32(), 0xff7f00);
+    assert_eq!(Color::new(2.0, -1.0, 0.25, 0.0).to_u32(), 0xff003f);
+    for byte in 0..=255u32 {
+        for &packed in [byte, byte << 8, byte << 16].iter() {
+            assert_eq!(Color::from_u32(packed).to_u32(), packed);
+        }
+    }
+
+    let mut buffer = Framebuffer::new(4, 3);
+    buffer.clear(Color::rgb(1.0, 0.5, 0.0));
+    buffer.finish_rendering();
+    assert!(buffer.colors().iter().all(|&c| c == 0xff7f00));
+    assert!(buffer.to_image().pixels.iter().all(|&p| Color::from(p) == Color::from_u32(0xff7f00)));
+
+    // vectors still clear to opaque colors
+    buffer.clear(Vector3::new(0.0, 0.0, 1.0));
+    buffer.finish_rendering();
+    assert!(buffer.colors().iter().all(|&c| c == 0x0000ff));
+}
+
+#[test]
+fn arithmetic_and_conversions() {
+    let a = Color::new(0.2, 0.4, 0.6, 1.0);
+    let b = Color::new(1.0, 0.5, 0.0, 0.5);
+    assert!((a * b).approx_eq(&Color::new(0.2, 0.2, 0.0, 0.5), Tolerance::default()));
+    assert!((a + b - b).approx_eq(&a, Tolerance::default()));
+    assert!(a.lerp(b, 0.5).approx_eq(&((a + b) * 0.5), Tolerance::default()));
+    assert_eq!(2.0 * a, a * 2.0);
+    assert_eq!(Color::from(Vector3::new(0.1, 0.2, 0.3)), Color::rgb(0.1, 0.2, 0.3));
+    assert_eq!(Vector4::from(b), Vector4::new(1.0, 0.5, 0.0, 0.5));
+    assert_eq!(Color::default(), Color::TRANSPARENT);
+    assert_eq!(format!("{:.1}", b), "(1.0, 0.5, 0.0, 0.5)");
+}
@@ -0,0 +1,94 @@
+//! Fixtures shared by the integration tests and the benchmarks, which include this file
+//! with `#[path]`. Every user needs only part of it.
+#![allow(dead_code)]
+
+use std::fmt::Debug;
+use std::ops::{Add, Mul};
+
+use cpu_renderer::approx::{ApproxEq, Tolerance};
+
+use cpu_renderer::framebuffer::Framebuffer;
+use cpu_renderer::matrix::Matrix4;
+use cpu_renderer::renderer::{Program, RenderRegion};
+use cpu_renderer::scalar::{consts, Scalar};
+use cpu_renderer::vector::{Quaternion, Vector3, Vector4};
+
+pub const EPSILON: Scalar = if cfg!(feature = "f32") { 1e-4 } else { 1e-9 };
+/// For results of longer computations, like inverses.
+pub const LOOSE: Scalar = if cfg!(feature = "f32") { 1e-2 } else { 1e-6 };
+
+/// Asserts that no component of `a` and `b` differs by more than `epsilon`.
+pub fn assert_close<T: ApproxEq + Debug>(a: T, b: T, epsilon: Scalar) {
+    assert!(a.approx_eq(&b, Tolerance::absolute(epsilon)), "{:?} != {:?}", a, b);
+}
+
+/// Small deterministic generator, so that failures are reproducible.
+pub struct Rng(pub u64);
+
+impl Rng {
+    /// Uniform in `[-1, 1)`.
+    pub fn next(&mut self) -> Scalar {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        ((self.0 >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0) as Scalar
+    }
+
+    pub fn vector4(&mut self) -> Vector4 {
+        Vector4::new(self.next(), self.next(), self.next(), self.next())
+    }
+
+    pub fn vector3(&mut self) -> Vector3 {
+        Vector3::new(self.next(), self.next(), self.next())
+    }
+
+    pub fn matrix(&mut self) -> Matrix4 {
+        Matrix4::from_rows([self.vector4(), self.vector4(), self.vector4(), self.vector4()])
+    }
+
+    pub fn rotation(&mut self) -> Quaternion {
+        Quaternion::from_axis_angle(self.vector3(), self.next() * consts::PI)
+    }
+
+    pub fn affine(&mut self) -> Matrix4 {
+        Matrix4 {
+            m30: 0.0,
+            m31: 0.0,
+            m32: 0.0,
+            m33: 1.0,
+            ..self.matrix()
+        }
+    }
+}
+
+/// The roller of the demo made of `segments` quads around the y axis, with a radius and a
+/// half height of 1. The uniform holds the time and the normal of the current quad.
+pub fn enqueue_roller<Attr, const N: usize>(program: &mut Program<Vector3, (Scalar, Vector3), Attr, N>, segments: usize)
+    where
+        Attr: Add<Output=Attr> + Clone + Mul<Scalar, Output=Attr>,
+        for<'a> &'a Attr: Add<Output=Attr> + Clone + Mul<Scalar, Output=Attr> {
+    let theta = 2.0 * consts::PI / segments as Scalar;
+    let edge = |i: Scalar| Matrix4::rotation_y(i * theta).transform_direction(Vector3::new(0.0, 0.0, 1.0));
+    for i in 0..segments {
+        let (edge0, edge1) = (edge(i as Scalar), edge(i as Scalar + 1.0));
+        program.uniform.1 = edge(i as Scalar + 0.5);
+        program.enqueue_triangle(Vector3::new(edge0.x, -1.0, edge0.z), Vector3::new(edge1.x, -1.0, edge1.z), Vector3::new(edge1.x, 1.0, edge1.z));
+        program.enqueue_triangle(Vector3::new(edge0.x, 1.0, edge0.z), Vector3::new(edge0.x, -1.0, edge0.z), Vector3::new(edge1.x, 1.0, edge1.z));
+    }
+}
+
+/// Renders every region of `program` into `buffer` on the current thread and presents the
+/// colors. `program` has to use square regions of `region_size` pixels.
+pub fn render<In, U, Attr, const N: usize>(program: &mut Program<In, U, Attr, N>, buffer: &mut Framebuffer, region_size: usize)
+    where
+        U: Clone,
+        Attr: Add<Output=Attr> + Clone + Mul<Scalar, Output=Attr>,
+        for<'a> &'a Attr: Add<Output=Attr> + Clone + Mul<Scalar, Output=Attr> {
+    let regions = buffer.regions(region_size, region_size);
+    for (renderers, regions) in program.regions.iter_mut().zip(regions) {
+        for (renderer, mut region) in renderers.iter_mut().zip(regions) {
+            renderer.render_region(&mut region);
+        }
+    }
+    buffer.finish_rendering();
+}
@@ -0,0 +1,164 @@
+use cpu_renderer::cubemap::{self, CubeFace, CubeTexture, SkyboxUniform};
+use cpu_renderer::framebuffer::{Framebuffer, Pick};
+use cpu_renderer::matrix::Matrix4;
+use cpu_renderer::renderer::Program;
+use cpu_renderer::scalar::{consts, Scalar};
+use cpu_renderer::texture::{Filter, ImageTexture, Texture};
+use cpu_renderer::vector::{Vector2, Vector3};
+
+mod common;
+
+use common::{assert_close, Rng, EPSILON, LOOSE};
+
+/// Distinct primary or secondary color of every face, in the order of `CubeFace::ALL`.
+const COLORS: [(Scalar, Scalar, Scalar); 6] =
+    [(1.0, 0.0, 0.0), (0.0, 1.0, 0.0), (0.0, 0.0, 1.0), (1.0, 1.0, 0.0), (0.0, 1.0, 1.0), (1.0, 0.0, 1.0)];
+
+fn color(face: CubeFace) -> Vector3 {
+    let (r, g, b) = COLORS[face as usize];
+    Vector3::new(r, g, b)
+}
+
+/// Cube of solid faces with `size` texels along each edge.
+fn solid_cube(size: usize) -> CubeTexture {
+    let face = |face: CubeFace| ImageTexture::new(size, size, vec![color(face); size * size]);
+    CubeTexture::from_faces([
+        face(CubeFace::PositiveX),
+        face(CubeFace::NegativeX),
+        face(CubeFace::PositiveY),
+        face(CubeFace::NegativeY),
+        face(CubeFace::PositiveZ),
+        face(CubeFace::NegativeZ),
+    ])
+}
+
+#[test]
+fn faces_project_back_onto_themselves() {
+    let mut rng = Rng(1);
+    for &face in CubeFace::ALL.iter() {
+        // the center of a face lies along its axis
+        let axis = face.direction(0.0, 0.0);
+        assert_eq!(axis.length(), 1.0);
+        assert_eq!(CubeFace::project(axis * 3.0), (face, 0.0, 0.0));
+
+        for _ in 0..1000 {
+            let (s, t) = (rng.next(), rng.next());
+            let scale = rng.next().abs() + 0.5;
+            let (projected, ps, pt) = CubeFace::project(face.direction(s, t) * scale);
+            assert_eq!(projected, face);
+            assert!((ps - s).abs() < EPSILON && (pt - t).abs() < EPSILON, "{:?} {:?}", (s, t), (ps, pt));
+        }
+    }
+}
+
+#[test]
+fn bilinear_filtering_crosses_face_edges() {
+    let cube = solid_cube(2);
+    // inside a face nothing of the neighbours shows
+    assert_eq!(cube.sample(Vector3::new(1.0, 0.1, 0.2)), color(CubeFace::PositiveX));
+    // on the edge between +x and +z, half of each
+    let edge = Vector3::new(1.0, 0.0, 1.0);
+    assert_close(cube.sample(edge), (color(CubeFace::PositiveX) + color(CubeFace::PositiveZ)) * 0.5, EPSILON);
+    let edge = Vector3::new(0.0, -1.0, -1.0);
+    assert_close(cube.sample(edge), (color(CubeFace::NegativeY) + color(CubeFace::NegativeZ)) * 0.5, EPSILON);
+    // a quarter texel away from the edge, a quarter of the neighbour
+    let s: Scalar = 1.0 - 0.25;
+    let near_edge = CubeFace::PositiveZ.direction(s, 0.0);
+    let expected = color(CubeFace::PositiveZ) * 0.75 + color(CubeFace::PositiveX) * 0.25;
+    assert_close(cube.sample(near_edge), expected, EPSILON);
+
+    // nearest filtering stays on the face the direction projects to
+    let nearest = cube.with_filter(Filter::Nearest);
+    assert_eq!(nearest.sample(Vector3::new(1.0, 0.0, 1.0)), color(CubeFace::PositiveX));
+}
+
+/// Panorama holding its own coordinates, `(u, v, 0)`.
+struct Coordinates;
+
+impl Texture for Coordinates {
+    fn sample(&self, uv: Vector2) -> Vector3 {
+        Vector3::new(uv.x, uv.y, 0.0)
+    }
+}
+
+#[test]
+fn panoramas_wrap_around_the_cube() {
+    // one texel per face, sampled along the axis of the face
+    let cube = CubeTexture::from_equirectangular(&Coordinates, 1);
+    let uv = |face: CubeFace| cube.face(face).texel(0, 0);
+    // the center looks towards -z, seen from the inside the right is -x
+    assert_close(uv(CubeFace::NegativeZ), Vector3::new(0.5, 0.5, 0.0), EPSILON);
+    assert_close(uv(CubeFace::NegativeX), Vector3::new(0.75, 0.5, 0.0), EPSILON);
+    assert_close(uv(CubeFace::PositiveX), Vector3::new(0.25, 0.5, 0.0), EPSILON);
+    // +z is behind, where the left and right edges meet
+    let behind = uv(CubeFace::PositiveZ).x;
+    assert!(!(EPSILON..=1.0 - EPSILON).contains(&behind), "{}", behind);
+    // the top row is +y, the bottom one -y
+    assert_close(Vector3::new(0.0, uv(CubeFace::PositiveY).y, 0.0), Vector3::zero(), EPSILON);
+    assert_close(Vector3::new(0.0, uv(CubeFace::NegativeY).y, 0.0), Vector3::new(0.0, 1.0, 0.0), EPSILON);
+
+    // texels away from the axis follow the directions through their centers
+    let cube = CubeTexture::from_equirectangular(&Coordinates, 4);
+    let texel = cube.face(CubeFace::NegativeZ).texel(3, 0);
+    let direction = CubeFace::NegativeZ.direction(0.75, -0.75).normalize();
+    let expected = Vector3::new(0.5 + (-direction.x).atan2(-direction.z) / (2.0 * consts::PI), direction.y.acos() / consts::PI, 0.0);
+    // stored with single precision
+    assert_close(texel, expected, LOOSE);
+    // the top right texel of the face is right of the center and above the horizon in the
+    // panorama as well
+    assert!(texel.x > 0.5 && texel.y < 0.5, "{:?}", texel);
+}
+
+#[test]
+fn skybox_keeps_up_at_the_top_of_the_frame() {
+    const SIZE: usize = 64;
+    // looking towards +z, wide enough that +y and -y show at the top and bottom
+    let projection = Matrix4::perspective(consts::PI * 2.0 / 3.0, 1.0, 0.1, 10.0);
+    let view = Matrix4::look_at(Vector3::zero(), Vector3::new(0.0, 0.0, 1.0), Vector3::new(0.0, 1.0, 0.0));
+    let uniform = SkyboxUniform { texture: solid_cube(4), view_projection: projection * view };
+    let mut program = cubemap::skybox_program(uniform, SIZE, SIZE, 16, 16);
+    cubemap::enqueue_skybox(&mut program, 8);
+    let mut buffer = Framebuffer::new(SIZE, SIZE);
+    common::render(&mut program, &mut buffer, 16);
+
+    let packed = |face: CubeFace| {
+        let c = color(face);
+        ((c.x * 255.0) as u32) << 16 | ((c.y * 255.0) as u32) << 8 | (c.z * 255.0) as u32
+    };
+    let pixel = |x: usize, y: usize| buffer.colors()[y * SIZE + x];
+    assert_eq!(pixel(SIZE / 2, 1), packed(CubeFace::PositiveY));
+    assert_eq!(pixel(SIZE / 2, SIZE - 2), packed(CubeFace::NegativeY));
+    assert_eq!(pixel(SIZE / 2, SIZE / 2), packed(CubeFace::PositiveZ));
+    // +x is to the right
+    assert_eq!(pixel(SIZE - 2, SIZE / 2), packed(CubeFace::PositiveX));
+    assert_eq!(pixel(1, SIZE / 2), packed(CubeFace::NegativeX));
+}
+
+fn flat(_: Vector3, _: Scalar, color: &Vector3) -> Vector3 {
+    *color
+}
+
+#[test]
+fn the_sky_is_not_picked() {
+    const SIZE: usize = 32;
+    let projection = Matrix4::perspective(consts::FRAC_PI_2, 1.0, 0.1, 10.0);
+    let uniform = SkyboxUniform { texture: solid_cube(1), view_projection: projection };
+    let mut sky = cubemap::skybox_program(uniform, SIZE, SIZE, 16, 16);
+    cubemap::enqueue_skybox(&mut sky, 4);
+    let mut buffer = Framebuffer::new(SIZE, SIZE);
+    common::render(&mut sky, &mut buffer, 16);
+
+    // an object over the left half in front of the sky
+    let screen = |v: Vector3, _: &Vector3| (v, v.z);
+    let mut scene = Program::new(screen, flat, Vector3::splat(1.0), SIZE, SIZE, 16, 16);
+    scene.object_id = 3;
+    scene.enqueue_triangle(Vector3::new(0.0, -1.0, 0.5), Vector3::new(0.5, -1.0, 0.5), Vector3::new(0.0, 3.0, 0.5));
+    scene.enqueue_triangle(Vector3::new(0.5, -1.0, 0.5), Vector3::new(0.5, 3.0, 0.5), Vector3::new(0.0, 3.0, 0.5));
+    common::render(&mut scene, &mut buffer, 16);
+
+    assert_eq!(buffer.pick(4, 16), Some(Pick { id: 3, depth: 0.5 }));
+    assert_ne!(buffer.colors()[16 * SIZE + SIZE - 4], 0, "the sky was drawn");
+    assert_eq!(buffer.pick(SIZE - 4, 16), None);
+    assert_eq!(buffer.pick_rect(SIZE / 2, 0, SIZE, SIZE), None);
+    assert_eq!(buffer.pick_rect(0, 0, SIZE, SIZE), Some(Pick { id: 3, depth: 0.5 }));
+}
@@ -0,0 +1,87 @@
+use cpu_renderer::format::{srgb_to_linear, TexelBuffer, TexelFormat};
+use cpu_renderer::framebuffer::{Framebuffer, Plane};
+use cpu_renderer::scalar::Scalar;
+use cpu_renderer::texture::Texture;
+use cpu_renderer::vector::{Vector2, Vector3, Vector4};
+
+mod common;
+
+use common::{assert_close, EPSILON};
+
+/// `value` stored as `R16F` and read back.
+fn half(value: Scalar) -> Scalar {
+    TexelBuffer::from_values(TexelFormat::R16F, vec![Vector4::new(value, 0.0, 0.0, 1.0)]).get(0).x
+}
+
+#[test]
+fn half_floats_round_to_nearest_even() {
+    for &value in [0.0, 1.0, -2.5, 0.375, 65504.0].iter() {
+        assert_eq!(half(value), value);
+    }
+    // one ulp is 2^-10 between one and two, ties go to the even mantissa
+    let ulp = Scalar::powi(2.0, -10);
+    assert_eq!(half(1.0 + ulp * 0.5), 1.0);
+    assert_eq!(half(1.0 + ulp * 1.5), 1.0 + ulp * 2.0);
+    assert_eq!(half(1.0 + ulp * 0.75), 1.0 + ulp);
+
+    // past the largest half float, rounding up to infinity
+    assert_eq!(half(65520.0), Scalar::INFINITY);
+    assert_eq!(half(-1e10), -Scalar::INFINITY);
+    assert!(half(Scalar::NAN).is_nan());
+    // subnormals down to 2^-24, half of that is a tie rounding to zero
+    let smallest = Scalar::powi(2.0, -24);
+    assert_eq!(half(smallest * 3.0), smallest * 3.0);
+    assert_eq!(half(smallest * 0.5), 0.0);
+    assert_eq!(half(smallest * 0.75), smallest);
+}
+
+#[cfg(not(feature = "f32"))]
+#[test]
+fn half_floats_round_once() {
+    // just above a tie, but rounding to `f32` first would land on the tie and round down
+    let ulp = Scalar::powi(2.0, -10);
+    assert_eq!(half(1.0 + ulp * 0.5 + Scalar::powi(2.0, -40)), 1.0 + ulp);
+    let smallest = Scalar::powi(2.0, -24);
+    assert_eq!(half(smallest * 0.5 + Scalar::powi(2.0, -60)), smallest);
+}
+
+#[test]
+fn fill_overwrites_every_texel() {
+    for &format in [TexelFormat::Rgba8, TexelFormat::R16F, TexelFormat::Rgba32F, TexelFormat::Rgb64F, TexelFormat::Depth24].iter() {
+        let mut buffer = TexelBuffer::new(format, 7, Vector4::new(0.25, 0.5, 0.75, 1.0));
+        buffer.set(3, Vector4::zero());
+        let value = Vector4::new(1.0, 0.0, 0.5, 0.0);
+        buffer.fill(value);
+        assert_eq!(buffer.len(), 7);
+        let expected = TexelBuffer::new(format, 7, value);
+        assert_eq!(buffer.bytes(), expected.bytes(), "{:?}", format);
+    }
+}
+
+#[test]
+fn framebuffer_colors_keep_the_precision_of_scalars() {
+    let mut buffer = Framebuffer::new(2, 1);
+    let color = Vector3::new(0.1, 1.0 / 3.0, 1e-7);
+    buffer.set_color(1, 0, color, 0.5);
+    assert_eq!(buffer.texture(Plane::Color).texel(1, 0), color);
+    assert_eq!(buffer.texture(Plane::Depth).texel(1, 0), Vector3::splat(0.5));
+}
+
+#[test]
+fn srgb_planes_encode_writes_and_decode_samples() {
+    let mut buffer = Framebuffer::new(2, 1);
+    buffer.set_color_format(TexelFormat::Rgba8Srgb);
+    buffer.set_color(0, 0, Vector3::new(0.5, 0.0, 1.0), 0.5);
+    buffer.finish_rendering();
+    // 0.5 is encoded as 0.735 and presented as it is stored
+    assert_eq!(buffer.colors()[0], 0xbc00ff);
+    assert_eq!(buffer.colors()[1], 0x000000);
+
+    let texture = buffer.texture(Plane::Color);
+    assert_eq!(texture.format(), TexelFormat::Rgba8Srgb);
+    // decoded from the stored byte, not the written value
+    let decoded = srgb_to_linear(188.0 / 255.0);
+    assert!((decoded - 0.5).abs() < 5e-3);
+    assert_close(texture.sample(Vector2::new(0.25, 0.5)), Vector3::new(decoded, 0.0, 1.0), EPSILON);
+    assert_close(buffer.sample(Vector2::new(0.0, 0.0)), Vector3::new(decoded, 0.0, 1.0), EPSILON);
+}
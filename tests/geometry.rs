@@ -0,0 +1,122 @@
+use cpu_renderer::geometry::{Aabb, Containment, Frustum, Plane, Ray, Sphere};
+use cpu_renderer::matrix::Matrix4;
+use cpu_renderer::scalar::consts;
+use cpu_renderer::vector::{Vector2, Vector3};
+
+mod common;
+
+use common::{assert_close, EPSILON};
+
+fn unit_box() -> Aabb {
+    Aabb::new(Vector3::splat(-1.0), Vector3::splat(1.0))
+}
+
+#[test]
+fn aabb_from_points_and_transform() {
+    assert!(Aabb::empty().is_empty());
+    let points = [Vector3::new(1.0, -2.0, 0.5), Vector3::new(-1.0, 3.0, 0.0), Vector3::new(0.0, 0.0, 2.0)];
+    let aabb = Aabb::from_points(points.iter().cloned());
+    assert_eq!(aabb, Aabb::new(Vector3::new(-1.0, -2.0, 0.0), Vector3::new(1.0, 3.0, 2.0)));
+    assert!(points.iter().all(|&p| aabb.contains(p)));
+
+    let matrix = Matrix4::translation(Vector3::new(5.0, 0.0, 0.0)) * Matrix4::rotation_z(consts::FRAC_PI_4);
+    let transformed = unit_box().transform(&matrix);
+    let half = consts::SQRT_2;
+    assert_close(transformed.min, Vector3::new(5.0 - half, -half, -1.0), EPSILON);
+    assert_close(transformed.max, Vector3::new(5.0 + half, half, 1.0), EPSILON);
+}
+
+#[test]
+fn ray_triangle() {
+    let (a, b, c) = (Vector3::new(0.0, 0.0, 5.0), Vector3::new(1.0, 0.0, 5.0), Vector3::new(0.0, 1.0, 5.0));
+    let ray = Ray::new(Vector3::new(0.25, 0.5, 0.0), Vector3::new(0.0, 0.0, 2.0));
+    let (t, u, v) = ray.intersect_triangle(a, b, c).unwrap();
+    assert!((t - 2.5).abs() < EPSILON);
+    assert!((u - 0.25).abs() < EPSILON && (v - 0.5).abs() < EPSILON);
+    assert_close(ray.at(t), a * (1.0 - u - v) + b * u + c * v, EPSILON);
+
+    // the back side counts, behind the origin and outside the edges do not
+    assert!(Ray::new(Vector3::new(0.25, 0.25, 10.0), Vector3::new(0.0, 0.0, -1.0)).intersect_triangle(a, b, c).is_some());
+    assert!(Ray::new(Vector3::new(0.25, 0.25, 10.0), Vector3::new(0.0, 0.0, 1.0)).intersect_triangle(a, b, c).is_none());
+    assert!(Ray::new(Vector3::new(0.75, 0.75, 0.0), Vector3::new(0.0, 0.0, 1.0)).intersect_triangle(a, b, c).is_none());
+
+    // tiny triangles and short directions are hit no matter the scale, parallel rays miss
+    let tiny = |v: Vector3| v * 1e-5;
+    let ray = Ray::new(Vector3::new(0.25e-5, 0.25e-5, 0.0), Vector3::new(0.0, 0.0, 1e-6));
+    let (t, _, _) = ray.intersect_triangle(tiny(a), tiny(b), tiny(c)).unwrap();
+    assert!((ray.at(t).z - 5e-5).abs() < EPSILON);
+    assert!(Ray::new(Vector3::zero(), Vector3::new(1.0, 0.0, 0.0)).intersect_triangle(a, b, c).is_none());
+}
+
+#[test]
+fn ray_aabb_sphere_and_plane() {
+    let ray = Ray::new(Vector3::new(-5.0, 0.5, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    assert_eq!(ray.intersect_aabb(&unit_box()), Some((4.0, 6.0)));
+    assert_eq!(Ray::new(Vector3::zero(), Vector3::new(0.0, 1.0, 0.0)).intersect_aabb(&unit_box()), Some((0.0, 1.0)));
+    assert_eq!(Ray::new(Vector3::new(-5.0, 2.0, 0.0), Vector3::new(1.0, 0.0, 0.0)).intersect_aabb(&unit_box()), None);
+    assert_eq!(Ray::new(Vector3::new(5.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0)).intersect_aabb(&unit_box()), None);
+
+    let sphere = Sphere::new(Vector3::new(0.0, 0.0, 10.0), 2.0);
+    let forward = Ray::new(Vector3::zero(), Vector3::new(0.0, 0.0, 1.0));
+    assert_eq!(forward.intersect_sphere(&sphere), Some(8.0));
+    assert_eq!(Ray::new(sphere.center, Vector3::new(1.0, 0.0, 0.0)).intersect_sphere(&sphere), Some(2.0));
+    assert_eq!(Ray::new(Vector3::new(3.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0)).intersect_sphere(&sphere), None);
+
+    let plane = Plane::from_point_normal(Vector3::new(0.0, 0.0, 3.0), Vector3::new(0.0, 0.0, -2.0));
+    assert_eq!(plane.signed_distance(Vector3::zero()), 3.0);
+    assert_eq!(forward.intersect_plane(&plane), Some(3.0));
+    assert_eq!(Ray::new(Vector3::zero(), Vector3::new(1.0, 0.0, 0.0)).intersect_plane(&plane), None);
+}
+
+fn camera() -> Matrix4 {
+    Matrix4::look_at(Vector3::new(0.0, 0.0, -10.0), Vector3::zero(), Vector3::new(0.0, 1.0, 0.0))
+}
+
+#[test]
+fn frustum_culling() {
+    let projections = [
+        Matrix4::perspective(consts::FRAC_PI_2, 1.0, 1.0, 100.0),
+        Matrix4::perspective_infinite(consts::FRAC_PI_2, 1.0, 1.0),
+        Matrix4::perspective_reverse_z(consts::FRAC_PI_2, 1.0, 1.0, 100.0),
+        Matrix4::perspective_infinite_reverse_z(consts::FRAC_PI_2, 1.0, 1.0),
+        Matrix4::orthographic(-12.0, 12.0, -12.0, 12.0, 1.0, 100.0),
+    ];
+    for projection in projections.iter() {
+        let frustum = Frustum::from_matrix(&(projection * camera()));
+        assert!(frustum.contains(Vector3::zero()));
+        assert_eq!(frustum.classify_aabb(&unit_box()), Containment::Inside);
+        assert_eq!(frustum.classify_sphere(&Sphere::new(Vector3::zero(), 1.0)), Containment::Inside);
+
+        // behind the camera, and straddling the left side
+        let behind = Aabb::new(Vector3::new(-1.0, -1.0, -15.0), Vector3::new(1.0, 1.0, -12.0));
+        assert_eq!(frustum.classify_aabb(&behind), Containment::Outside);
+        assert!(!frustum.intersects_sphere(&Sphere::new(Vector3::new(0.0, 0.0, -20.0), 1.0)));
+        let side = Vector3::new(-10.0, 0.0, 0.0);
+        assert_eq!(frustum.classify_aabb(&Aabb::new(side - Vector3::splat(3.0), side + Vector3::splat(3.0))), Containment::Intersecting);
+        assert_eq!(frustum.classify_sphere(&Sphere::new(side, 3.0)), Containment::Intersecting);
+        assert!(!frustum.intersects_aabb(&Aabb::new(Vector3::new(-40.0, -1.0, -1.0), Vector3::new(-30.0, 1.0, 1.0))));
+    }
+}
+
+#[test]
+fn picking_rays_start_at_the_near_plane() {
+    let projections = [
+        Matrix4::perspective(consts::FRAC_PI_2, 1.5, 1.0, 100.0),
+        Matrix4::perspective_infinite(consts::FRAC_PI_2, 1.5, 1.0),
+        Matrix4::perspective_reverse_z(consts::FRAC_PI_2, 1.5, 1.0, 100.0),
+        Matrix4::perspective_infinite_reverse_z(consts::FRAC_PI_2, 1.5, 1.0),
+    ];
+    for projection in projections.iter() {
+        let to_screen = Matrix4::viewport() * projection * camera();
+        let center = Ray::from_screen(&to_screen, Vector2::new(0.5, 0.5)).unwrap();
+        assert_close(center.origin, Vector3::new(0.0, 0.0, -9.0), 1e3 * EPSILON);
+        assert_close(center.direction, Vector3::new(0.0, 0.0, 1.0), 1e3 * EPSILON);
+
+        // a ray through a projected point hits it
+        let point = Vector3::new(2.0, -1.0, 3.0);
+        let screen = to_screen.transform_point(point);
+        let ray = Ray::from_screen(&to_screen, Vector2::new(screen.x, screen.y)).unwrap();
+        let t = (point - ray.origin).length();
+        assert_close(ray.at(t), point, 1e3 * EPSILON);
+    }
+}
@@ -0,0 +1,173 @@
+use cpu_renderer::image::{self, Image, ImageError};
+use cpu_renderer::vector::Vector4;
+
+const RED: Vector4 = Vector4 { x: 1.0, y: 0.0, z: 0.0, w: 1.0 };
+const GREEN: Vector4 = Vector4 { x: 0.0, y: 1.0, z: 0.0, w: 1.0 };
+const BLUE: Vector4 = Vector4 { x: 0.0, y: 0.0, z: 1.0, w: 1.0 };
+const WHITE: Vector4 = Vector4 { x: 1.0, y: 1.0, z: 1.0, w: 1.0 };
+
+fn assert_pixels(image: &Image, width: usize, height: usize, pixels: &[Vector4]) {
+    assert_eq!((image.width, image.height), (width, height));
+    assert_eq!(image.pixels, pixels);
+}
+
+/// Every proper prefix of `bytes` is an error, not a panic.
+fn assert_truncations_fail(bytes: &[u8]) {
+    for len in 0..bytes.len() {
+        assert!(image::decode(&bytes[..len]).is_err(), "prefix of {} bytes decoded", len);
+    }
+}
+
+#[test]
+fn ppm_and_pgm_decode() {
+    let binary = b"P6\n2 1\n255\n\xff\x00\x00\x00\x00\xff";
+    assert_pixels(&image::decode(binary).unwrap(), 2, 1, &[RED, BLUE]);
+    assert_truncations_fail(binary);
+
+    let ascii = b"P3\n# two rows\n1 2\n255\n0 255 0\n255 255 255\n";
+    assert_pixels(&image::decode(ascii).unwrap(), 1, 2, &[GREEN, WHITE]);
+
+    // 16-bit samples are big endian
+    let wide = b"P5 2 1 65535\n\xff\xff\x00\x00";
+    let black = Vector4::new(0.0, 0.0, 0.0, 1.0);
+    assert_pixels(&image::decode(wide).unwrap(), 2, 1, &[WHITE, black]);
+    assert_truncations_fail(wide);
+}
+
+#[test]
+fn malformed_ppm_is_rejected() {
+    assert!(matches!(image::decode(b"P6\n0 1\n255\n"), Err(ImageError::InvalidHeader(_))));
+    assert!(matches!(image::decode(b"P6\n1 1\n0\n\x00\x00\x00"), Err(ImageError::InvalidHeader(_))));
+    assert!(matches!(image::decode(b"P6\n1 1\n255\x00\x00\x00"), Err(ImageError::InvalidHeader(_))));
+    assert!(matches!(image::decode(b"P3\n1 1\n100\n0 101 0\n"), Err(ImageError::InvalidData(_))));
+    assert!(matches!(image::decode(b"P3\n1 x\n255\n"), Err(ImageError::InvalidData(_))));
+    assert!(matches!(image::decode(b"P3\n99999999999 1\n255\n"), Err(ImageError::InvalidData(_))));
+    assert!(matches!(image::decode(b"P1\n1 1\n1\n"), Err(ImageError::Unsupported(_))));
+}
+
+/// TGA header of an image without id and color map.
+fn tga_header(image_type: u8, width: u16, height: u16, depth: u8, descriptor: u8) -> Vec<u8> {
+    let mut header = vec![0, 0, image_type, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    header.extend_from_slice(&width.to_le_bytes());
+    header.extend_from_slice(&height.to_le_bytes());
+    header.extend_from_slice(&[depth, descriptor]);
+    header
+}
+
+#[test]
+fn tga_decodes() {
+    // 24 bits in BGR order, rows stored bottom to top by default
+    let mut bottom_up = tga_header(2, 1, 2, 24, 0);
+    bottom_up.extend_from_slice(&[0, 0, 255, 255, 0, 0]);
+    assert_pixels(&image::decode(&bottom_up).unwrap(), 1, 2, &[BLUE, RED]);
+    assert_truncations_fail(&bottom_up);
+
+    // 32 bits with 8 alpha bits, rows stored top to bottom
+    let mut top_down = tga_header(2, 2, 1, 32, 0x28);
+    top_down.extend_from_slice(&[0, 255, 0, 255, 255, 255, 255, 0]);
+    let clear = Vector4::new(1.0, 1.0, 1.0, 0.0);
+    assert_pixels(&image::decode(&top_down).unwrap(), 2, 1, &[GREEN, clear]);
+    assert_truncations_fail(&top_down);
+
+    // run-length encoded: a run of two pixels, then one literal pixel
+    let mut rle = tga_header(10, 3, 1, 24, 0x20);
+    rle.extend_from_slice(&[0x81, 0, 0, 255, 0x00, 255, 0, 0]);
+    assert_pixels(&image::decode(&rle).unwrap(), 3, 1, &[RED, RED, BLUE]);
+    assert_truncations_fail(&rle);
+}
+
+#[test]
+fn malformed_tga_is_rejected() {
+    assert!(matches!(image::decode_tga(&tga_header(2, 1, 1, 24, 0)[..10]), Err(ImageError::UnexpectedEnd)));
+    assert!(matches!(image::decode_tga(&tga_header(2, 0, 1, 24, 0)), Err(ImageError::InvalidHeader(_))));
+    assert!(matches!(image::decode_tga(&tga_header(2, 1, 1, 8, 0)), Err(ImageError::InvalidHeader(_))));
+    assert!(matches!(image::decode_tga(&tga_header(0, 1, 1, 24, 0)), Err(ImageError::InvalidHeader(_))));
+    // color mapped without a color map
+    assert!(matches!(image::decode_tga(&tga_header(1, 1, 1, 8, 0)), Err(ImageError::InvalidHeader(_))));
+
+    let mut long_run = tga_header(10, 2, 1, 24, 0);
+    long_run.extend_from_slice(&[0x82, 0, 0, 255]);
+    assert!(matches!(image::decode(&long_run), Err(ImageError::InvalidData(_))));
+}
+
+/// BMP with a 40 byte info header and `rows` of pixel data, padding included.
+fn bmp(width: i32, height: i32, bits: u16, rows: &[u8]) -> Vec<u8> {
+    let mut bytes = b"BM".to_vec();
+    bytes.extend_from_slice(&(54 + rows.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend_from_slice(&54u32.to_le_bytes());
+    bytes.extend_from_slice(&40u32.to_le_bytes());
+    bytes.extend_from_slice(&width.to_le_bytes());
+    bytes.extend_from_slice(&height.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&bits.to_le_bytes());
+    // no compression, then sizes and palette counts nobody reads
+    bytes.extend_from_slice(&[0; 24]);
+    bytes.extend_from_slice(rows);
+    bytes
+}
+
+#[test]
+fn bmp_decodes() {
+    // 24 bits in BGR order, rows padded to 4 bytes and stored bottom to top
+    let bottom_up = bmp(1, 2, 24, &[255, 0, 0, 0, 0, 0, 255, 0]);
+    assert_pixels(&image::decode(&bottom_up).unwrap(), 1, 2, &[RED, BLUE]);
+    assert_truncations_fail(&bottom_up);
+
+    // a negative height stores rows top to bottom, the fourth byte is unused without masks
+    let top_down = bmp(2, -1, 32, &[0, 255, 0, 0, 255, 255, 255, 7]);
+    assert_pixels(&image::decode(&top_down).unwrap(), 2, 1, &[GREEN, WHITE]);
+    assert_truncations_fail(&top_down);
+}
+
+#[test]
+fn malformed_bmp_is_rejected() {
+    assert!(matches!(image::decode(b"BM\x00\x00"), Err(ImageError::UnexpectedEnd)));
+    assert!(matches!(image::decode(&bmp(0, 1, 24, &[])), Err(ImageError::InvalidHeader(_))));
+    assert!(matches!(image::decode(&bmp(1, 0, 24, &[])), Err(ImageError::InvalidHeader(_))));
+    assert!(matches!(image::decode(&bmp(1, 1, 8, &[0, 0, 0, 0])), Err(ImageError::Unsupported(_))));
+
+    let mut small_header = bmp(1, 1, 24, &[0, 0, 0, 0]);
+    small_header[14] = 20;
+    assert!(matches!(image::decode(&small_header), Err(ImageError::InvalidHeader(_))));
+
+    let mut past_the_end = bmp(1, 1, 24, &[0, 0, 0, 0]);
+    past_the_end[10] = 200;
+    assert!(matches!(image::decode(&past_the_end), Err(ImageError::UnexpectedEnd)));
+}
+
+#[test]
+fn unknown_data_is_rejected() {
+    assert!(matches!(image::decode(b""), Err(ImageError::UnknownFormat)));
+    assert!(matches!(image::decode(b"GIF89a"), Err(ImageError::UnknownFormat)));
+}
+
+#[cfg(feature = "png")]
+#[test]
+fn framebuffers_round_trip_through_png() {
+    use cpu_renderer::color::Color;
+    use cpu_renderer::framebuffer::Framebuffer;
+    use cpu_renderer::vector::Vector3;
+
+    let mut buffer = Framebuffer::new(5, 3);
+    buffer.clear(Color::rgb(0.2, 0.4, 0.6));
+    buffer.set_color(0, 0, Vector3::new(1.0, 0.0, 0.0), 0.5);
+    buffer.set_color(4, 2, Vector3::new(0.0, 0.5, 1.0), 0.5);
+    buffer.finish_rendering();
+
+    let path = std::env::temp_dir().join(format!("cpu_renderer_round_trip_{}.png", std::process::id()));
+    buffer.save_png(&path).unwrap();
+    let loaded = image::load(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    // 8 bits per channel on both sides, so nothing is lost
+    let expected = buffer.to_image();
+    assert_pixels(&loaded.unwrap(), 5, 3, &expected.pixels);
+    assert_eq!(expected.pixel(0, 0), RED);
+
+    let mut encoded = Vec::new();
+    image::encode_png(&expected, &mut encoded).unwrap();
+    assert_pixels(&image::decode(&encoded).unwrap(), 5, 3, &expected.pixels);
+    // the 12 byte end chunk is optional to the decoder
+    assert_truncations_fail(&encoded[..encoded.len() - 12]);
+}
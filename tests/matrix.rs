@@ -0,0 +1,378 @@
+use cpu_renderer::approx::{ApproxEq, Tolerance};
+use cpu_renderer::matrix::{Matrix3, Matrix4, Transform};
+use cpu_renderer::scalar::{consts, Scalar};
+use cpu_renderer::vector::{Quaternion, Vector3, Vector4};
+
+mod common;
+
+use common::{Rng, EPSILON, LOOSE};
+
+const CASES: usize = 1000;
+
+fn matrix_close(a: &Matrix4, b: &Matrix4, epsilon: Scalar) -> bool {
+    a.approx_eq(b, Tolerance::absolute(epsilon))
+}
+
+fn vector_close(a: &Vector4, b: &Vector4, epsilon: Scalar) -> bool {
+    a.approx_eq(b, Tolerance::absolute(epsilon))
+}
+
+fn vector3_close(a: Vector3, b: Vector3, epsilon: Scalar) -> bool {
+    a.approx_eq(&b, Tolerance::absolute(epsilon))
+}
+
+fn rotation_close(a: Quaternion, b: Quaternion, epsilon: Scalar) -> bool {
+    // q and -q are the same rotation
+    (a.dot(b).abs() - 1.0).abs() <= epsilon
+}
+
+fn close(a: Scalar, b: Scalar, epsilon: Scalar) -> bool {
+    (a - b).abs() <= epsilon * (1.0 + a.abs().max(b.abs()))
+}
+
+#[test]
+fn rows_and_columns_follow_the_element_names() {
+    let m = Matrix4 { m01: 2.0, m13: 3.0, ..Matrix4::identity() };
+    assert!(vector_close(&m.as_rows()[0], &Vector4::new(1.0, 2.0, 0.0, 0.0), 0.0));
+    assert!(vector_close(&m.as_columns()[3], &Vector4::new(0.0, 3.0, 0.0, 1.0), 0.0));
+    assert_eq!(Matrix4::from_rows(m.as_rows()), m);
+    assert_eq!(Matrix4::from_columns(m.as_columns()), m);
+    assert_eq!(Matrix4::from_rows(m.as_columns()), m.transpose());
+}
+
+#[test]
+fn multiplication_composes_right_to_left() {
+    let scale = Matrix4 { m00: 2.0, m11: 2.0, m22: 2.0, ..Matrix4::identity() };
+    let translate = Matrix4 { m03: 1.0, m13: 1.0, m23: 1.0, ..Matrix4::identity() };
+    let point = Vector4::new(0.0, 0.0, 1.0, 1.0);
+    assert!(vector_close(&(scale * translate * point), &Vector4::new(2.0, 2.0, 4.0, 1.0), EPSILON));
+    assert!(vector_close(&(translate * scale * point), &Vector4::new(1.0, 1.0, 3.0, 1.0), EPSILON));
+}
+
+#[test]
+fn identity_is_neutral() {
+    let mut rng = Rng(1);
+    for _ in 0..CASES {
+        let m = rng.matrix();
+        assert_eq!(Matrix4::identity() * m, m);
+        assert_eq!(m * Matrix4::identity(), m);
+    }
+}
+
+#[test]
+fn multiplication_is_associative() {
+    let mut rng = Rng(2);
+    for _ in 0..CASES {
+        let (a, b, c) = (rng.matrix(), rng.matrix(), rng.matrix());
+        assert!(matrix_close(&((a * b) * c), &(a * (b * c)), EPSILON));
+        let v = rng.vector4();
+        assert!(vector_close(&((a * b) * v), &(a * (b * v)), EPSILON));
+    }
+}
+
+#[test]
+fn multiplication_distributes_over_addition() {
+    let mut rng = Rng(3);
+    for _ in 0..CASES {
+        let (a, b, c) = (rng.matrix(), rng.matrix(), rng.matrix());
+        assert!(matrix_close(&(a * (b + c)), &(a * b + a * c), EPSILON));
+        assert!(matrix_close(&((a - b) * c), &(a * c - b * c), EPSILON));
+    }
+}
+
+#[test]
+fn transpose_reverses_products() {
+    let mut rng = Rng(4);
+    for _ in 0..CASES {
+        let (a, b) = (rng.matrix(), rng.matrix());
+        assert_eq!(a.transpose().transpose(), a);
+        assert!(matrix_close(&(a * b).transpose(), &(b.transpose() * a.transpose()), EPSILON));
+    }
+}
+
+#[test]
+fn determinant_is_multiplicative() {
+    let mut rng = Rng(5);
+    for _ in 0..CASES {
+        let (a, b) = (rng.matrix(), rng.matrix());
+        assert!(close((a * b).determinant(), a.determinant() * b.determinant(), EPSILON));
+        assert!(close(a.transpose().determinant(), a.determinant(), EPSILON));
+        assert!(close((a * 2.0).determinant(), a.determinant() * 16.0, EPSILON));
+    }
+    assert_eq!(Matrix4::identity().determinant(), 1.0);
+}
+
+#[test]
+fn inverse_undoes_the_matrix() {
+    let mut rng = Rng(6);
+    for _ in 0..CASES {
+        let m = rng.matrix();
+        if m.determinant().abs() < 1e-3 {
+            continue;
+        }
+        let inverse = m.inverse().unwrap();
+        assert!(matrix_close(&(m * inverse), &Matrix4::identity(), LOOSE));
+        assert!(matrix_close(&(inverse * m), &Matrix4::identity(), LOOSE));
+    }
+}
+
+#[test]
+fn singular_matrices_have_no_inverse() {
+    let mut rng = Rng(7);
+    // small integers keep every product exact, so the determinants are exactly zero
+    let mut row = || {
+        let mut next = || (rng.next() * 8.0).round();
+        Vector4::new(next(), next(), next(), next())
+    };
+    for _ in 0..CASES {
+        let (r0, r1, r2) = (row(), row(), row());
+        // a repeated row, a multiple of another and a combination of two
+        for &last in [r0, r1 * -3.0, r0 * 2.0 + r2].iter() {
+            let m = Matrix4::from_rows([r0, r1, r2, last]);
+            assert_eq!(m.determinant(), 0.0);
+            assert!(m.inverse().is_none(), "{:?}", m);
+            assert!(m.transpose().inverse().is_none(), "{:?}", m);
+        }
+    }
+    assert!(Matrix4::default().inverse().is_none());
+    assert!(Matrix4::default().affine_inverse().is_none());
+}
+
+#[test]
+fn affine_inverse_matches_general_inverse() {
+    let mut rng = Rng(8);
+    for _ in 0..CASES {
+        let m = rng.affine();
+        if m.determinant().abs() < 1e-3 {
+            continue;
+        }
+        assert!(matrix_close(&m.affine_inverse().unwrap(), &m.inverse().unwrap(), LOOSE));
+    }
+}
+
+#[test]
+fn points_are_translated_and_directions_are_not() {
+    let mut rng = Rng(9);
+    for _ in 0..CASES {
+        let m = rng.affine();
+        let v = rng.vector3();
+        let point = m * Vector4::new(v.x, v.y, v.z, 1.0);
+        let direction = m * Vector4::new(v.x, v.y, v.z, 0.0);
+        let p = m * v;
+        let d = m.transform_direction(v);
+        assert!(vector_close(&Vector4::new(p.x, p.y, p.z, 1.0), &point, EPSILON));
+        assert!(vector_close(&Vector4::new(d.x, d.y, d.z, 0.0), &direction, EPSILON));
+    }
+}
+
+#[test]
+fn points_are_divided_by_w() {
+    let projection = Matrix4 { m32: 1.0, m33: 0.0, ..Matrix4::identity() };
+    let p = projection.transform_point(Vector3::new(2.0, 4.0, 2.0));
+    assert!(vector_close(&Vector4::new(p.x, p.y, p.z, 0.0), &Vector4::new(1.0, 2.0, 1.0, 0.0), EPSILON));
+}
+
+#[test]
+fn projections_map_near_and_far_to_depth_bounds() {
+    let (near, far) = (0.5, 40.0);
+    let (fov_y, aspect) = (1.2, 1.5);
+    // half the height and width of the view volume one unit in front of the camera
+    let h = (fov_y * 0.5 as Scalar).tan();
+    let w = h * aspect;
+    let projections = [
+        Matrix4::perspective(fov_y, aspect, near, far),
+        Matrix4::frustum(-w * near, w * near, -h * near, h * near, near, far),
+    ];
+    for projection in projections.iter() {
+        for &(z, depth) in [(near, 0.0), (far, 1.0)].iter() {
+            // the corners of the view volume on the plane land on the corners of clip space
+            for &(x, y) in [(1.0, 1.0), (-1.0, 1.0), (1.0, -1.0), (-1.0, -1.0), (0.0, 0.0)].iter() {
+                let p = projection.transform_point(Vector3::new(x * w * z, y * h * z, z));
+                assert!(vector3_close(p, Vector3::new(x, y, depth), EPSILON), "{:?} at {:?}", p, (x, y, z));
+            }
+        }
+        // depth grows monotonically in between
+        let depth = |z: Scalar| projection.transform_point(Vector3::new(0.0, 0.0, z)).z;
+        assert!(depth(1.0) < depth(2.0) && depth(2.0) < depth(20.0));
+    }
+
+    let orthographic = Matrix4::orthographic(-2.0, 4.0, -1.0, 3.0, near, far);
+    assert!(vector3_close(orthographic * Vector3::new(-2.0, -1.0, near), Vector3::new(-1.0, -1.0, 0.0), EPSILON));
+    assert!(vector3_close(orthographic * Vector3::new(4.0, 3.0, far), Vector3::new(1.0, 1.0, 1.0), EPSILON));
+
+    let depth = |projection: Matrix4, z: Scalar| projection.transform_point(Vector3::new(0.0, 0.0, z)).z;
+    let reverse = Matrix4::perspective_reverse_z(fov_y, aspect, near, far);
+    assert!(close(depth(reverse, near), 1.0, EPSILON));
+    assert!(close(depth(reverse, far), 0.0, EPSILON));
+    let infinite = Matrix4::perspective_infinite(fov_y, aspect, near);
+    assert!(close(depth(infinite, near), 0.0, EPSILON));
+    assert!(depth(infinite, 1e6) < 1.0 && close(depth(infinite, 1e6), 1.0, LOOSE));
+    let infinite_reverse = Matrix4::perspective_infinite_reverse_z(fov_y, aspect, near);
+    assert!(close(depth(infinite_reverse, near), 1.0, EPSILON));
+    assert!(depth(infinite_reverse, 1e6) > 0.0 && close(depth(infinite_reverse, 1e6), 0.0, LOOSE));
+}
+
+#[test]
+fn viewport_maps_clip_space_to_the_screen() {
+    let viewport = Matrix4::viewport();
+    // +y up becomes rows going down, depth is kept
+    assert_eq!(viewport * Vector3::new(-1.0, 1.0, 0.25), Vector3::new(0.0, 0.0, 0.25));
+    assert_eq!(viewport * Vector3::new(1.0, -1.0, 0.75), Vector3::new(1.0, 1.0, 0.75));
+    assert_eq!(viewport * Vector3::new(0.0, 0.0, 0.5), Vector3::new(0.5, 0.5, 0.5));
+}
+
+#[test]
+fn look_at_moves_the_eye_to_the_origin() {
+    let mut rng = Rng(15);
+    let up = Vector3::new(0.0, 1.0, 0.0);
+    for _ in 0..CASES {
+        let eye = rng.vector3() * 10.0;
+        let target = rng.vector3() * 10.0;
+        let forward = target - eye;
+        // looking straight along up has no defined orientation
+        if forward.length() < 1e-2 || forward.normalize().cross(up).length() < 1e-2 {
+            continue;
+        }
+        let view = Matrix4::look_at(eye, target, up);
+        assert!(vector3_close(view * eye, Vector3::zero(), LOOSE));
+        // the target lies ahead on +z, points above the eye stay above it
+        assert!(vector3_close(view * target, Vector3::new(0.0, 0.0, forward.length()), LOOSE));
+        let above = view * (eye + up);
+        assert!(above.y > 0.0 && above.x.abs() < LOOSE, "{:?}", above);
+        // and the view is a rigid motion
+        assert!(close(view.determinant(), 1.0, LOOSE));
+    }
+}
+
+#[test]
+fn quaternions_match_rotation_matrices() {
+    let mut rng = Rng(10);
+    for _ in 0..CASES {
+        let (axis, angle) = (rng.vector3(), rng.next() * 4.0);
+        let q = Quaternion::from_axis_angle(axis, angle);
+        let m = Matrix4::rotation(axis, angle);
+        assert!(matrix_close(&q.to_matrix4(), &m, EPSILON));
+        let v = rng.vector3();
+        assert!(vector3_close(q * v, m * v, EPSILON));
+    }
+    let (x, y, z) = (0.3, -1.2, 2.0);
+    let euler = Matrix4::rotation_y(y) * Matrix4::rotation_x(x) * Matrix4::rotation_z(z);
+    assert!(matrix_close(&Quaternion::from_euler(x, y, z).to_matrix4(), &euler, EPSILON));
+}
+
+#[test]
+fn quaternion_products_compose_rotations() {
+    let mut rng = Rng(11);
+    for _ in 0..CASES {
+        let (a, b) = (rng.rotation(), rng.rotation());
+        assert!(matrix_close(&(a * b).to_matrix4(), &(a.to_matrix4() * b.to_matrix4()), EPSILON));
+        assert!(rotation_close(a * a.conjugate(), Quaternion::identity(), EPSILON));
+        assert!(rotation_close(a.inverse(), a.conjugate(), EPSILON));
+    }
+}
+
+#[test]
+fn conversions_round_trip() {
+    let mut rng = Rng(12);
+    for _ in 0..CASES {
+        let q = rng.rotation();
+        let (axis, angle) = q.to_axis_angle();
+        assert!(rotation_close(Quaternion::from_axis_angle(axis, angle), q, EPSILON));
+        let euler = q.to_euler();
+        assert!(rotation_close(Quaternion::from_euler(euler.x, euler.y, euler.z), q, EPSILON));
+    }
+    let locked = Quaternion::from_euler(consts::FRAC_PI_2, 0.5, 0.25);
+    let euler = locked.to_euler();
+    assert!(rotation_close(Quaternion::from_euler(euler.x, euler.y, euler.z), locked, LOOSE));
+}
+
+#[test]
+fn slerp_turns_at_constant_speed() {
+    let mut rng = Rng(13);
+    for _ in 0..CASES {
+        let (a, b) = (rng.rotation(), rng.rotation());
+        assert!(rotation_close(a.slerp(b, 0.0), a, EPSILON));
+        assert!(rotation_close(a.slerp(b, 1.0), b, EPSILON));
+        assert!(rotation_close(a.nlerp(b, 1.0), b, EPSILON));
+        let (t, u) = (rng.next().abs(), rng.next().abs());
+        let angle = |p: Quaternion, q: Quaternion| p.dot(q).abs().min(1.0).acos();
+        let total = angle(a, b);
+        assert!(close(angle(a.slerp(b, t), a.slerp(b, u)), total * (t - u).abs(), LOOSE));
+    }
+}
+
+#[test]
+fn squad_passes_through_the_keys() {
+    let mut rng = Rng(14);
+    for _ in 0..CASES {
+        let mut keys = [rng.rotation(), rng.rotation(), rng.rotation(), rng.rotation()];
+        for i in 1..keys.len() {
+            if keys[i].dot(keys[i - 1]) < 0.0 {
+                keys[i] = keys[i] * -1.0;
+            }
+        }
+        let a = Quaternion::squad_control(keys[0], keys[1], keys[2]);
+        let b = Quaternion::squad_control(keys[1], keys[2], keys[3]);
+        assert!(rotation_close(keys[1].squad(keys[2], a, b, 0.0), keys[1], EPSILON));
+        assert!(rotation_close(keys[1].squad(keys[2], a, b, 1.0), keys[2], EPSILON));
+        assert!(close(keys[1].squad(keys[2], a, b, rng.next().abs()).length(), 1.0, EPSILON));
+    }
+}
+
+#[test]
+fn transforms_compose_and_invert() {
+    let mut rng = Rng(15);
+    for _ in 0..CASES {
+        let transform = |rng: &mut Rng| {
+            let s = rng.next().abs() + 0.5;
+            Transform::identity()
+                .with_translation(rng.vector3())
+                .with_rotation(rng.rotation())
+                .with_scale(Vector3::new(s, s, s))
+        };
+        let (a, b) = (transform(&mut rng), transform(&mut rng));
+        let v = rng.vector3();
+        assert!(vector3_close(a * v, a.to_matrix4() * v, EPSILON));
+        assert!(matrix_close(&(a * b).to_matrix4(), &(a.to_matrix4() * b.to_matrix4()), EPSILON));
+        let inverse = a.inverse().unwrap();
+        assert!(matrix_close(&inverse.to_matrix4(), &a.to_matrix4().affine_inverse().unwrap(), LOOSE));
+        assert!(vector3_close(inverse * (a * v), v, LOOSE));
+    }
+    assert!(Transform::identity().with_scale(Vector3::new(1.0, 0.0, 1.0)).inverse().is_none());
+}
+
+#[test]
+fn matrix3_matches_the_linear_part() {
+    let mut rng = Rng(16);
+    for _ in 0..CASES {
+        let (a, b) = (rng.affine(), rng.affine());
+        let v = rng.vector3();
+        assert!(vector3_close(a.to_matrix3() * v, a.transform_direction(v), EPSILON));
+        assert!(matrix_close(&(a.to_matrix3() * b.to_matrix3()).to_matrix4(), &(a * b).to_matrix3().to_matrix4(), EPSILON));
+        assert!(close(a.to_matrix3().determinant(), a.determinant(), LOOSE));
+        assert_eq!(a.to_matrix3().transpose(), Matrix3::from_columns(a.to_matrix3().as_rows()));
+        if a.determinant().abs() < 1e-3 {
+            continue;
+        }
+        let inverse = a.to_matrix3().inverse().unwrap();
+        assert!(matrix_close(&inverse.to_matrix4(), &a.affine_inverse().unwrap().to_matrix3().to_matrix4(), LOOSE));
+    }
+    assert!(Matrix3::default().inverse().is_none());
+}
+
+#[test]
+fn normals_stay_perpendicular_under_non_uniform_scale() {
+    let mut rng = Rng(17);
+    for _ in 0..CASES {
+        let m = rng.rotation().to_matrix4() * Matrix4::scale(Vector3::new(3.0, 0.5, 1.0)) * rng.rotation().to_matrix4();
+        let (tangent, bitangent) = (rng.vector3(), rng.vector3());
+        let normal = tangent.cross(bitangent);
+        let transformed = m.normal_matrix().unwrap() * normal;
+        assert!(close(transformed * m.transform_direction(tangent), 0.0, LOOSE));
+        assert!(close(transformed * m.transform_direction(bitangent), 0.0, LOOSE));
+    }
+    // rotations leave normals alone
+    let rotation = Matrix4::rotation(Vector3::new(1.0, 2.0, 3.0), 0.7);
+    assert!(matrix_close(&rotation.normal_matrix().unwrap().to_matrix4(), &rotation, EPSILON));
+    assert!(Matrix4::scale(Vector3::new(1.0, 0.0, 1.0)).normal_matrix().is_none());
+}
@@ -0,0 +1,88 @@
+use cpu_renderer::procedural::{perlin, simplex, worley, Cellular, Checkerboard, Fbm, Noise, Worley};
+use cpu_renderer::scalar::Scalar;
+use cpu_renderer::texture::Texture;
+use cpu_renderer::vector::{Vector2, Vector3};
+
+mod common;
+
+use common::{Rng, EPSILON};
+
+/// Points spread over a few hundred lattice cells, negative coordinates included.
+fn points() -> Vec<Vector2> {
+    let mut rng = Rng(1);
+    (0..5000).map(|_| Vector2::new(rng.next() * 20.0, rng.next() * 20.0)).collect()
+}
+
+#[test]
+fn noise_is_deterministic() {
+    for &p in points().iter() {
+        for &noise in [Noise::Perlin, Noise::Simplex].iter() {
+            assert_eq!(noise.at(p, 7).to_bits(), noise.at(p, 7).to_bits());
+        }
+        assert_eq!(worley(p, 7), worley(p, 7));
+    }
+
+    let fbm = Fbm::new(Noise::Simplex, 4).with_seed(3);
+    let copy = fbm;
+    assert!(points().iter().all(|&p| fbm.sample(p) == copy.sample(p)));
+
+    // the seed picks another pattern
+    let differs = points().iter().filter(|&&p| perlin(p, 1) != perlin(p, 2)).count();
+    assert!(differs > points().len() / 2);
+}
+
+#[test]
+fn gradient_noise_stays_in_range() {
+    for &p in points().iter() {
+        let value = perlin(p, 0);
+        assert!((-1.0..=1.0).contains(&value), "perlin {} at {:?}", value, p);
+        let value = simplex(p, 0);
+        assert!((-1.0..=1.0).contains(&value), "simplex {} at {:?}", value, p);
+        let turbulence = Fbm::new(Noise::Perlin, 5).turbulence(p);
+        assert!((0.0..=1.0).contains(&turbulence), "turbulence {} at {:?}", turbulence, p);
+    }
+    // the lattice points themselves are zeros of perlin noise
+    assert_eq!(perlin(Vector2::new(3.0, -2.0), 5), 0.0);
+}
+
+#[test]
+fn worley_distances_are_ordered() {
+    for &p in points().iter() {
+        let (f1, f2) = worley(p, 0);
+        assert!(0.0 <= f1 && f1 <= f2, "F1 {} and F2 {} at {:?}", f1, f2, p);
+        // the feature point of the own cell is at most a diagonal away
+        assert!(f1 <= (2.0 as Scalar).sqrt());
+    }
+    let border = Worley::new(Cellular::Border, 4.0);
+    assert!(points().iter().all(|&p| border.sample(p).x >= 0.0));
+}
+
+#[test]
+fn noise_is_continuous_across_cells() {
+    // lattice cells join without seams, so noise textures can span any number of them
+    let mut rng = Rng(2);
+    for _ in 0..1000 {
+        let edge = Vector2::new((rng.next() * 10.0).round(), rng.next() * 10.0);
+        let (before, after) = (edge - Vector2::new(EPSILON, 0.0), edge + Vector2::new(EPSILON, 0.0));
+        assert!((perlin(before, 0) - perlin(after, 0)).abs() < 1e2 * EPSILON);
+        assert!((simplex(before, 0) - simplex(after, 0)).abs() < 1e2 * EPSILON);
+        assert!((worley(before, 0).0 - worley(after, 0).0).abs() < 1e2 * EPSILON);
+    }
+}
+
+#[test]
+fn checkerboard_tiles_the_unit_square() {
+    let (even, odd) = (Vector3::splat(1.0), Vector3::zero());
+    let board = Checkerboard::new(even, odd, 4.0);
+    assert_eq!(board.sample(Vector2::new(0.1, 0.1)), even);
+    assert_eq!(board.sample(Vector2::new(0.3, 0.1)), odd);
+    assert_eq!(board.sample(Vector2::new(0.3, 0.3)), even);
+    // repeats with every unit square, also towards negative coordinates
+    let mut rng = Rng(3);
+    for _ in 0..1000 {
+        let uv = Vector2::new(rng.next() * 0.5 + 0.5, rng.next() * 0.5 + 0.5);
+        for &(dx, dy) in [(1.0, 0.0), (0.0, 1.0), (-3.0, 2.0)].iter() {
+            assert_eq!(board.sample(uv + Vector2::new(dx, dy)), board.sample(uv), "{:?}", uv);
+        }
+    }
+}
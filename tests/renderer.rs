@@ -0,0 +1,275 @@
+use cpu_renderer::format::TexelFormat;
+use cpu_renderer::framebuffer::{Framebuffer, GBufferSample, Pick, Plane};
+use cpu_renderer::geometry::{Aabb, Frustum};
+use cpu_renderer::matrix::Matrix4;
+use cpu_renderer::renderer::Program;
+use cpu_renderer::scalar::Scalar;
+use cpu_renderer::target::TargetDescription;
+use cpu_renderer::texture::{ImageTexture, Texture};
+use cpu_renderer::vector::{Vector2, Vector3, Vector4};
+
+mod common;
+
+use common::Rng;
+
+// not multiples of the region size, so that the last regions are partial
+const WIDTH: usize = 97;
+const HEIGHT: usize = 61;
+const REGION: usize = 16;
+
+/// Vertices are given in screen space already.
+fn screen(v: Vector3, _: &Vector3) -> (Vector3, Scalar) {
+    (v, v.z)
+}
+
+fn flat(_: Vector3, _: Scalar, color: &Vector3) -> Vector3 {
+    *color
+}
+
+/// Colors, depths and ids of `triangles` drawn in order with flat colors and ids counting
+/// from one.
+fn render_flat(triangles: &[([Vector3; 3], Vector3)], depth_prepass: bool) -> (Vec<u32>, Vec<Scalar>, Vec<u32>) {
+    let mut buffer = Framebuffer::new(WIDTH, HEIGHT);
+    let mut program = Program::new(screen, flat, Vector3::zero(), WIDTH, HEIGHT, REGION, REGION);
+    program.set_depth_prepass(depth_prepass);
+    for (i, &([a, b, c], color)) in triangles.iter().enumerate() {
+        program.uniform = color;
+        program.object_id = i as u32 + 1;
+        program.enqueue_triangle(a, b, c);
+    }
+    common::render(&mut program, &mut buffer, REGION);
+
+    let pixels = || (0..HEIGHT).flat_map(|y| (0..WIDTH).map(move |x| (x, y)));
+    let depths = pixels().map(|(x, y)| buffer.depth(x, y)).collect();
+    let ids = pixels().map(|(x, y)| buffer.id(x, y)).collect();
+    (buffer.colors().to_vec(), depths, ids)
+}
+
+#[test]
+fn early_and_hierarchical_z_match_a_plain_depth_test() {
+    let mut rng = Rng(1);
+    let mut triangles = Vec::new();
+    for _ in 0..60 {
+        // spilling over the screen, with depths varying across and between triangles
+        let mut vertex = || Vector3::new(rng.next() * 0.7 + 0.5, rng.next() * 0.7 + 0.5, rng.next() * 0.4 + 0.5);
+        let corners = [vertex(), vertex(), vertex()];
+        let color = Vector3::new(rng.next(), rng.next(), rng.next()) * 0.5 + Vector3::splat(0.5);
+        triangles.push((corners, color));
+    }
+    // a far screen covering quad at the end is mostly rejected by whole tiles
+    let far = |x: Scalar, y: Scalar| Vector3::new(x, y, 0.95);
+    triangles.push(([far(-1.0, -1.0), far(3.0, -1.0), far(-1.0, 3.0)], Vector3::splat(0.2)));
+
+    // the plain depth test: every triangle drawn alone, so that no earlier triangle can
+    // reject anything, then merged keeping the nearest fragment and the first one of ties
+    let mut colors = vec![0; WIDTH * HEIGHT];
+    let mut depths = vec![Scalar::INFINITY; WIDTH * HEIGHT];
+    let mut ids = vec![0; WIDTH * HEIGHT];
+    for (i, triangle) in triangles.iter().enumerate() {
+        let (alone_colors, alone_depths, _) = render_flat(&[*triangle], false);
+        for index in 0..WIDTH * HEIGHT {
+            if alone_depths[index] < depths[index] {
+                colors[index] = alone_colors[index];
+                depths[index] = alone_depths[index];
+                ids[index] = i as u32 + 1;
+            }
+        }
+    }
+    assert!(ids.iter().all(|&id| id != 0), "scene should cover the screen");
+
+    for &depth_prepass in [false, true].iter() {
+        let (scene_colors, scene_depths, scene_ids) = render_flat(&triangles, depth_prepass);
+        assert!(scene_colors == colors, "colors differ, depth pre-pass {}", depth_prepass);
+        assert!(scene_depths.iter().zip(depths.iter()).all(|(a, b)| a.to_bits() == b.to_bits()), "depths differ, depth pre-pass {}", depth_prepass);
+        assert!(scene_ids == ids, "ids differ, depth pre-pass {}", depth_prepass);
+    }
+}
+
+fn geometry(_: Vector3, _: Scalar, color: &Vector3) -> GBufferSample {
+    GBufferSample { albedo: *color, ..GBufferSample::default() }
+}
+
+fn unlit(_: Vector3, sample: &GBufferSample, _: &Vector3) -> Vector3 {
+    sample.albedo
+}
+
+#[test]
+fn deferred_shading_writes_the_gbuffer() {
+    let mut buffer = Framebuffer::with_gbuffer(WIDTH, HEIGHT);
+    let mut program = Program::deferred(screen, geometry, unlit, Vector3::new(1.0, 0.0, 0.0), WIDTH, HEIGHT, REGION, REGION);
+    program.enqueue_triangle(Vector3::new(-1.0, -1.0, 0.5), Vector3::new(3.0, -1.0, 0.5), Vector3::new(-1.0, 3.0, 0.5));
+    common::render(&mut program, &mut buffer, REGION);
+    assert_eq!(buffer.gbuffer(WIDTH / 2, HEIGHT / 2).albedo, Vector3::new(1.0, 0.0, 0.0));
+    assert_eq!(buffer.colors()[0], 0xff0000);
+}
+
+#[test]
+#[should_panic(expected = "Framebuffer::with_gbuffer")]
+fn deferred_shading_needs_a_gbuffer() {
+    let mut buffer = Framebuffer::new(WIDTH, HEIGHT);
+    let mut program = Program::deferred(screen, geometry, unlit, Vector3::new(1.0, 0.0, 0.0), WIDTH, HEIGHT, REGION, REGION);
+    program.enqueue_triangle(Vector3::new(-1.0, -1.0, 0.5), Vector3::new(3.0, -1.0, 0.5), Vector3::new(-1.0, 3.0, 0.5));
+    common::render(&mut program, &mut buffer, REGION);
+}
+
+fn two_targets(_: Vector3, depth: Scalar, color: &Vector3) -> [Vector4; 2] {
+    [Vector4::new(color.x, color.y, color.z, 1.0), Vector4::new(depth, 0.0, 0.0, 1.0)]
+}
+
+#[test]
+fn outputs_go_to_the_targets_in_order() {
+    let description = TargetDescription::new(TexelFormat::Rgba32F);
+    let mut buffer = Framebuffer::with_targets(WIDTH, HEIGHT, &[description, description]);
+    let mut program = Program::with_targets(screen, two_targets, Vector3::new(0.0, 1.0, 0.0), WIDTH, HEIGHT, REGION, REGION);
+    program.enqueue_triangle(Vector3::new(-1.0, -1.0, 0.5), Vector3::new(3.0, -1.0, 0.5), Vector3::new(-1.0, 3.0, 0.5));
+    common::render(&mut program, &mut buffer, REGION);
+    assert_eq!(buffer.target_value(0, 3, 4), Vector4::new(0.0, 1.0, 0.0, 1.0));
+    assert_eq!(buffer.target_value(1, 3, 4), Vector4::new(0.5, 0.0, 0.0, 1.0));
+}
+
+#[test]
+#[should_panic(expected = "program writes 2 render targets, the framebuffer has 1")]
+fn programs_need_a_target_per_output() {
+    let mut buffer = Framebuffer::with_targets(WIDTH, HEIGHT, &[TargetDescription::new(TexelFormat::Rgba32F)]);
+    let mut program = Program::with_targets(screen, two_targets, Vector3::new(0.0, 1.0, 0.0), WIDTH, HEIGHT, REGION, REGION);
+    program.enqueue_triangle(Vector3::new(-1.0, -1.0, 0.5), Vector3::new(3.0, -1.0, 0.5), Vector3::new(-1.0, 3.0, 0.5));
+    common::render(&mut program, &mut buffer, REGION);
+}
+
+#[test]
+fn picking_finds_the_nearest_object() {
+    let mut buffer = Framebuffer::new(WIDTH, HEIGHT);
+    let mut program = Program::new(screen, flat, Vector3::splat(1.0), WIDTH, HEIGHT, REGION, REGION);
+    // left half far, a nearer triangle over the top left corner
+    program.object_id = 1;
+    program.enqueue_triangle(Vector3::new(0.0, -1.0, 0.75), Vector3::new(0.5, -1.0, 0.75), Vector3::new(0.0, 3.0, 0.75));
+    program.enqueue_triangle(Vector3::new(0.5, -1.0, 0.75), Vector3::new(0.5, 3.0, 0.75), Vector3::new(0.0, 3.0, 0.75));
+    program.object_id = 2;
+    program.enqueue_triangle(Vector3::new(0.0, 0.0, 0.25), Vector3::new(0.25, 0.0, 0.25), Vector3::new(0.0, 0.25, 0.25));
+    common::render(&mut program, &mut buffer, REGION);
+
+    assert_eq!(buffer.pick(1, 1), Some(Pick { id: 2, depth: 0.25 }));
+    assert_eq!(buffer.pick(30, 50), Some(Pick { id: 1, depth: 0.75 }));
+    // nothing drawn on the right, nor outside of the framebuffer
+    assert_eq!(buffer.pick(WIDTH - 1, 0), None);
+    assert_eq!(buffer.pick(WIDTH, 0), None);
+
+    assert_eq!(buffer.pick_rect(5, 5, 40, 40), Some(Pick { id: 2, depth: 0.25 }));
+    assert_eq!(buffer.pick_rect(30, 30, 40, 40), Some(Pick { id: 1, depth: 0.75 }));
+    // clipped to the framebuffer
+    assert_eq!(buffer.pick_rect(40, 50, 1000, 1000), Some(Pick { id: 1, depth: 0.75 }));
+    assert_eq!(buffer.pick_rect(60, 0, 1000, 1000), None);
+    assert_eq!(buffer.pick_rect(10, 10, 10, 40), None);
+}
+
+#[test]
+fn objects_outside_of_the_frustum_are_culled() {
+    // bounds say where objects are, the triangles cover the left and the right half of the
+    // screen either way, so that only culling can keep them off it
+    let left = [[Vector3::new(0.0, -1.0, 0.5), Vector3::new(0.5, -1.0, 0.5), Vector3::new(0.0, 3.0, 0.5)]];
+    let right = [[Vector3::new(0.5, -1.0, 0.5), Vector3::new(1.0, -1.0, 0.5), Vector3::new(1.0, 3.0, 0.5)]];
+    let inside = Aabb::new(Vector3::splat(0.25), Vector3::splat(0.75));
+    let outside = Aabb::new(Vector3::new(2.0, 0.0, 0.0), Vector3::new(3.0, 1.0, 1.0));
+
+    for &culling in [true, false].iter() {
+        let mut buffer = Framebuffer::new(WIDTH, HEIGHT);
+        let mut program = Program::new(screen, flat, Vector3::splat(1.0), WIDTH, HEIGHT, REGION, REGION);
+        if culling {
+            program.set_frustum(Some(Frustum::from_matrix(&Matrix4::orthographic(0.0, 1.0, 0.0, 1.0, 0.0, 1.0))));
+        }
+        program.object_id = 1;
+        assert!(program.enqueue_object(&inside, left.iter().cloned()));
+        program.object_id = 2;
+        assert_eq!(program.enqueue_object(&outside, right.iter().cloned()), !culling);
+        common::render(&mut program, &mut buffer, REGION);
+
+        assert_eq!(buffer.pick(5, 30).map(|pick| pick.id), Some(1));
+        assert_eq!(buffer.pick(WIDTH - 5, 30).map(|pick| pick.id), if culling { None } else { Some(2) });
+    }
+}
+
+/// Corner colors as attributes, one vertex per channel.
+fn corners(v: (Vector3, Vector3), _: &Vector3) -> (Vector3, Vector3) {
+    v
+}
+
+fn interpolated(_: Vector3, color: Vector3, _: &Vector3) -> [Vector4; 1] {
+    [Vector4::new(color.x, color.y, color.z, 1.0)]
+}
+
+#[test]
+fn attributes_are_weighted_by_their_own_vertices() {
+    let mut buffer = Framebuffer::with_targets(WIDTH, HEIGHT, &[TargetDescription::new(TexelFormat::Rgba32F)]);
+    let mut program = Program::with_targets(corners, interpolated, Vector3::zero(), WIDTH, HEIGHT, REGION, REGION);
+    program.enqueue_triangle(
+        (Vector3::new(0.0, 0.0, 0.5), Vector3::new(1.0, 0.0, 0.0)),
+        (Vector3::new(1.0, 0.0, 0.5), Vector3::new(0.0, 1.0, 0.0)),
+        (Vector3::new(0.0, 1.0, 0.5), Vector3::new(0.0, 0.0, 1.0)),
+    );
+    common::render(&mut program, &mut buffer, REGION);
+
+    // pixels sit at x / width and y / height, where the weights of the second and the third
+    // vertex are those coordinates
+    for &(x, y) in [(0, 0), (1, 2), (30, 5), (4, 40), (40, 20)].iter() {
+        let (px, py) = (x as Scalar / WIDTH as Scalar, y as Scalar / HEIGHT as Scalar);
+        let expected = Vector4::new(1.0 - px - py, px, py, 1.0);
+        let color = buffer.target_value(0, x, y);
+        assert!((color - expected).length() < 1e-5, "{:?} != {:?} at {:?}", color, expected, (x, y));
+    }
+}
+
+/// Screen position as the output, so that a target records where every pixel sits.
+fn position(point: Vector3, _: Scalar, _: &Vector3) -> [Vector4; 1] {
+    [Vector4::new(point.x, point.y, 0.0, 1.0)]
+}
+
+/// Color, depth and target plane of the first pass.
+type Planes = (ImageTexture, ImageTexture, ImageTexture);
+
+fn screen_planes(v: Vector3, _: &Planes) -> (Vector3, Scalar) {
+    (v, v.z)
+}
+
+/// Second pass reading the planes of the first one, each at the center of the pixel.
+fn planes(point: Vector3, _: Scalar, textures: &Planes) -> [Vector4; 3] {
+    let uv = Vector2::new(point.x + 0.5 / WIDTH as Scalar, point.y + 0.5 / HEIGHT as Scalar);
+    let (color, depth, target) = (textures.0.sample(uv), textures.1.sample(uv), textures.2.sample(uv));
+    [Vector4::new(color.x, color.y, color.z, 1.0), Vector4::new(depth.x, 0.0, 0.0, 1.0), Vector4::new(target.x, target.y, 0.0, 1.0)]
+}
+
+#[test]
+fn planes_are_sampled_in_a_later_pass() {
+    let description = TargetDescription::new(TexelFormat::Rgba32F);
+    let mut buffer = Framebuffer::with_targets(WIDTH, HEIGHT, &[description]);
+    let mut background = Program::with_targets(screen, position, Vector3::zero(), WIDTH, HEIGHT, REGION, REGION);
+    background.enqueue_triangle(Vector3::new(-1.0, -1.0, 0.75), Vector3::new(3.0, -1.0, 0.75), Vector3::new(-1.0, 3.0, 0.75));
+    common::render(&mut background, &mut buffer, REGION);
+    // red over the upper half of the frame, the rows with y < 0.5
+    let mut upper = Program::new(screen, flat, Vector3::new(1.0, 0.0, 0.0), WIDTH, HEIGHT, REGION, REGION);
+    upper.enqueue_triangle(Vector3::new(-1.0, -1.0, 0.25), Vector3::new(2.0, -1.0, 0.25), Vector3::new(2.0, 0.5, 0.25));
+    upper.enqueue_triangle(Vector3::new(-1.0, -1.0, 0.25), Vector3::new(2.0, 0.5, 0.25), Vector3::new(-1.0, 0.5, 0.25));
+    common::render(&mut upper, &mut buffer, REGION);
+
+    let textures = (buffer.texture(Plane::Color), buffer.texture(Plane::Depth), buffer.texture(Plane::Target(0)));
+    // row 0 of every plane is at v = 0
+    assert_eq!(textures.0.sample(Vector2::new(0.0, 0.0)), Vector3::new(1.0, 0.0, 0.0));
+    assert_eq!(textures.0.sample(Vector2::new(0.0, 0.99)), Vector3::zero());
+    assert_eq!(textures.1.sample(Vector2::new(0.0, 0.0)).x, 0.25);
+    assert_eq!(textures.1.sample(Vector2::new(0.0, 0.99)).x, 0.75);
+
+    let mut resampled = Framebuffer::with_targets(WIDTH, HEIGHT, &[description, description, description]);
+    let mut program = Program::with_targets(screen_planes, planes, textures, WIDTH, HEIGHT, REGION, REGION);
+    program.enqueue_triangle(Vector3::new(-1.0, -1.0, 0.5), Vector3::new(3.0, -1.0, 0.5), Vector3::new(-1.0, 3.0, 0.5));
+    common::render(&mut program, &mut resampled, REGION);
+
+    for &(x, y) in [(0, 0), (3, 4), (WIDTH - 1, HEIGHT / 2), (50, HEIGHT / 2 + 1), (WIDTH - 1, HEIGHT - 1)].iter() {
+        let upper = (y as Scalar) < HEIGHT as Scalar * 0.5;
+        let red = if upper { 1.0 } else { 0.0 };
+        assert_eq!(resampled.target_value(0, x, y), Vector4::new(red, 0.0, 0.0, 1.0), "color at {:?}", (x, y));
+        let depth = if upper { 0.25 } else { 0.75 };
+        assert_eq!(resampled.target_value(1, x, y), Vector4::new(depth, 0.0, 0.0, 1.0), "depth at {:?}", (x, y));
+        let expected = Vector4::new(x as Scalar / WIDTH as Scalar, y as Scalar / HEIGHT as Scalar, 0.0, 1.0);
+        let position = resampled.target_value(2, x, y);
+        assert!((position - expected).length() < 1e-5, "{:?} != {:?} at {:?}", position, expected, (x, y));
+    }
+}
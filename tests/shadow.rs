@@ -0,0 +1,94 @@
+use cpu_renderer::format::{TexelBuffer, TexelFormat};
+use cpu_renderer::scalar::Scalar;
+use cpu_renderer::shadow::{Compare, DepthTexture, Kernel};
+use cpu_renderer::texture::{Filter, Sampler, Wrap};
+use cpu_renderer::vector::{Vector2, Vector3, Vector4};
+
+/// 4x4 shadow map with an occluder at depth 0.2 over the left half, nothing but the far
+/// plane over the right half.
+fn half_occluded() -> DepthTexture {
+    let depths = (0..16).map(|i| if i % 4 < 2 { 0.2 } else { 1.0 }).collect();
+    DepthTexture::new(4, 4, depths)
+}
+
+#[test]
+fn kernels_agree_away_from_edges() {
+    let kernels = [Kernel::Single, Kernel::Grid { radius: 1 }, Kernel::Poisson { samples: 16, radius: 0.4 }];
+    for &filter in [Filter::Nearest, Filter::Bilinear].iter() {
+        for &kernel in kernels.iter() {
+            let map = half_occluded().with_sampler(Sampler::default().with_filter(filter)).with_kernel(kernel);
+            // the column of texels in front of the occluder and behind it
+            assert_eq!(map.sample_compare(Vector2::new(0.125, 0.5), 0.5), 0.0, "{:?} {:?}", filter, kernel);
+            assert_eq!(map.sample_compare(Vector2::new(0.875, 0.5), 0.5), 1.0, "{:?} {:?}", filter, kernel);
+            // in front of the occluder everywhere
+            assert_eq!(map.sample_compare(Vector2::new(0.125, 0.5), 0.1), 1.0, "{:?} {:?}", filter, kernel);
+        }
+    }
+}
+
+#[test]
+fn pcf_blends_the_comparisons_over_an_edge() {
+    let map = half_occluded();
+    // right on the edge the 2x2 footprint has two texels on either side
+    assert_eq!(map.sample_compare(Vector2::new(0.5, 0.5), 0.5), 0.5);
+    // a quarter texel towards the lit side
+    assert_eq!(map.sample_compare(Vector2::new(0.5625, 0.5), 0.5), 0.75);
+
+    // taps one texel to the left and right of the edge are fully shadowed and fully lit
+    let grid = map.clone().with_kernel(Kernel::Grid { radius: 1 });
+    assert_eq!(grid.sample_compare(Vector2::new(0.5, 0.5), 0.5), 0.5);
+    let nearest = grid.with_sampler(Sampler::default().with_filter(Filter::Nearest));
+    assert_eq!(nearest.sample_compare(Vector2::new(0.5, 0.5), 0.5), 2.0 / 3.0);
+
+    let poisson = map.with_kernel(Kernel::Poisson { samples: 16, radius: 1.5 });
+    let lit = poisson.sample_compare(Vector2::new(0.5, 0.5), 0.5);
+    assert!(0.0 < lit && lit < 1.0, "{}", lit);
+}
+
+#[test]
+fn compare_functions_and_the_border() {
+    let map = half_occluded().with_sampler(Sampler::default().with_filter(Filter::Nearest));
+    let behind = Vector2::new(0.125, 0.5);
+    assert_eq!(map.clone().with_compare(Compare::Greater).sample_compare(behind, 0.5), 1.0);
+    // on the far plane, which unlike 0.2 is stored exactly
+    let far = Vector2::new(0.875, 0.5);
+    assert_eq!(map.clone().with_compare(Compare::Less).sample_compare(far, 1.0), 0.0);
+    assert_eq!(map.clone().with_compare(Compare::LessEqual).sample_compare(far, 1.0), 1.0);
+    assert_eq!(map.clone().with_compare(Compare::GreaterEqual).sample_compare(far, 1.0), 1.0);
+    assert_eq!(map.clone().with_compare(Compare::Never).sample_compare(behind, 0.0), 0.0);
+    assert_eq!(map.clone().with_compare(Compare::Always).sample_compare(behind, 2.0), 1.0);
+
+    // outside of the map the border depth decides
+    let border = Sampler::default()
+        .with_filter(Filter::Nearest)
+        .with_wrap(Wrap::ClampToBorder, Wrap::ClampToBorder)
+        .with_border_color(Vector3::splat(1.0));
+    let map = map.with_sampler(border);
+    assert_eq!(map.sample_compare(Vector2::new(-0.5, 0.5), 0.5), 1.0);
+    assert_eq!(map.sample_compare(Vector2::new(-0.5, 0.5), 1.5), 0.0);
+}
+
+#[test]
+fn bias_removes_acne_without_losing_shadows() {
+    // a sloped surface seen from the light, stored with 16 bits, so that about half of its
+    // own depths round to slightly in front of it
+    let width = 64;
+    let surface = |x: usize| 0.3 + x as Scalar * 0.0037;
+    let depths = (0..width).map(|x| Vector4::new(surface(x), 0.0, 0.0, 1.0));
+    let buffer = TexelBuffer::from_values(TexelFormat::Depth16, depths);
+    let map = DepthTexture::from_buffer(width, 1, buffer)
+        .with_sampler(Sampler::default().with_filter(Filter::Nearest))
+        .with_compare(Compare::LessEqual);
+    let lit = |map: &DepthTexture, reference: &dyn Fn(usize) -> Scalar| {
+        (0..width).filter(|&x| map.sample_compare(Vector2::new((x as Scalar + 0.5) / width as Scalar, 0.5), reference(x)) == 1.0).count()
+    };
+
+    let acne = width - lit(&map, &surface);
+    assert!(acne > width / 4, "only {} texels shadow themselves", acne);
+
+    // one step of the format is enough
+    let biased = map.with_bias(1.0 / 65535.0);
+    assert_eq!(lit(&biased, &surface), width);
+    // points well behind the surface stay in its shadow
+    assert_eq!(lit(&biased, &|x| surface(x) + 0.01), 0);
+}
@@ -0,0 +1,114 @@
+use cpu_renderer::framebuffer::Framebuffer;
+use cpu_renderer::matrix::Matrix4;
+use cpu_renderer::renderer::Program;
+use cpu_renderer::scalar::{consts, Scalar};
+use cpu_renderer::simd::{self, Level};
+use cpu_renderer::vector::{Vector3, Vector4};
+
+mod common;
+
+use common::Rng;
+
+const WIDTH: usize = 350;
+const HEIGHT: usize = 200;
+
+fn supported_levels() -> Vec<Level> {
+    [Level::Sse2, Level::Avx2].iter().cloned().filter(|&level| level.supported() == level).collect()
+}
+
+#[test]
+fn matrix_products_match_the_scalar_path() {
+    let mut rng = Rng(1);
+    for _ in 0..1000 {
+        let (a, b, v) = (rng.matrix(), rng.matrix(), rng.vector4());
+        let product = simd::mul_matrix4(Level::Scalar, &a, &b);
+        let transformed = simd::mul_matrix4_vector4(Level::Scalar, &a, &v);
+        for &level in supported_levels().iter() {
+            assert_eq!(simd::mul_matrix4(level, &a, &b), product, "{:?}", level);
+            assert_eq!(simd::mul_matrix4_vector4(level, &a, &v), transformed, "{:?}", level);
+        }
+    }
+}
+
+#[test]
+fn vector_slices_match_the_scalar_path() {
+    let mut rng = Rng(2);
+    // odd lengths leave components to the scalar tail of every level
+    for &len in [0, 1, 3, 17].iter() {
+        let a: Vec<Vector4> = (0..len).map(|_| rng.vector4()).collect();
+        let b: Vec<Vector4> = (0..len).map(|_| rng.vector4()).collect();
+        let (m, factor) = (rng.matrix(), rng.next());
+        let mut out = vec![Vector4::zero(); len];
+
+        simd::mul_matrix4_vector4s(Level::Scalar, &m, &a, &mut out);
+        assert!(out.iter().zip(a.iter()).all(|(&out, &a)| out == m * a));
+        let transformed = out.clone();
+        simd::add_vector4s(Level::Scalar, &a, &b, &mut out);
+        assert!(out.iter().zip(a.iter().zip(b.iter())).all(|(&out, (&a, &b))| out == a + b));
+        let sums = out.clone();
+        simd::sub_vector4s(Level::Scalar, &a, &b, &mut out);
+        assert!(out.iter().zip(a.iter().zip(b.iter())).all(|(&out, (&a, &b))| out == a - b));
+        let differences = out.clone();
+        simd::scale_vector4s(Level::Scalar, &a, factor, &mut out);
+        assert!(out.iter().zip(a.iter()).all(|(&out, &a)| out == a * factor));
+        let scaled = out.clone();
+
+        for &level in supported_levels().iter() {
+            simd::mul_matrix4_vector4s(level, &m, &a, &mut out);
+            assert_eq!(out, transformed, "{:?}", level);
+            simd::add_vector4s(level, &a, &b, &mut out);
+            assert_eq!(out, sums, "{:?}", level);
+            simd::sub_vector4s(level, &a, &b, &mut out);
+            assert_eq!(out, differences, "{:?}", level);
+            simd::scale_vector4s(level, &a, factor, &mut out);
+            assert_eq!(out, scaled, "{:?}", level);
+        }
+    }
+}
+
+fn model(t: Scalar) -> Matrix4 {
+    Matrix4::translation(Vector3::new(0.0, 0.0, 4.0)) * Matrix4::rotation_x(consts::PI / 5.0) * Matrix4::rotation_y(t)
+}
+
+fn vertex(v: Vector3, t: &(Scalar, Vector3)) -> (Vector3, Scalar) {
+    let projection = Matrix4::perspective(consts::PI / 3.0, WIDTH as Scalar / HEIGHT as Scalar, 0.1, 100.0);
+    ((Matrix4::viewport() * projection * model(t.0)).transform_point(v), v.y)
+}
+
+fn fragment(point: Vector3, height: Scalar, (t, n): &(Scalar, Vector3)) -> Vector3 {
+    let normal = model(*t).transform_direction(*n);
+    let light = 0.3 + 0.7 * normal.dot(Vector3::new(0.0, 0.0, -1.0)).max(0.0);
+    // the low bits of the interpolated height, so that colors differ with the weights
+    let bits = (height * (2.0 as Scalar).powi(Scalar::MANTISSA_DIGITS as i32 - 8)).fract().abs();
+    Vector3::new(light, bits, point.z.fract())
+}
+
+/// Colors and depths of a roller rendered at `level`, triangles overlap in depth so the
+/// result depends on every depth test.
+fn render(level: Level, depth_prepass: bool) -> (Vec<u32>, Vec<Scalar>) {
+    let mut buffer = Framebuffer::new(WIDTH, HEIGHT);
+    let mut program = Program::new(vertex, fragment, (0.7, Vector3::zero()), WIDTH, HEIGHT, 30, 30);
+    program.set_simd(level);
+    program.set_depth_prepass(depth_prepass);
+
+    common::enqueue_roller(&mut program, 64);
+
+    common::render(&mut program, &mut buffer, 30);
+
+    let depths = (0..HEIGHT).flat_map(|y| (0..WIDTH).map(move |x| (x, y))).map(|(x, y)| buffer.depth(x, y)).collect();
+    (buffer.colors().to_vec(), depths)
+}
+
+#[test]
+fn rasterization_matches_the_scalar_path() {
+    for &depth_prepass in [false, true].iter() {
+        let (colors, depths) = render(Level::Scalar, depth_prepass);
+        assert!(colors.iter().filter(|&&c| c != 0).count() > WIDTH * HEIGHT / 10, "scene should cover the screen");
+        for &level in supported_levels().iter() {
+            let (simd_colors, simd_depths) = render(level, depth_prepass);
+            assert!(simd_colors == colors, "colors differ at {:?}", level);
+            // compared as bits, the cleared depth is infinite
+            assert!(simd_depths.iter().zip(depths.iter()).all(|(a, b)| a.to_bits() == b.to_bits()), "depths differ at {:?}", level);
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use cpu_renderer::scalar::Scalar;
+use cpu_renderer::texture::{Filter, ImageTexture, Mipmap, MipmapFilter, Sampler, Texture, Texture3D, TextureArray, Wrap};
+use cpu_renderer::vector::{Vector2, Vector3};
+
+mod common;
+
+use common::{assert_close, EPSILON, LOOSE};
+
+/// Three layers of two texels, black and red, with the layer index in blue.
+fn array() -> TextureArray {
+    let layer = |i: Scalar| ImageTexture::new(2, 1, vec![Vector3::new(0.0, 0.0, i), Vector3::new(1.0, 0.0, i)]);
+    TextureArray::from_layers(vec![layer(0.0), layer(1.0), layer(2.0)])
+}
+
+#[test]
+fn texture_arrays_filter_within_a_layer() {
+    let nearest = array();
+    assert_eq!(nearest.sample(1.0, Vector2::new(0.25, 0.5)), Vector3::new(0.0, 0.0, 1.0));
+    assert_eq!(nearest.sample(1.0, Vector2::new(0.75, 0.5)), Vector3::new(1.0, 0.0, 1.0));
+
+    let bilinear = array().with_sampler(Sampler::default().with_filter(Filter::Bilinear));
+    assert_close(bilinear.sample(2.0, Vector2::new(0.5, 0.5)), Vector3::new(0.5, 0.0, 2.0), EPSILON);
+    assert_close(bilinear.sample(2.0, Vector2::new(0.375, 0.5)), Vector3::new(0.25, 0.0, 2.0), EPSILON);
+    // the sampler of the array wraps, not those of the layers
+    let repeat = array().with_sampler(Sampler::default().with_filter(Filter::Bilinear).with_wrap(Wrap::Repeat, Wrap::Repeat));
+    assert_close(repeat.sample(0.0, Vector2::new(0.0, 0.5)), Vector3::new(0.5, 0.0, 0.0), EPSILON);
+
+    let mipmapped = array()
+        .with_sampler(Sampler::default().with_mipmap(Mipmap::Nearest))
+        .generate_mipmaps(MipmapFilter::Box);
+    assert_close(mipmapped.sample_lod(1.0, Vector2::new(0.25, 0.5), 1.0), Vector3::new(0.5, 0.0, 1.0), EPSILON);
+}
+
+#[test]
+fn texture_array_layers_are_rounded_and_clamped() {
+    let array = array();
+    let uv = Vector2::new(0.25, 0.5);
+    let layer = |l: Scalar| array.sample(l, uv).z;
+    assert_eq!(layer(0.4), 0.0);
+    assert_eq!(layer(0.6), 1.0);
+    // layers are never blended
+    assert_eq!(layer(1.5), 2.0);
+    assert_eq!(layer(-3.0), 0.0);
+    assert_eq!(layer(10.0), 2.0);
+}
+
+/// 2x2x2 volume whose texels hold their own coordinates.
+fn volume() -> Texture3D {
+    let mut texels = Vec::new();
+    for z in 0..2 {
+        for y in 0..2 {
+            for x in 0..2 {
+                texels.push(Vector3::new(x as Scalar, y as Scalar, z as Scalar));
+            }
+        }
+    }
+    Texture3D::new(2, 2, 2, texels)
+}
+
+#[test]
+fn volumes_filter_trilinearly() {
+    let nearest = volume();
+    assert_eq!(nearest.texel(1, 0, 1), Vector3::new(1.0, 0.0, 1.0));
+    assert_eq!(nearest.sample(Vector3::new(0.25, 0.75, 0.25)), Vector3::new(0.0, 1.0, 0.0));
+
+    let trilinear = volume().with_sampler(Sampler::default().with_filter(Filter::Bilinear));
+    assert_close(trilinear.sample(Vector3::splat(0.5)), Vector3::splat(0.5), EPSILON);
+    assert_close(trilinear.sample(Vector3::new(0.5, 0.25, 0.75)), Vector3::new(0.5, 0.0, 1.0), EPSILON);
+    assert_close(trilinear.sample(Vector3::new(0.625, 0.375, 0.5)), Vector3::new(0.75, 0.25, 0.5), EPSILON);
+    // clamped to the edge by default
+    assert_close(trilinear.sample(Vector3::new(1.5, -0.5, 0.25)), Vector3::new(1.0, 0.0, 0.0), EPSILON);
+}
+
+#[test]
+fn volumes_wrap_along_every_axis() {
+    let border = Vector3::splat(9.0);
+    let volume = volume().with_sampler(
+        Sampler::default()
+            .with_wrap(Wrap::ClampToBorder, Wrap::ClampToEdge)
+            .with_wrap_w(Wrap::Repeat)
+            .with_border_color(border),
+    );
+    assert_eq!(volume.fetch(-1, 0, 0), border);
+    assert_eq!(volume.fetch(1, 5, 0), Vector3::new(1.0, 1.0, 0.0));
+    assert_eq!(volume.fetch(0, 0, 3), Vector3::new(0.0, 0.0, 1.0));
+    assert_eq!(volume.fetch(0, 0, -2), Vector3::new(0.0, 0.0, 0.0));
+
+    // repeating depth blends the last slice into the first one
+    let filtered = volume.clone().with_sampler(Sampler { filter: Filter::Bilinear, ..volume.sampler });
+    assert_close(filtered.sample(Vector3::new(0.25, 0.25, 0.0)), Vector3::new(0.0, 0.0, 0.5), EPSILON);
+}
+
+#[test]
+fn wrap_modes_map_indices_into_the_texture() {
+    let apply = |wrap: Wrap, indices: &[i64]| indices.iter().map(|&i| wrap.apply(i, 4)).collect::<Vec<_>>();
+    let indices = [-5, -4, -1, 0, 3, 4, 5, 9];
+    let some = |indices: &[usize]| indices.iter().map(|&i| Some(i)).collect::<Vec<_>>();
+    assert_eq!(apply(Wrap::Repeat, &indices), some(&[3, 0, 3, 0, 3, 0, 1, 1]));
+    assert_eq!(apply(Wrap::MirroredRepeat, &indices), some(&[3, 3, 0, 0, 3, 3, 2, 1]));
+    assert_eq!(apply(Wrap::ClampToEdge, &indices), some(&[0, 0, 0, 0, 3, 3, 3, 3]));
+    assert_eq!(apply(Wrap::ClampToBorder, &indices), vec![None, None, None, Some(0), Some(3), None, None, None]);
+}
+
+/// 3x2 texture whose texels hold their own coordinates.
+fn coordinates() -> ImageTexture {
+    let texels = (0..6).map(|i| Vector3::new((i % 3) as Scalar, (i / 3) as Scalar, 0.0)).collect();
+    ImageTexture::new(3, 2, texels)
+}
+
+#[test]
+fn bilinear_filtering_is_exact_at_texel_centers() {
+    let bilinear = coordinates().with_sampler(Sampler::default().with_filter(Filter::Bilinear));
+    for y in 0..2 {
+        for x in 0..3 {
+            let uv = Vector2::new((x as Scalar + 0.5) / 3.0, (y as Scalar + 0.5) / 2.0);
+            assert_close(bilinear.sample(uv), Vector3::new(x as Scalar, y as Scalar, 0.0), EPSILON);
+        }
+    }
+    // halfway between centers
+    assert_close(bilinear.sample(Vector2::new(1.0 / 3.0, 0.5)), Vector3::new(0.5, 0.5, 0.0), EPSILON);
+}
+
+#[test]
+fn textures_wrap_out_of_range_coordinates() {
+    let sampler = Sampler::default().with_filter(Filter::Bilinear);
+    let repeat = coordinates().with_sampler(sampler.with_wrap(Wrap::Repeat, Wrap::Repeat));
+    assert_eq!(repeat.fetch(-1, 2), Vector3::new(2.0, 0.0, 0.0));
+    // the left edge blends the first column with the last one
+    assert_close(repeat.sample(Vector2::new(0.0, 0.25)), Vector3::new(1.0, 0.0, 0.0), EPSILON);
+    assert_close(repeat.sample(Vector2::new(-5.0 / 6.0, 1.25)), Vector3::new(0.0, 0.0, 0.0), EPSILON);
+
+    let mirrored = coordinates().with_sampler(sampler.with_wrap(Wrap::MirroredRepeat, Wrap::MirroredRepeat));
+    assert_eq!(mirrored.fetch(-1, -1), Vector3::new(0.0, 0.0, 0.0));
+    assert_eq!(mirrored.fetch(4, 3), Vector3::new(1.0, 0.0, 0.0));
+    assert_close(mirrored.sample(Vector2::new(7.0 / 6.0, 0.25)), Vector3::new(2.0, 0.0, 0.0), EPSILON);
+
+    let clamped = coordinates().with_sampler(sampler);
+    assert_eq!(clamped.fetch(-7, 9), Vector3::new(0.0, 1.0, 0.0));
+    assert_close(clamped.sample(Vector2::new(2.0, -1.0)), Vector3::new(2.0, 0.0, 0.0), EPSILON);
+}
+
+#[test]
+fn clamping_to_the_border_returns_the_border_color() {
+    let border = Vector3::new(9.0, 9.0, 9.0);
+    let sampler = Sampler::default().with_wrap(Wrap::ClampToBorder, Wrap::ClampToBorder).with_border_color(border);
+    let nearest = coordinates().with_sampler(sampler);
+    assert_eq!(nearest.fetch(-1, 0), border);
+    assert_eq!(nearest.fetch(0, 2), border);
+    assert_eq!(nearest.fetch(2, 1), Vector3::new(2.0, 1.0, 0.0));
+    assert_eq!(nearest.sample(Vector2::new(1.01, 0.5)), border);
+    assert_eq!(nearest.sample(Vector2::new(0.5, -0.01)), border);
+
+    // half of the footprint at the edge lies on the border
+    let bilinear = coordinates().with_sampler(sampler.with_filter(Filter::Bilinear));
+    assert_close(bilinear.sample(Vector2::new(0.0, 0.25)), Vector3::new(4.5, 4.5, 4.5), EPSILON);
+}
+
+#[test]
+#[should_panic(expected = "textures need at least one texel")]
+fn empty_textures_are_rejected() {
+    ImageTexture::new(0, 0, Vec::new());
+}
+
+#[test]
+#[should_panic(expected = "textures need at least one texel")]
+fn empty_volumes_are_rejected() {
+    Texture3D::new(4, 4, 0, Vec::new());
+}
+
+#[test]
+fn box_mipmaps_average_odd_sizes() {
+    // red holds the column, green the row
+    let texels = (0..15).map(|i| Vector3::new((i % 5) as Scalar, (i / 5) as Scalar, 0.0)).collect();
+    let texture = ImageTexture::new(5, 3, texels).generate_mipmaps(MipmapFilter::Box);
+    assert_eq!(texture.level_count(), 3);
+    assert_eq!(texture.level_size(1), (2, 1));
+    assert_eq!(texture.level_size(2), (1, 1));
+
+    // each texel of level 1 covers two and a half columns, all three rows
+    assert_close(texture.texel_level(1, 0, 0), Vector3::new(0.8, 1.0, 0.0), LOOSE);
+    assert_close(texture.texel_level(1, 1, 0), Vector3::new(3.2, 1.0, 0.0), LOOSE);
+    assert_close(texture.texel_level(2, 0, 0), Vector3::new(2.0, 1.0, 0.0), LOOSE);
+}
+
+#[test]
+fn kaiser_mipmaps_keep_constant_textures_constant() {
+    // the weights of every texel sum to one, also where the kernel is cut at the edges
+    let color = Vector3::new(0.25, 0.5, 0.75);
+    let texture = ImageTexture::new(7, 5, vec![color; 35]).generate_mipmaps(MipmapFilter::Kaiser { alpha: 4.0 });
+    for level in 0..texture.level_count() {
+        let (width, height) = texture.level_size(level);
+        for y in 0..height {
+            for x in 0..width {
+                assert_close(texture.texel_level(level, x, y), color, LOOSE);
+            }
+        }
+    }
+}
+
+/// 2x2 texture with a single white texel in the top left corner.
+fn corner() -> ImageTexture {
+    let mut texels = vec![Vector3::zero(); 4];
+    texels[0] = Vector3::splat(1.0);
+    ImageTexture::new(2, 2, texels).generate_mipmaps(MipmapFilter::Box)
+}
+
+#[test]
+fn linear_mipmapping_blends_two_levels() {
+    let uv = Vector2::new(0.25, 0.25);
+    let linear = corner().with_sampler(Sampler::default().with_mipmap(Mipmap::Linear));
+    assert_close(linear.sample_lod(uv, 0.0), Vector3::splat(1.0), EPSILON);
+    assert_close(linear.sample_lod(uv, 1.0), Vector3::splat(0.25), EPSILON);
+    assert_close(linear.sample_lod(uv, 0.25), Vector3::splat(0.75 + 0.25 * 0.25), EPSILON);
+    assert_close(linear.sample_lod(uv, 0.5), Vector3::splat(0.625), EPSILON);
+    // past the last level
+    assert_close(linear.sample_lod(uv, 3.0), Vector3::splat(0.25), EPSILON);
+
+    let nearest = corner().with_sampler(Sampler::default().with_mipmap(Mipmap::Nearest));
+    assert_eq!(nearest.sample_lod(uv, 0.25), Vector3::splat(1.0));
+    assert_eq!(nearest.sample_lod(uv, 0.75), Vector3::splat(0.25));
+}
+
+/// 16x16 texture of white even and black odd columns, never filtered.
+fn stripes(max_anisotropy: usize) -> ImageTexture {
+    let texels = (0..256).map(|i| Vector3::splat(if i % 2 == 0 { 1.0 } else { 0.0 })).collect();
+    let sampler = Sampler::default().with_wrap(Wrap::Repeat, Wrap::Repeat).with_max_anisotropy(max_anisotropy);
+    ImageTexture::new(16, 16, texels).with_sampler(sampler)
+}
+
+#[test]
+fn anisotropic_filtering_samples_along_the_major_axis() {
+    // the footprint is 8 texels wide and 1 texel high, centered on the first column
+    let uv = Vector2::new(0.5 / 16.0, 0.5);
+    let (wide, narrow) = (Vector2::new(0.5, 0.0), Vector2::new(0.0, 1.0 / 16.0));
+    let sample = |max_anisotropy: usize, ddx: Vector2, ddy: Vector2| stripes(max_anisotropy).sample_grad(uv, ddx, ddy).x;
+
+    // a single sample in the center
+    assert_eq!(sample(1, wide, narrow), 1.0);
+    // 4 samples 2 texels apart all hit black columns
+    assert_eq!(sample(4, wide, narrow), 0.0);
+    // 8 samples 1 texel apart cover the footprint
+    assert_eq!(sample(8, wide, narrow), 0.5);
+    // no more samples than the ratio of the axes
+    assert_eq!(sample(16, wide, narrow), 0.5);
+    // samples of a footprint 8 texels high stay in the first column
+    assert_eq!(sample(8, narrow, Vector2::new(0.0, 0.5)), 1.0);
+}
@@ -0,0 +1,143 @@
+use cpu_renderer::scalar::{consts, Scalar};
+use cpu_renderer::vector::{Vector2, Vector3, Vector4};
+
+mod common;
+
+use common::{assert_close, Rng, EPSILON};
+
+const CASES: usize = 1000;
+
+#[test]
+fn reflection_mirrors_at_the_normal() {
+    let normal = Vector3::new(0.0, 1.0, 0.0);
+    assert_eq!(Vector3::new(1.0, -1.0, 0.5).reflect(normal), Vector3::new(1.0, 1.0, 0.5));
+    assert_eq!(Vector2::new(-2.0, 3.0).reflect(Vector2::new(1.0, 0.0)), Vector2::new(2.0, 3.0));
+
+    let mut rng = Rng(1);
+    for _ in 0..CASES {
+        let (d, n) = (rng.vector3(), rng.vector3().normalize());
+        let r = d.reflect(n);
+        // same length and angle to the normal, only the normal part flips
+        assert_close(r.length(), d.length(), EPSILON);
+        assert_close(r.dot(n), -d.dot(n), EPSILON);
+        assert_close((r - d).cross(n), Vector3::zero(), EPSILON);
+        assert_close(r.reflect(n), d, EPSILON);
+    }
+}
+
+#[test]
+fn refraction_follows_snells_law() {
+    let normal = Vector3::new(0.0, 1.0, 0.0);
+    let incoming = |angle: Scalar| Vector3::new(angle.sin(), -angle.cos(), 0.0);
+    // straight through at normal incidence and with equal indices
+    assert_close(incoming(0.0).refract(normal, 1.5).unwrap(), incoming(0.0), EPSILON);
+    assert_close(incoming(0.7).refract(normal, 1.0).unwrap(), incoming(0.7), EPSILON);
+
+    for i in 0..90 {
+        let angle = i as Scalar / 90.0 * consts::FRAC_PI_2;
+        for &eta in [1.0 / 1.5, 1.5, 1.0 / 1.33].iter() {
+            let sin_out = eta * angle.sin();
+            match incoming(angle).refract(normal, eta) {
+                Some(t) => {
+                    // sin of the refracted angle is eta times the incoming one, going on
+                    // into the surface as a unit vector
+                    assert!(sin_out <= 1.0 + EPSILON, "refracted past the critical angle");
+                    assert_close(t.x, sin_out, EPSILON);
+                    assert_close(t.length(), 1.0, EPSILON);
+                    assert!(t.y < 0.0 && t.z == 0.0);
+                }
+                // total internal reflection only past the critical angle, which leaving
+                // a denser medium (eta > 1) has
+                None => assert!(sin_out > 1.0 - EPSILON, "no refraction at {} with eta {}", angle, eta),
+            }
+        }
+    }
+    // glass to air beyond asin(1 / 1.5), about 0.73
+    assert!(incoming(0.8).refract(normal, 1.5).is_none());
+    assert!(incoming(0.7).refract(normal, 1.5).is_some());
+    assert!(Vector2::new(0.8, -0.6).refract(Vector2::new(0.0, 1.0), 1.5).is_none());
+    assert_close(Vector2::new(0.6, -0.8).refract(Vector2::new(0.0, 1.0), 1.0).unwrap(), Vector2::new(0.6, -0.8), EPSILON);
+}
+
+#[test]
+fn component_wise_operations() {
+    let a = Vector4::new(1.0, -2.0, 3.0, 4.0);
+    let b = Vector4::new(2.0, 4.0, -0.5, 8.0);
+    assert_eq!(a.component_mul(b), Vector4::new(2.0, -8.0, -1.5, 32.0));
+    assert_eq!(a.component_div(b), Vector4::new(0.5, -0.5, -6.0, 0.5));
+    assert_eq!(a.component_mul(b).component_div(b), a);
+    assert_eq!(Vector3::new(1.0, 2.0, 3.0).component_mul(Vector3::new(0.0, -1.0, 2.0)), Vector3::new(0.0, -2.0, 6.0));
+    assert_eq!(Vector2::new(3.0, 1.0).component_div(Vector2::new(2.0, 4.0)), Vector2::new(1.5, 0.25));
+
+    assert_eq!(a.min(b), Vector4::new(1.0, -2.0, -0.5, 4.0));
+    assert_eq!(a.max(b), Vector4::new(2.0, 4.0, 3.0, 8.0));
+    assert_eq!(a.abs(), Vector4::new(1.0, 2.0, 3.0, 4.0));
+}
+
+#[test]
+fn lerp_and_clamp() {
+    let (a, b) = (Vector3::new(1.0, 2.0, 3.0), Vector3::new(3.0, -2.0, 3.0));
+    assert_eq!(a.lerp(b, 0.0), a);
+    assert_eq!(a.lerp(b, 1.0), b);
+    assert_eq!(a.lerp(b, 0.25), Vector3::new(1.5, 1.0, 3.0));
+    // extrapolates outside of [0, 1]
+    assert_eq!(a.lerp(b, -1.0), Vector3::new(-1.0, 6.0, 3.0));
+    assert_eq!(Vector4::zero().lerp(Vector4::splat(2.0), 0.5), Vector4::splat(1.0));
+    assert_eq!(Vector2::new(0.0, 4.0).lerp(Vector2::new(2.0, 0.0), 0.75), Vector2::new(1.5, 1.0));
+
+    let (min, max) = (Vector3::splat(0.0), Vector3::new(1.0, 2.0, 3.0));
+    assert_eq!(Vector3::new(-1.0, 1.0, 5.0).clamp(min, max), Vector3::new(0.0, 1.0, 3.0));
+    assert_eq!(Vector4::new(-1.0, 0.5, 2.0, 1.0).clamp(Vector4::zero(), Vector4::splat(1.0)), Vector4::new(0.0, 0.5, 1.0, 1.0));
+    assert_eq!(Vector2::new(9.0, -9.0).clamp(Vector2::new(-1.0, -1.0), Vector2::new(1.0, 1.0)), Vector2::new(1.0, -1.0));
+}
+
+#[test]
+fn swizzles_pick_components_in_order() {
+    let v4 = Vector4::new(1.0, 2.0, 3.0, 4.0);
+    assert_eq!(v4.xy(), Vector2::new(1.0, 2.0));
+    assert_eq!(v4.zw(), Vector2::new(3.0, 4.0));
+    assert_eq!(v4.xyz(), Vector3::new(1.0, 2.0, 3.0));
+    assert_eq!(v4.zyx(), Vector3::new(3.0, 2.0, 1.0));
+    assert_eq!(v4.wzyx(), Vector4::new(4.0, 3.0, 2.0, 1.0));
+    assert_eq!(v4.truncate(), v4.xyz());
+
+    let v3 = Vector3::new(1.0, 2.0, 3.0);
+    assert_eq!(v3.xy(), Vector2::new(1.0, 2.0));
+    assert_eq!(v3.xz(), Vector2::new(1.0, 3.0));
+    assert_eq!(v3.yx(), Vector2::new(2.0, 1.0));
+    assert_eq!(v3.yz(), Vector2::new(2.0, 3.0));
+    assert_eq!(v3.zx(), Vector2::new(3.0, 1.0));
+    assert_eq!(v3.zy(), Vector2::new(3.0, 2.0));
+    assert_eq!(v3.xzy(), Vector3::new(1.0, 3.0, 2.0));
+    assert_eq!(v3.yxz(), Vector3::new(2.0, 1.0, 3.0));
+    assert_eq!(v3.yzx(), Vector3::new(2.0, 3.0, 1.0));
+    assert_eq!(v3.zxy(), Vector3::new(3.0, 1.0, 2.0));
+    assert_eq!(v3.zyx(), Vector3::new(3.0, 2.0, 1.0));
+    assert_eq!(v3.truncate(), v3.xy());
+    assert_eq!(v3.extend(4.0), v4);
+
+    let v2 = Vector2::new(1.0, 2.0);
+    assert_eq!(v2.yx(), Vector2::new(2.0, 1.0));
+    assert_eq!(v2.extend(3.0), v3);
+}
+
+#[test]
+fn conversions_round_trip() {
+    let v4 = Vector4::new(1.0, 2.0, 3.0, 4.0);
+    assert_eq!(Vector4::from([1.0, 2.0, 3.0, 4.0]), v4);
+    assert_eq!(Vector4::from((1.0, 2.0, 3.0, 4.0)), v4);
+    assert_eq!(<[Scalar; 4]>::from(v4), [1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(<(Scalar, Scalar, Scalar, Scalar)>::from(v4), (1.0, 2.0, 3.0, 4.0));
+
+    let v3 = Vector3::new(1.0, 2.0, 3.0);
+    assert_eq!(Vector3::from([1.0, 2.0, 3.0]), v3);
+    assert_eq!(Vector3::from((1.0, 2.0, 3.0)), v3);
+    assert_eq!(<[Scalar; 3]>::from(v3), [1.0, 2.0, 3.0]);
+    assert_eq!(<(Scalar, Scalar, Scalar)>::from(v3), (1.0, 2.0, 3.0));
+
+    let v2 = Vector2::new(1.0, 2.0);
+    assert_eq!(Vector2::from([1.0, 2.0]), v2);
+    assert_eq!(Vector2::from((1.0, 2.0)), v2);
+    assert_eq!(<[Scalar; 2]>::from(v2), [1.0, 2.0]);
+    assert_eq!(<(Scalar, Scalar)>::from(v2), (1.0, 2.0));
+}